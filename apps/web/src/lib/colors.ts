@@ -10,9 +10,61 @@ export const Color = {
 	Cyan: 8
 } as const;
 
-export type ColorId = (typeof Color)[keyof typeof Color];
+/** 数字格 0~8 的编码起点：`NUMBER_BASE + n` 表示“八邻恰好 n 格勾选”。 */
+export const NUMBER_BASE = 9;
+export const MAX_NUMBER = 8;
+
+export type NumberColorId = 9 | 10 | 11 | 12 | 13 | 14 | 15 | 16 | 17;
+
+/** 变体格的编码起点：`VARIANT_BASE + 底色序号 * VARIANT_HOODS.length + 邻域序号`（与引擎一致）。 */
+export const VARIANT_BASE = 18;
+
+/** 计数类线索可换用的邻域（八邻即原色本身，不另占编码）。 */
+export const VARIANT_HOODS = ['orthogonal', 'knight', 'line2', 'diagonal'] as const;
+export type Neighborhood = 'king' | (typeof VARIANT_HOODS)[number];
+
+/** 可以换邻域的计数颜色，顺序即底色序号。 */
+export const VARIANT_BASES = [Color.Red, Color.Blue, Color.Purple, Color.Orange] as const;
+type VariantBase = (typeof VARIANT_BASES)[number];
+
+export type VariantColorId =
+	| 18 | 19 | 20 | 21 | 22 | 23 | 24 | 25
+	| 26 | 27 | 28 | 29 | 30 | 31 | 32 | 33;
+
+export type ColorId = (typeof Color)[keyof typeof Color] | NumberColorId | VariantColorId;
+
+/** 数字 n（0~8）对应的颜色编码。 */
+export function numberColor(n: number): ColorId {
+	return (NUMBER_BASE + n) as NumberColorId;
+}
+
+/** 若为数字格则返回其数字，否则返回 null。 */
+export function numberOf(color: ColorId): number | null {
+	return color >= NUMBER_BASE && color <= NUMBER_BASE + MAX_NUMBER ? color - NUMBER_BASE : null;
+}
+
+/** `base` 在邻域 `hood` 上的颜色编码。 */
+export function variantColor(base: VariantBase, hood: (typeof VARIANT_HOODS)[number]): ColorId {
+	return (VARIANT_BASE +
+		VARIANT_BASES.indexOf(base) * VARIANT_HOODS.length +
+		VARIANT_HOODS.indexOf(hood)) as VariantColorId;
+}
+
+/** 拆出颜色的底色与邻域：八邻的红/蓝/紫/橙与其它颜色原样返回，邻域为 `king`。 */
+export function variantOf(color: ColorId): { base: ColorId; hood: Neighborhood } {
+	const k = color - VARIANT_BASE;
+	if (k < 0 || k >= VARIANT_BASES.length * VARIANT_HOODS.length) {
+		return { base: color, hood: 'king' };
+	}
+	return {
+		base: VARIANT_BASES[Math.floor(k / VARIANT_HOODS.length)],
+		hood: VARIANT_HOODS[k % VARIANT_HOODS.length]
+	};
+}
 
 export function colorToCss(color: ColorId): string {
+	if (numberOf(color) !== null) return 'var(--c-number)';
+	color = variantOf(color).base;
 	switch (color) {
 		case Color.Red:
 			return 'var(--c-red)';
@@ -1,4 +1,6 @@
 export const LEVEL_VERSION: 1;
+export const LEVEL_VERSION_WIDE: 2;
+export const MAX_COLOR_CODE: 33;
 export const GRID_SIZE: 5;
 export const CELL_COUNT: 25;
 
@@ -6,11 +6,16 @@
 // - Node 与浏览器均可运行（Node 测试用 Buffer，浏览器用 btoa/atob）。
 
 export const LEVEL_VERSION = 1;
+/** 宽格式：每格一个字节，用于容纳超出 nibble 的颜色编码（数字格 7/8 为 16/17，变体格为 18~33）。 */
+export const LEVEL_VERSION_WIDE = 2;
+/** 当前最大的颜色编码（斜向邻域的橙格）。 */
+export const MAX_COLOR_CODE = 33;
 export const GRID_SIZE = 5;
 export const CELL_COUNT = GRID_SIZE * GRID_SIZE;
 
 /**
- * 将 0..15 的值打包为 nibble（低 4bit / 高 4bit），并在首字节写入版本号。
+ * 将颜色编码打包，并在首字节写入版本号：
+ * 全部不超过 15 时按 nibble（低 4bit / 高 4bit）打包为 v1，否则每格一字节写为 v2。
  * @param {number[]} grid
  * @returns {Uint8Array}
  */
@@ -22,15 +27,25 @@ function packLevelBytes(grid) {
 	if (cellCount !== CELL_COUNT) {
 		throw new Error(`grid 长度必须为 ${CELL_COUNT}（当前：${cellCount}）`);
 	}
+	for (let i = 0; i < cellCount; i++) {
+		const v = grid[i];
+		if (!Number.isInteger(v) || v < 0 || v > MAX_COLOR_CODE) {
+			throw new Error(`grid[${i}] 非法：${String(v)}（要求 0..${MAX_COLOR_CODE} 整数）`);
+		}
+	}
+
+	if (grid.some((v) => v > 15)) {
+		const bytes = new Uint8Array(1 + cellCount);
+		bytes[0] = LEVEL_VERSION_WIDE;
+		bytes.set(grid, 1);
+		return bytes;
+	}
 
 	const bytes = new Uint8Array(1 + Math.ceil(cellCount / 2));
 	bytes[0] = LEVEL_VERSION;
 
 	for (let i = 0; i < cellCount; i++) {
 		const v = grid[i];
-		if (!Number.isInteger(v) || v < 0 || v > 15) {
-			throw new Error(`grid[${i}] 非法：${String(v)}（要求 0..15 整数）`);
-		}
 		const bi = 1 + (i >> 1);
 		if ((i & 1) === 0) bytes[bi] = v & 0xf;
 		else bytes[bi] |= (v & 0xf) << 4;
@@ -49,6 +64,15 @@ function unpackLevelBytes(bytes) {
 	}
 
 	const version = bytes[0];
+	if (version === LEVEL_VERSION_WIDE) {
+		if (bytes.length !== 1 + CELL_COUNT) {
+			throw new Error(`bytes 长度非法：${bytes.length}（期望：${1 + CELL_COUNT}）`);
+		}
+		const grid = Array.from(bytes.subarray(1));
+		const bad = grid.findIndex((v) => v > MAX_COLOR_CODE);
+		if (bad >= 0) throw new Error(`grid[${bad}] 非法：${grid[bad]}`);
+		return { version, grid };
+	}
 	if (version !== LEVEL_VERSION) {
 		throw new Error(`不支持的关卡版本：${version}`);
 	}
@@ -6,3 +6,4 @@ export function createMarks(length?: number): number[];
 export function normalizeMarks(raw: any, length?: number): number[];
 export function cycleMarkValue(v: number): number;
 
+export function excludedMaskFromMarks(marks: number[], checkedMask?: bigint): bigint;
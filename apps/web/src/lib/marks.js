@@ -40,3 +40,23 @@ export function cycleMarkValue(v) {
 	return MARK_NONE;
 }
 
+
+/**
+ * 将“排除”标记转为引擎使用的 unchecked_mask（bit i 表示第 i 格已确认不勾选）。
+ *
+ * 已勾选的格子不计入（勾选优先于标记）。
+ *
+ * @param {number[]} marks
+ * @param {bigint} checkedMask
+ * @returns {bigint}
+ */
+export function excludedMaskFromMarks(marks, checkedMask = 0n) {
+	let mask = 0n;
+	for (let i = 0; i < marks.length; i++) {
+		if (marks[i] !== MARK_EXCLUDE) continue;
+		const bit = 1n << BigInt(i);
+		if ((checkedMask & bit) !== 0n) continue;
+		mask |= bit;
+	}
+	return mask;
+}
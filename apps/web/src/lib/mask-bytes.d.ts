@@ -0,0 +1,2 @@
+export function maskToBytes(mask: bigint, cellCount: number): Uint8Array;
+export function bytesToMask(bytes: Uint8Array): bigint;
@@ -0,0 +1,32 @@
+// 棋盘 mask 的 WASM 边界编码
+//
+// 约定（与引擎 `Bitset::to_bytes/from_bytes` 一致）：
+// - 前端内部仍用 bigint 表示 mask（bit i 对应 row-major 第 i 格）；
+// - 传给引擎时编码为小端字节数组：byte k 的 bit j 对应格子 8k + j，长度为 ceil(cellCount / 8)。
+
+/**
+ * @param {bigint} mask
+ * @param {number} cellCount
+ * @returns {Uint8Array}
+ */
+export function maskToBytes(mask, cellCount) {
+	const out = new Uint8Array(Math.ceil(cellCount / 8));
+	let rest = BigInt(mask);
+	for (let k = 0; k < out.length; k++) {
+		out[k] = Number(rest & 0xffn);
+		rest >>= 8n;
+	}
+	return out;
+}
+
+/**
+ * @param {Uint8Array} bytes
+ * @returns {bigint}
+ */
+export function bytesToMask(bytes) {
+	let mask = 0n;
+	for (let k = bytes.length - 1; k >= 0; k--) {
+		mask = (mask << 8n) | BigInt(bytes[k]);
+	}
+	return mask;
+}
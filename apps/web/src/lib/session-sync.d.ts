@@ -0,0 +1,12 @@
+export type SessionMasks = { checked: bigint; unchecked: bigint };
+export type SessionLike = {
+	assign(cell: number, checked: boolean): void;
+	retract(cell: number): void;
+};
+
+export function syncSessionMasks(
+	session: SessionLike,
+	prev: SessionMasks,
+	next: SessionMasks,
+	cellCount: number
+): SessionMasks;
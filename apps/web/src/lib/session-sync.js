@@ -0,0 +1,41 @@
+// 把前端的勾选/排除 mask 同步到引擎的解题会话（PuzzleSession）
+//
+// 约定：
+// - 前端以 bigint mask 为准（撤销/重做、读档都可能一次改动多格）；
+// - 只对前后状态不同的格子调用 assign/retract，让会话保持增量传播；
+// - 同时出现在两个 mask 中的格子按“勾选”处理（与引擎一致）。
+
+/**
+ * @typedef {{ checked: bigint, unchecked: bigint }} SessionMasks
+ * @typedef {{ assign(cell: number, checked: boolean): void, retract(cell: number): void }} SessionLike
+ */
+
+/**
+ * @param {SessionMasks} masks
+ * @param {number} i
+ * @returns {0 | 1 | 2} 0=未知，1=勾选，2=排除
+ */
+function cellState(masks, i) {
+	const bit = 1n << BigInt(i);
+	if ((BigInt(masks.checked) & bit) !== 0n) return 1;
+	if ((BigInt(masks.unchecked) & bit) !== 0n) return 2;
+	return 0;
+}
+
+/**
+ * @param {SessionLike} session
+ * @param {SessionMasks} prev 会话当前对应的 mask
+ * @param {SessionMasks} next 目标 mask
+ * @param {number} cellCount
+ * @returns {SessionMasks} 同步后的 mask（即 next）
+ */
+export function syncSessionMasks(session, prev, next, cellCount) {
+	for (let i = 0; i < cellCount; i++) {
+		const before = cellState(prev, i);
+		const after = cellState(next, i);
+		if (before === after) continue;
+		if (after === 0) session.retract(i);
+		else session.assign(i, after === 1);
+	}
+	return { checked: BigInt(next.checked), unchecked: BigInt(next.unchecked) };
+}
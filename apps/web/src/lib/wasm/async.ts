@@ -1,4 +1,11 @@
-import type { SolutionCountResult, DifficultyReport } from './load';
+import type {
+	SolutionCountResult,
+	ExactSolutionCountResult,
+	SolutionMarginalsResult,
+	DifficultyReport,
+	SolveBudget
+} from './load';
+import { maskToBytes } from '$lib/mask-bytes.js';
 
 let worker: Worker | null = null;
 let nextId = 0;
@@ -20,6 +27,9 @@ function getWorker() {
 	return worker;
 }
 
+/** 可跨线程传递的预算（函数无法发往 worker，因此不含 `onProgress`）。 */
+export type WorkerBudget = Omit<SolveBudget, 'onProgress'>;
+
 function callWorker<T>(type: string, payload: any): Promise<T> {
 	const id = nextId++;
 	return new Promise((resolve, reject) => {
@@ -38,19 +48,60 @@ export async function getDifficultyReportAsync(colorGrid: Uint8Array): Promise<D
 
 export async function getSolutionCountAsync(
 	colorGrid: Uint8Array,
-	limit: number
+	limit: number,
+	budget?: WorkerBudget
 ): Promise<SolutionCountResult> {
-	return callWorker('solution_count', { color_grid: colorGrid, limit });
+	return callWorker('solution_count', { color_grid: colorGrid, limit, budget });
 }
 
 export async function getSolutionCountWithCheckedAsync(
 	checkedMask: bigint,
+	uncheckedMask: bigint,
 	colorGrid: Uint8Array,
-	limit: number
+	limit: number,
+	budget?: WorkerBudget
 ): Promise<SolutionCountResult> {
 	return callWorker('solution_count_with_checked', {
-		checked_mask: checkedMask,
+		checked_mask: maskToBytes(checkedMask, colorGrid.length),
+		unchecked_mask: maskToBytes(uncheckedMask, colorGrid.length),
+		color_grid: colorGrid,
+		limit,
+		budget
+	});
+}
+
+/** 逐格边际概率：解数不超过 `samples` 时精确，否则均匀抽样估计；`samples=0` 表示总是精确枚举。 */
+export async function getSolutionMarginalsAsync(
+	checkedMask: bigint,
+	uncheckedMask: bigint,
+	colorGrid: Uint8Array,
+	samples: number,
+	maxStates: number,
+	budget?: WorkerBudget
+): Promise<SolutionMarginalsResult> {
+	return callWorker('solution_marginals', {
+		checked_mask: maskToBytes(checkedMask, colorGrid.length),
+		unchecked_mask: maskToBytes(uncheckedMask, colorGrid.length),
+		color_grid: colorGrid,
+		samples,
+		max_states: maxStates,
+		budget
+	});
+}
+
+/** 精确解数（不枚举）；`maxStates=0` 表示不限制 DP 状态数。 */
+export async function getExactSolutionCountAsync(
+	checkedMask: bigint,
+	uncheckedMask: bigint,
+	colorGrid: Uint8Array,
+	maxStates: number,
+	budget?: WorkerBudget
+): Promise<ExactSolutionCountResult> {
+	return callWorker('solution_count_exact', {
+		checked_mask: maskToBytes(checkedMask, colorGrid.length),
+		unchecked_mask: maskToBytes(uncheckedMask, colorGrid.length),
 		color_grid: colorGrid,
-		limit
+		max_states: maxStates,
+		budget
 	});
 }
\ No newline at end of file
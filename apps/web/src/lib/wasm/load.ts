@@ -1,8 +1,57 @@
+/** 引擎文字的语言（BCP 47 标签）；省略时为 zh-CN，目录里没有的语言退回英文。 */
+export type Locale = 'zh-CN' | 'en' | (string & {});
+
+export type MessageParam = number | string | EngineMessage | EngineMessage[];
+
+/** 稳定的消息键与类型化参数：同一结构里的文字字段是它按 `locale` 渲染的结果，前端也可以按键自行翻译。 */
+export type EngineMessage = {
+	key: string;
+	params?: Record<string, MessageParam>;
+};
+
+/** 引擎错误的稳定错误码（与 Rust `EngineError::code` 一致）。 */
+export type EngineErrorCode =
+	| 'grid_not_square'
+	| 'invalid_color'
+	| 'size_out_of_range'
+	| 'invalid_win_condition'
+	| 'no_win_lines'
+	| 'generation_failed'
+	| 'unsolvable'
+	| 'cell_out_of_range'
+	| 'fixed_cell_excluded'
+	| 'mask_too_large'
+	| 'mask_out_of_range'
+	| 'invalid_date'
+	| 'invalid_date_field'
+	| 'date_before_epoch'
+	| 'budget_not_object'
+	| 'invalid_budget_number'
+	| 'invalid_progress_callback'
+	| 'invalid_depth'
+	| 'invalid_generate_options'
+	| 'invalid_locale'
+	| 'serialization';
+
+/**
+ * 引擎抛出的错误：`Error` 实例（`name` 为 `EngineError`），`message` 按 `locale` 渲染；
+ * `details` 为结构化字段（如 `{index, value}`、`{actual, max}`），与消息参数同名。
+ */
+export type EngineError = Error & {
+	code: EngineErrorCode;
+	details?: Record<string, unknown>;
+};
+
+export function isEngineError(e: unknown): e is EngineError {
+	return e instanceof Error && e.name === 'EngineError' && typeof (e as EngineError).code === 'string';
+}
+
 export type ValidateResult = {
 	is_bingo: boolean;
 	is_valid: boolean;
 	cell_ok: boolean[];
 	cell_messages: (string | undefined)[];
+	cell_message_keys: (EngineMessage | undefined)[];
 };
 
 export type DifficultyStats = {
@@ -18,12 +67,69 @@ export type DifficultyStats = {
 	max_depth: number;
 };
 
+/** 技巧难度档位（由易到难）。 */
+export type TechniqueTier = 'basic' | 'intermediate' | 'advanced' | 'expert';
+
+/** 推理技巧 id（目录顺序，见 `technique_catalog`）。 */
+export type TechniqueId =
+	| 'red_last_neighbour'
+	| 'blue_saturation'
+	| 'cyan_last_neighbour'
+	| 'cyan_isolated'
+	| 'number_count'
+	| 'purple_last_neighbour'
+	| 'orange_last_neighbour'
+	| 'green_squeeze'
+	| 'yellow_squeeze'
+	| 'bingo_common_cells'
+	| 'count_chain'
+	| 'parity_pair'
+	| 'parity_chain'
+	| 'single_assumption_contradiction';
+
+/** 技巧目录中的一项。 */
+export type TechniqueInfo = {
+	id: TechniqueId;
+	tier: TechniqueTier;
+	title: string;
+	titleKey: EngineMessage;
+	description: string;
+	descriptionKey: EngineMessage;
+};
+
+/** 技巧直方图的一项：该技巧推出的格子数（单步反证按次数计）。 */
+export type TechniqueCount = {
+	technique: TechniqueId;
+	tier: TechniqueTier;
+	count: number;
+};
+
 export type DifficultyReport = {
 	difficulty_score: number;
+	/** 人类逻辑阶段用到的最难技巧；一步都推不出时为 null。 */
+	hardest_technique: TechniqueId | null;
+	/** 目录顺序，略去未用到的技巧。 */
+	techniques: TechniqueCount[];
 	stats: DifficultyStats;
 };
 
-export type HintAction = 'check' | 'uncheck';
+/** 互相冲突的一条线索；`ruleId` 与 `rules.json` 对齐（黑格为 `black`）。 */
+export type ConflictingClue = {
+	cell: number;
+	ruleId: string;
+};
+
+/** 自定义题目无解时的解释：互相冲突的极小线索集合（把任意一条涂白，其余线索便不再矛盾）。 */
+export type UnsolvableReport = {
+	solvable: boolean;
+	/** `solvable=false` 而为空表示胜利条件在该尺寸下无法达成。 */
+	clues: ConflictingClue[];
+	/** 预算耗尽的原因；收缩中途耗尽时 `clues` 仍然无解但不保证极小。 */
+	exhausted?: BudgetStop;
+};
+
+/** `uncheck` 撤销已有勾选，`exclude` 把未知格标记为排除。 */
+export type HintAction = 'check' | 'uncheck' | 'exclude';
 
 export type HintMove = {
 	cell: number;
@@ -31,12 +137,13 @@ export type HintMove = {
 	forced: boolean;
 };
 
-export type HintStatus = 'no_solution' | 'forced' | 'suggested';
+export type HintStatus = 'no_solution' | 'forced' | 'suggested' | 'exhausted';
 
 export type HintReasonKind = 'propagate' | 'contradiction' | 'suggest' | 'repair';
 
 export type HintReasonStep = {
 	title: string;
+	titleKey: EngineMessage;
 	ruleId?: string;
 	cells?: number[];
 };
@@ -47,36 +154,310 @@ export type HintReason = {
 	affectedCells?: number[];
 	secondaryCells?: number[];
 	steps?: HintReasonStep[];
+	/** 推出结论所用的技巧（仅 `propagate` / `contradiction`）。 */
+	technique?: TechniqueId;
+	/** 无解时的冲突解释（仅 `repair`）。 */
+	conflict?: ConflictCore;
+};
+
+/** 冲突链上的一条规则实例。 */
+export type ConflictRule = {
+	ruleId: string;
+	title: string;
+	titleKey: EngineMessage;
+	cells: number[];
+};
+
+/** 互相冲突的极小勾选子集（去掉任意一个就不再矛盾），以及推出矛盾所经过的规则。 */
+export type ConflictCore = {
+	checks: number[];
+	/** 按推导顺序，最后一条为发现矛盾的规则；需要搜索才能发现矛盾时为空。 */
+	rules: ConflictRule[];
+};
+
+export type ConflictCoreResult = ConflictCore & {
+	/** 当前局面可解（此时两个列表都为空）。 */
+	solvable: boolean;
+	/** 预算耗尽的原因；收缩中途耗尽时 `checks` 仍无解但不保证极小。 */
+	exhausted?: BudgetStop;
 };
 
 export type HintResult = {
 	status: HintStatus;
 	message: string;
+	messageKey: EngineMessage;
 	move?: HintMove;
 	reason?: HintReason;
+	/** 预算耗尽的原因；在预算内完成时缺省。 */
+	exhausted?: BudgetStop;
+};
+
+/** 最小修复：改动最少的一组操作（撤销勾选为 `uncheck`，解除排除为 `check`），最近的操作在前。 */
+export type RepairResult = {
+	/** 当前局面可解（此时 `moves` 为空）。 */
+	solvable: boolean;
+	/** 无解但找不到修复（改动超过上限）时为空，`reason.conflict` 仍给出冲突解释。 */
+	moves: HintMove[];
+	reason?: HintReason;
+	exhausted?: BudgetStop;
+};
+
+/** 分级提示中的一层：1 区域/规则，2 规则实例与关注格，3 目标格，4 完整答案。 */
+export type HintTier = {
+	level: number;
+	message: string;
+	messageKey: EngineMessage;
+	ruleId?: string;
+	/** 第 2 层起。 */
+	technique?: TechniqueId;
+	cells?: number[];
+	/** 仅最后一层。 */
+	move?: HintMove;
+	/** 仅最后一层。 */
+	steps?: HintReasonStep[];
+};
+
+/** 分级提示：`tiers` 含全部层级（由浅到深），升级时直接取下一层即可。 */
+export type GradedHint = {
+	status: HintStatus;
+	/** 本次请求的层级（已截断到 1..=tiers.length）。 */
+	level: number;
+	message: string;
+	messageKey: EngineMessage;
+	tiers: HintTier[];
+	exhausted?: BudgetStop;
 };
 
 export type SolutionCountResult = {
 	count: number;
 	truncated: boolean;
+	/** 预算耗尽的原因；此时 `count` 只是已找到的解数（下界）。 */
+	exhausted?: BudgetStop;
+};
+
+export type ExactSolutionCountResult = {
+	/** 精确解数；DP 状态数超过上限或预算耗尽时缺省。 */
+	count?: bigint;
+	exhausted?: BudgetStop;
+};
+
+/** 逐格边际概率（热力图）：每格在全部解中被勾选的比例。 */
+export type SolutionMarginalsResult = {
+	/** row-major，取值 0..1；放弃计算时为空数组。 */
+	cells: number[];
+	/** 解的总数；DP 状态数超过上限或预算耗尽时缺省。 */
+	count?: bigint;
+	/** `cells` 是否为抽样估计（解数超过 `samples` 时）。 */
+	sampled: boolean;
+	/** 抽样估计所用的样本数；精确时为 0。 */
+	samples: number;
+	exhausted?: BudgetStop;
+};
+
+/** 骨干：在所有解中取值都相同的格子（含黑格与已确认的格子）。 */
+export type BackboneResult = {
+	/** 已证明无解（此时两个列表都为空）。 */
+	no_solution: boolean;
+	checked: number[];
+	unchecked: number[];
+	/** 预算耗尽的原因；此时只含已确认的格子（真实骨干的子集）。 */
+	exhausted?: BudgetStop;
+};
+
+/** 推理深度：只传播 / 传播 + 单步反证 / 完整骨干。 */
+export type DeductionDepth = 'propagate' | 'probe' | 'backbone';
+
+/** 一个已能确定、但尚未标记的格子。 */
+export type DeducedCell = {
+	cell: number;
+	checked: boolean;
+	/** 首次确定该格所需的深度。 */
+	depth: DeductionDepth;
+	ruleId?: string;
+	reason: string;
+	reasonKey: EngineMessage;
+};
+
+export type DeductionsResult = {
+	/** 已证明无解（此时 `cells` 为空）。 */
+	noSolution: boolean;
+	/** 按深度分组，组内按推出先后排列。 */
+	cells: DeducedCell[];
+	/** 预算耗尽的原因；此时只含已确定的部分。 */
+	exhausted?: BudgetStop;
+};
+
+/** 预算耗尽的原因：节点数 / 耗时 / 被取消 / 精确计数的 DP 状态数。 */
+export type BudgetStop = 'nodes' | 'time' | 'cancelled' | 'states';
+
+export type SolveProgress = {
+	nodes: number;
+	solutions: number;
+	elapsedMs: number;
+};
+
+/** 求解预算；各项省略或为 0 表示不限制。`onProgress` 返回 false 或抛出异常即取消。 */
+export type SolveBudget = {
+	maxNodes?: number;
+	maxMillis?: number;
+	progressEvery?: number;
+	onProgress?: (progress: SolveProgress) => boolean | void;
+};
+
+/** 胜利条件；省略时为默认的五连线（横/竖/斜）。 */
+export type WinCondition =
+	| { kind: 'full_line'; diagonals: boolean }
+	| { kind: 'in_a_row'; length: number; diagonals: boolean };
+
+/** 生成器选项；省略时与旧版生成器一致，已有 seed（含每日一题）的题目不变。 */
+export type GenerateOptions = {
+	/** 出数字格（八邻恰有 N 格勾选）；开启后同一 seed 得到的是另一道题。 */
+	numberClues?: boolean;
+};
+
+/** 解题会话：每道题创建一次，随玩家操作增量维护求解状态（用完需调用 `free()`）。 */
+export type PuzzleSession = {
+	/** 切换之后返回的文字与错误信息所用的语言。 */
+	set_locale(locale?: Locale): void;
+	assign(cell: number, checked: boolean): void;
+	retract(cell: number): void;
+	is_consistent(): boolean;
+	checked_mask(): Uint8Array;
+	unchecked_mask(): Uint8Array;
+	hint(budget?: SolveBudget): HintResult;
+	hint_with_level(level: number, budget?: SolveBudget): GradedHint;
+	solution_count(limit: number, budget?: SolveBudget): SolutionCountResult;
+	backbone(budget?: SolveBudget): BackboneResult;
+	deductions(depth?: DeductionDepth, budget?: SolveBudget): DeductionsResult;
+	repair(release_exclusions: boolean, budget?: SolveBudget): RepairResult;
+	validate(): ValidateResult;
+	free(): void;
 };
 
 export type Engine = {
-	date_to_seed_ymd(date: string): bigint;
-	generate_puzzle(seed: bigint, size: number): number[][];
-	validate_state(checked_mask: bigint, color_grid: Uint8Array): ValidateResult;
-	difficulty_report(color_grid: Uint8Array): DifficultyReport;
-	hint_next(checked_mask: bigint, color_grid: Uint8Array): HintResult;
-	solution_count(color_grid: Uint8Array, limit: number): SolutionCountResult;
+	PuzzleSession: new (
+		color_grid: Uint8Array,
+		win_condition?: WinCondition,
+		locale?: Locale
+	) => PuzzleSession;
+	date_to_seed_ymd(date: string, locale?: Locale): bigint;
+	generate_puzzle(
+		seed: bigint,
+		size: number,
+		win_condition?: WinCondition,
+		options?: GenerateOptions,
+		locale?: Locale
+	): number[][];
+	validate_state(
+		checked_mask: Uint8Array,
+		color_grid: Uint8Array,
+		win_condition?: WinCondition,
+		locale?: Locale
+	): ValidateResult;
+	difficulty_report(
+		color_grid: Uint8Array,
+		win_condition?: WinCondition,
+		locale?: Locale
+	): DifficultyReport;
+	technique_catalog(locale?: Locale): TechniqueInfo[];
+	explain_unsolvable(
+		color_grid: Uint8Array,
+		win_condition?: WinCondition,
+		budget?: SolveBudget,
+		locale?: Locale
+	): UnsolvableReport;
+	hint_next(
+		checked_mask: Uint8Array,
+		unchecked_mask: Uint8Array,
+		color_grid: Uint8Array,
+		win_condition?: WinCondition,
+		budget?: SolveBudget,
+		locale?: Locale
+	): HintResult;
+	hint_with_level(
+		checked_mask: Uint8Array,
+		unchecked_mask: Uint8Array,
+		color_grid: Uint8Array,
+		win_condition: WinCondition | undefined,
+		level: number,
+		budget?: SolveBudget,
+		locale?: Locale
+	): GradedHint;
+	minimal_repair(
+		checked_mask: Uint8Array,
+		unchecked_mask: Uint8Array,
+		color_grid: Uint8Array,
+		win_condition: WinCondition | undefined,
+		history: Uint32Array,
+		release_exclusions: boolean,
+		budget?: SolveBudget,
+		locale?: Locale
+	): RepairResult;
+	solution_count(
+		color_grid: Uint8Array,
+		limit: number,
+		win_condition?: WinCondition,
+		budget?: SolveBudget,
+		locale?: Locale
+	): SolutionCountResult;
 	solution_count_with_checked(
-		checked_mask: bigint,
+		checked_mask: Uint8Array,
+		unchecked_mask: Uint8Array,
 		color_grid: Uint8Array,
-		limit: number
+		limit: number,
+		win_condition?: WinCondition,
+		budget?: SolveBudget,
+		locale?: Locale
 	): SolutionCountResult;
+	solution_marginals(
+		checked_mask: Uint8Array,
+		unchecked_mask: Uint8Array,
+		color_grid: Uint8Array,
+		samples: number,
+		max_states: number,
+		win_condition?: WinCondition,
+		budget?: SolveBudget,
+		locale?: Locale
+	): SolutionMarginalsResult;
+	backbone(
+		checked_mask: Uint8Array,
+		unchecked_mask: Uint8Array,
+		color_grid: Uint8Array,
+		win_condition?: WinCondition,
+		budget?: SolveBudget,
+		locale?: Locale
+	): BackboneResult;
+	deductions(
+		checked_mask: Uint8Array,
+		unchecked_mask: Uint8Array,
+		color_grid: Uint8Array,
+		win_condition?: WinCondition,
+		depth?: DeductionDepth,
+		budget?: SolveBudget,
+		locale?: Locale
+	): DeductionsResult;
+	conflict_core(
+		checked_mask: Uint8Array,
+		unchecked_mask: Uint8Array,
+		color_grid: Uint8Array,
+		win_condition?: WinCondition,
+		budget?: SolveBudget,
+		locale?: Locale
+	): ConflictCoreResult;
+	solution_count_exact(
+		checked_mask: Uint8Array,
+		unchecked_mask: Uint8Array,
+		color_grid: Uint8Array,
+		max_states: number,
+		win_condition?: WinCondition,
+		budget?: SolveBudget,
+		locale?: Locale
+	): ExactSolutionCountResult;
 };
 
 // 说明：
-// - Rust 的 u64 在 wasm-bindgen 中会映射为 JS BigInt，因此这里使用 bigint。
+// - Rust 的 u64 在 wasm-bindgen 中会映射为 JS BigInt，因此 seed 使用 bigint；
+// - 棋盘 mask 以小端字节数组传入（见 `$lib/mask-bytes.js`），以支持超过 64 格的棋盘。
 export async function loadEngine(): Promise<Engine> {
 	try {
 		// wasm-pack 输出会生成 `pkg/` 目录（本仓库默认不提交产物）。
@@ -23,13 +23,37 @@ self.onmessage = async (e: MessageEvent) => {
 				result = eng.difficulty_report(payload.color_grid);
 				break;
 			case 'solution_count':
-				result = eng.solution_count(payload.color_grid, payload.limit);
+				result = eng.solution_count(payload.color_grid, payload.limit, undefined, payload.budget);
 				break;
 			case 'solution_count_with_checked':
 				result = eng.solution_count_with_checked(
 					payload.checked_mask,
+					payload.unchecked_mask,
 					payload.color_grid,
-					payload.limit
+					payload.limit,
+					undefined,
+					payload.budget
+				);
+				break;
+			case 'solution_marginals':
+				result = eng.solution_marginals(
+					payload.checked_mask,
+					payload.unchecked_mask,
+					payload.color_grid,
+					payload.samples,
+					payload.max_states,
+					undefined,
+					payload.budget
+				);
+				break;
+			case 'solution_count_exact':
+				result = eng.solution_count_exact(
+					payload.checked_mask,
+					payload.unchecked_mask,
+					payload.color_grid,
+					payload.max_states,
+					undefined,
+					payload.budget
 				);
 				break;
 			default:
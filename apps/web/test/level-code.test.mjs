@@ -11,6 +11,22 @@ test('encodeLevel/decodeLevel: roundtrip', () => {
 	assert.deepEqual(decoded.grid, grid);
 });
 
+test('encodeLevel/decodeLevel: numbered cells above 15 use the wide format', () => {
+	const grid = Array.from({ length: 25 }, (_, i) => i % 18);
+	const decoded = decodeLevel(encodeLevel(grid));
+	assert.equal(decoded.version, 2);
+	assert.deepEqual(decoded.grid, grid);
+
+	assert.throws(() => encodeLevel(Array.from({ length: 25 }, () => 34)));
+});
+
+test('encodeLevel/decodeLevel: variant neighbourhood cells round-trip', () => {
+	const grid = Array.from({ length: 25 }, (_, i) => 18 + (i % 16));
+	const decoded = decodeLevel(encodeLevel(grid));
+	assert.equal(decoded.version, 2);
+	assert.deepEqual(decoded.grid, grid);
+});
+
 test('normalizeLevelJson: accepts {version, grid:number[25]}', () => {
 	const grid = Array.from({ length: 25 }, (_, i) => (i * 3) % 9);
 	const obj = levelToJson(grid);
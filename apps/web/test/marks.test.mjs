@@ -1,7 +1,15 @@
 import test from 'node:test';
 import assert from 'node:assert/strict';
 
-import { createMarks, cycleMarkValue, normalizeMarks, MARK_EXCLUDE, MARK_NONE, MARK_QUESTION } from '../src/lib/marks.js';
+import {
+	createMarks,
+	cycleMarkValue,
+	excludedMaskFromMarks,
+	normalizeMarks,
+	MARK_EXCLUDE,
+	MARK_NONE,
+	MARK_QUESTION
+} from '../src/lib/marks.js';
 
 test('marks: createMarks length + defaults', () => {
 	const m = createMarks();
@@ -26,3 +34,12 @@ test('marks: normalizeMarks clamps shape + values', () => {
 	assert.equal(m[2], MARK_QUESTION);
 });
 
+
+test('marks: excludedMaskFromMarks keeps only exclude marks on unchecked cells', () => {
+	const m = createMarks();
+	m[0] = MARK_EXCLUDE;
+	m[3] = MARK_QUESTION;
+	m[5] = MARK_EXCLUDE;
+	assert.equal(excludedMaskFromMarks(m), (1n << 0n) | (1n << 5n));
+	assert.equal(excludedMaskFromMarks(m, 1n << 5n), 1n << 0n);
+});
@@ -0,0 +1,17 @@
+import test from 'node:test';
+import assert from 'node:assert/strict';
+
+import { bytesToMask, maskToBytes } from '../src/lib/mask-bytes.js';
+
+test('mask-bytes: little-endian layout', () => {
+	const bytes = maskToBytes((1n << 0n) | (1n << 7n) | (1n << 8n), 25);
+	assert.equal(bytes.length, 4);
+	assert.deepEqual([...bytes], [0b1000_0001, 1, 0, 0]);
+});
+
+test('mask-bytes: roundtrip beyond 64 cells', () => {
+	const mask = (1n << 80n) | (1n << 143n) | 5n;
+	const bytes = maskToBytes(mask, 144);
+	assert.equal(bytes.length, 18);
+	assert.equal(bytesToMask(bytes), mask);
+});
@@ -0,0 +1,32 @@
+import test from 'node:test';
+import assert from 'node:assert/strict';
+
+import { syncSessionMasks } from '../src/lib/session-sync.js';
+
+function recorder() {
+	const calls = [];
+	return {
+		calls,
+		assign: (cell, checked) => calls.push(['assign', cell, checked]),
+		retract: (cell) => calls.push(['retract', cell])
+	};
+}
+
+test('session-sync: only touches changed cells', () => {
+	const s = recorder();
+	const prev = { checked: 0b0011n, unchecked: 0b0100n };
+	const next = { checked: 0b0101n, unchecked: 0b1000n };
+	const out = syncSessionMasks(s, prev, next, 4);
+	assert.deepEqual(s.calls, [
+		['retract', 1],
+		['assign', 2, true],
+		['assign', 3, false]
+	]);
+	assert.deepEqual(out, next);
+});
+
+test('session-sync: checked wins over excluded', () => {
+	const s = recorder();
+	syncSessionMasks(s, { checked: 0n, unchecked: 0n }, { checked: 1n << 70n, unchecked: 1n << 70n }, 81);
+	assert.deepEqual(s.calls, [['assign', 70, true]]);
+});
@@ -3,16 +3,23 @@
 //! 示例：
 //! - `cargo run -p kairm_engine --bin debug -- --seed 123`
 //! - `cargo run -p kairm_engine --bin debug -- --date 2025-12-18`
+//! - `cargo run -p kairm_engine --bin debug -- --seed 123 --size 9`
+//! - `cargo run -p kairm_engine --bin debug -- --seed 123 --size 9 --win '{"kind":"full_line","diagonals":false}'`
+//! - `cargo run -p kairm_engine --bin debug -- --seed 123 --number-clues`
 
 use std::env;
 
 use kairm_engine::{
-    date_to_seed_ymd, difficulty_report_native, generate_puzzle_grid, validate_state_native, Color,
+    date_to_seed_ymd_native, difficulty_report_native, generate_puzzle_grid, validate_state_native,
+    Color, GenerateOptions, Mask, WinCondition,
 };
 
 fn main() {
     let mut args = env::args().skip(1).collect::<Vec<_>>();
     let mut seed: Option<u64> = None;
+    let mut size = 5usize;
+    let mut win = WinCondition::default();
+    let mut options = GenerateOptions::default();
 
     while !args.is_empty() {
         let k = args.remove(0);
@@ -23,37 +30,48 @@ fn main() {
             }
             "--date" => {
                 let v = args.remove(0);
-                seed = Some(date_to_seed_ymd(&v).expect("date 解析失败"));
+                seed = Some(date_to_seed_ymd_native(&v).expect("date 解析失败"));
             }
+            "--size" => {
+                let v = args.remove(0);
+                size = v.parse::<usize>().expect("size 必须是正整数");
+            }
+            "--win" => {
+                let v = args.remove(0);
+                win = serde_json::from_str(&v).expect("win 必须是 WinCondition JSON");
+            }
+            "--number-clues" => options.number_clues = true,
             _ => {
                 eprintln!("未知参数：{k}");
-                eprintln!("用法：--seed <u64> 或 --date <YYYY-MM-DD>");
+                eprintln!(
+                    "用法：--seed <u64> 或 --date <YYYY-MM-DD>，可选 --size <n>、--win <json>、--number-clues"
+                );
                 std::process::exit(2);
             }
         }
     }
 
     let seed = seed.unwrap_or(0);
-    let grid = generate_puzzle_grid(seed, 5).expect("生成题目失败");
+    let grid = generate_puzzle_grid(seed, size, win, options).expect("生成题目失败");
 
     let flat: Vec<u8> = grid.iter().flat_map(|r| r.iter().copied()).collect();
-    let mut black_mask = 0u64;
+    let mut black_mask = Mask::EMPTY;
     for (i, &c) in flat.iter().enumerate() {
         if c == Color::Black.to_u8() {
-            black_mask |= 1u64 << i;
+            black_mask.insert(i);
         }
     }
 
-    let res = validate_state_native(black_mask, &flat).expect("校验失败");
+    let res = validate_state_native(black_mask, &flat, win).expect("校验失败");
 
-    let diff = difficulty_report_native(&flat).expect("difficulty_report failed");
+    let diff = difficulty_report_native(&flat, win).expect("difficulty_report failed");
 
     println!("seed={seed}");
     println!(
-        "grid(5x5,u8)={}",
+        "grid({size}x{size},u8)={}",
         serde_json::to_string_pretty(&grid).unwrap()
     );
-    println!("initial_black_mask={black_mask}");
+    println!("initial_black_mask={black_mask:?}");
     println!(
         "validate(initial)={}",
         serde_json::to_string_pretty(&res).unwrap()
@@ -1,20 +1,56 @@
 use serde::{Deserialize, Serialize};
 
-pub const COLOR_COUNT: usize = 9;
+use crate::error::EngineError;
+use crate::masks::{Neighborhood, MAX_GRID_SIZE};
+use crate::solver::rule_for;
+
+/// 颜色编码的个数（`0..COLOR_COUNT` 都是合法编码）。
+pub const COLOR_COUNT: usize = VARIANT_BASE as usize + CountColor::ALL.len() * VARIANT_HOODS.len();
+
+/// 数字格 `N` 的编码为 `NUMBER_BASE + N`。
+pub const NUMBER_BASE: u8 = 9;
+/// 数字格的最大数字（八邻最多 8 格）。
+pub const MAX_NUMBER: u8 = 8;
+
+/// 变体格的编码起点：`VARIANT_BASE + 底色序号 * VARIANT_HOODS.len() + 邻域序号`。
+pub const VARIANT_BASE: u8 = NUMBER_BASE + MAX_NUMBER + 1;
+/// 变体格可换用的邻域（八邻即原色本身，不另占编码）。
+pub const VARIANT_HOODS: [Neighborhood; 4] = [
+    Neighborhood::Orthogonal,
+    Neighborhood::Knight,
+    Neighborhood::Line2,
+    Neighborhood::Diagonal,
+];
+
+/// 可以换邻域的计数颜色（见 `Color::Variant`）。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CountColor {
+    Red,
+    Blue,
+    Purple,
+    Orange,
+}
+
+impl CountColor {
+    pub const ALL: [Self; 4] = [Self::Red, Self::Blue, Self::Purple, Self::Orange];
+}
 
 /// 颜色编码（与 `rules.json` 保持一致）。
-#[repr(u8)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Color {
-    Red = 0,
-    Blue = 1,
-    Black = 2,
-    Green = 3,
-    Yellow = 4,
-    Purple = 5,
-    White = 6,
-    Orange = 7,
-    Cyan = 8,
+    Red,
+    Blue,
+    Black,
+    Green,
+    Yellow,
+    Purple,
+    White,
+    Orange,
+    Cyan,
+    /// 数字格：八邻恰好 N 格勾选（`N` 为 `0..=MAX_NUMBER`）。
+    Number(u8),
+    /// 变体格：红/蓝/紫/橙的计数改在另一种邻域上（邻域取自 `VARIANT_HOODS`）。
+    Variant(CountColor, Neighborhood),
 }
 
 impl Color {
@@ -29,22 +65,80 @@ impl Color {
             6 => Self::White,
             7 => Self::Orange,
             8 => Self::Cyan,
+            v if (NUMBER_BASE..=NUMBER_BASE + MAX_NUMBER).contains(&v) => {
+                Self::Number(v - NUMBER_BASE)
+            }
+            v if (VARIANT_BASE as usize..COLOR_COUNT).contains(&(v as usize)) => {
+                let k = (v - VARIANT_BASE) as usize;
+                Self::Variant(
+                    CountColor::ALL[k / VARIANT_HOODS.len()],
+                    VARIANT_HOODS[k % VARIANT_HOODS.len()],
+                )
+            }
             _ => return None,
         })
     }
 
     pub fn to_u8(self) -> u8 {
-        self as u8
+        match self {
+            Self::Red => 0,
+            Self::Blue => 1,
+            Self::Black => 2,
+            Self::Green => 3,
+            Self::Yellow => 4,
+            Self::Purple => 5,
+            Self::White => 6,
+            Self::Orange => 7,
+            Self::Cyan => 8,
+            Self::Number(n) => NUMBER_BASE + n,
+            Self::Variant(base, hood) => {
+                let hood = VARIANT_HOODS
+                    .iter()
+                    .position(|&h| h == hood)
+                    .expect("变体格的邻域必须取自 VARIANT_HOODS");
+                VARIANT_BASE + (base as usize * VARIANT_HOODS.len() + hood) as u8
+            }
+        }
+    }
+
+    /// `base` 在邻域 `hood` 上的颜色：八邻即原色，其余为变体格。
+    pub fn counted(base: CountColor, hood: Neighborhood) -> Self {
+        match (base, hood) {
+            (CountColor::Red, Neighborhood::King) => Self::Red,
+            (CountColor::Blue, Neighborhood::King) => Self::Blue,
+            (CountColor::Purple, Neighborhood::King) => Self::Purple,
+            (CountColor::Orange, Neighborhood::King) => Self::Orange,
+            _ => Self::Variant(base, hood),
+        }
+    }
+
+    /// 对应的规则 id（与 `rules.json` 对齐，见规则注册表）；白格没有规则。
+    pub fn rule_id(self) -> Option<&'static str> {
+        rule_for(self).map(|rule| rule.id())
     }
 }
 
-pub const NON_WHITE_COLORS: [Color; 8] = [
-    Color::Red,
-    Color::Blue,
-    Color::Black,
-    Color::Green,
-    Color::Yellow,
-    Color::Purple,
-    Color::Orange,
-    Color::Cyan,
-];
+/// 颜色布局的边长：长度必须是边长 `1..=MAX_GRID_SIZE` 的正方形。
+pub(crate) fn grid_size(cell_count: usize) -> Result<usize, EngineError> {
+    let size = (cell_count as f64).sqrt() as usize;
+    if size * size != cell_count || !(1..=MAX_GRID_SIZE).contains(&size) {
+        return Err(EngineError::GridNotSquare {
+            actual: cell_count,
+            max: MAX_GRID_SIZE,
+        });
+    }
+    Ok(size)
+}
+
+/// 校验并解析颜色布局（u8，row-major），返回边长与颜色。
+pub(crate) fn parse_grid(color_grid: &[u8]) -> Result<(usize, Vec<Color>), EngineError> {
+    let size = grid_size(color_grid.len())?;
+    let colors = color_grid
+        .iter()
+        .enumerate()
+        .map(|(index, &value)| {
+            Color::from_u8(value).ok_or(EngineError::InvalidColor { index, value })
+        })
+        .collect::<Result<_, _>>()?;
+    Ok((size, colors))
+}
@@ -0,0 +1,96 @@
+use serde::Serialize;
+
+use crate::colors::parse_grid;
+use crate::error::EngineError;
+use crate::masks::Mask;
+use crate::solver::{BudgetStop, SolveBudget, Solver};
+use crate::win::WinCondition;
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+pub struct ExactSolutionCountResult {
+    /// 解的精确数量（超过 u128 时饱和）；放弃计算时为 None。
+    pub count: Option<u128>,
+    /// 放弃计算的原因：DP 状态数超过 `max_states`（`states`）或预算耗尽；完成时为 None。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exhausted: Option<BudgetStop>,
+}
+
+/// 精确统计解的数量（轮廓 DP，不枚举解）。
+///
+/// `max_states` 为 DP 单层状态数上限（0 表示不限制）；超过或预算耗尽时 `count` 为 None。
+pub fn solution_count_exact(
+    checked_mask: Mask,
+    unchecked_mask: Mask,
+    color_grid: &[u8],
+    win: WinCondition,
+    max_states: usize,
+    budget: &mut SolveBudget,
+) -> Result<ExactSolutionCountResult, EngineError> {
+    let (size, colors) = parse_grid(color_grid)?;
+
+    let res = Solver::new(size, colors, win).count_solutions_exact(
+        checked_mask,
+        unchecked_mask,
+        max_states,
+        budget,
+    );
+    Ok(ExactSolutionCountResult {
+        count: res.value,
+        exhausted: res.exhausted,
+    })
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct SolutionMarginalsResult {
+    /// row-major，每格在全部解中被勾选的比例；放弃计算时为空。
+    pub cells: Vec<f64>,
+    /// 解的总数（超过 u128 时饱和）；放弃计算时为 None。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub count: Option<u128>,
+    /// `cells` 是否为抽样估计（解数超过 `samples` 时）。
+    pub sampled: bool,
+    /// 抽样估计所用的样本数；精确时为 0。
+    pub samples: u32,
+    /// 放弃计算的原因，约定同 [`ExactSolutionCountResult::exhausted`]。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exhausted: Option<BudgetStop>,
+}
+
+/// 逐格边际概率：解数不超过 `samples` 时精确枚举，否则从 DP 中均匀抽样估计。
+///
+/// `max_states` 约定同 [`solution_count_exact`]；超过或预算耗尽时 `cells` 为空、`count` 为 None。
+pub fn solution_marginals(
+    checked_mask: Mask,
+    unchecked_mask: Mask,
+    color_grid: &[u8],
+    win: WinCondition,
+    samples: u32,
+    max_states: usize,
+    budget: &mut SolveBudget,
+) -> Result<SolutionMarginalsResult, EngineError> {
+    let (size, colors) = parse_grid(color_grid)?;
+
+    let res = Solver::new(size, colors, win).solution_marginals(
+        checked_mask,
+        unchecked_mask,
+        samples,
+        max_states,
+        budget,
+    );
+    Ok(match res.value {
+        Some(m) => SolutionMarginalsResult {
+            cells: m.cells,
+            count: Some(m.count),
+            sampled: m.samples != 0,
+            samples: m.samples,
+            exhausted: res.exhausted,
+        },
+        None => SolutionMarginalsResult {
+            cells: Vec::new(),
+            count: None,
+            sampled: false,
+            samples: 0,
+            exhausted: res.exhausted,
+        },
+    })
+}
@@ -1,47 +1,38 @@
-use thiserror::Error;
-
-#[derive(Debug, Error)]
-pub enum DateSeedError {
-    #[error("日期格式错误：期望 YYYY-MM-DD，得到：{0}")]
-    BadFormat(String),
-    #[error("日期字段非法：{0}")]
-    BadValue(String),
-}
+use crate::error::EngineError;
 
 /// 将 YYYY-MM-DD 转为 u64 seed（以 UTC 1970-01-01 为 day0）。
 ///
 /// 说明：
 /// - 该 seed 仅用于“每日一题”的确定性输入，不依赖本地时区；
 /// - 前端应使用 `Asia/Shanghai` 时区计算“今日日期字符串”，再传入此函数。
-pub fn date_to_seed_ymd(date_ymd: &str) -> Result<u64, DateSeedError> {
+pub fn date_to_seed_ymd(date_ymd: &str) -> Result<u64, EngineError> {
     let parts: Vec<&str> = date_ymd.trim().split('-').collect();
     if parts.len() != 3 {
-        return Err(DateSeedError::BadFormat(date_ymd.to_string()));
+        return Err(EngineError::InvalidDate {
+            value: date_ymd.to_string(),
+        });
     }
 
-    let year: i32 = parts[0]
-        .parse()
-        .map_err(|_| DateSeedError::BadValue(format!("year={}", parts[0])))?;
-    let month: u32 = parts[1]
-        .parse()
-        .map_err(|_| DateSeedError::BadValue(format!("month={}", parts[1])))?;
-    let day: u32 = parts[2]
-        .parse()
-        .map_err(|_| DateSeedError::BadValue(format!("day={}", parts[2])))?;
+    let field = |field: &'static str, value: &str| EngineError::InvalidDateField {
+        field,
+        value: value.to_string(),
+    };
+    let year: i32 = parts[0].parse().map_err(|_| field("year", parts[0]))?;
+    let month: u32 = parts[1].parse().map_err(|_| field("month", parts[1]))?;
+    let day: u32 = parts[2].parse().map_err(|_| field("day", parts[2]))?;
 
-    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
-        return Err(DateSeedError::BadValue(format!(
-            "month/day out of range: {}-{:02}-{:02}",
-            year, month, day
-        )));
+    if !(1..=12).contains(&month) {
+        return Err(field("month", parts[1]));
+    }
+    if !(1..=31).contains(&day) {
+        return Err(field("day", parts[2]));
     }
 
     let days = days_from_civil(year, month, day);
     if days < 0 {
-        return Err(DateSeedError::BadValue(format!(
-            "date before 1970-01-01: {}",
-            date_ymd
-        )));
+        return Err(EngineError::DateBeforeEpoch {
+            value: date_ymd.to_string(),
+        });
     }
     Ok(days as u64)
 }
@@ -72,4 +63,23 @@ mod tests {
         assert_eq!(date_to_seed_ymd("1970-01-02").unwrap(), 1);
         assert_eq!(date_to_seed_ymd("1970-01-31").unwrap(), 30);
     }
+
+    #[test]
+    fn reports_the_offending_field() {
+        assert_eq!(
+            date_to_seed_ymd("2025-13-01"),
+            Err(EngineError::InvalidDateField {
+                field: "month",
+                value: "13".to_string(),
+            })
+        );
+        assert_eq!(
+            date_to_seed_ymd("2025/01/01").unwrap_err().code(),
+            "invalid_date"
+        );
+        assert_eq!(
+            date_to_seed_ymd("1969-12-31").unwrap_err().code(),
+            "date_before_epoch"
+        );
+    }
 }
@@ -1,19 +1,12 @@
 use serde::Serialize;
-use thiserror::Error;
-
-use crate::colors::Color;
-use crate::masks::CELL_COUNT;
-use crate::solver::{HumanDifficultyAnalysis, RuleType, SolveStats, Solver};
-
-#[derive(Debug, Error)]
-pub enum DifficultyError {
-    #[error("color_grid 长度必须为 {expected}，得到：{actual}")]
-    BadGridLength { expected: usize, actual: usize },
-    #[error("color_grid 含非法颜色编码：index={index}, value={value}")]
-    BadColor { index: usize, value: u8 },
-    #[error("该题目无解，无法计算难度分")]
-    NoSolution,
-}
+
+use crate::colors::{parse_grid, Color};
+use crate::error::EngineError;
+use crate::solver::{
+    hardest_technique, rule_for, technique_histogram, Budgeted, HumanDifficultyAnalysis, RuleType,
+    SolveBudget, SolveStats, Solver, Technique, TechniqueCount,
+};
+use crate::win::WinCondition;
 
 #[derive(Debug, Clone, Serialize)]
 pub struct DifficultyStats {
@@ -47,6 +40,10 @@ pub struct DifficultyStats {
 #[derive(Debug, Clone, Serialize)]
 pub struct DifficultyReport {
     pub difficulty_score: u32,
+    /// 人类逻辑阶段（传播 + 单步反证）用到的最难技巧；一步都推不出时为 None。
+    pub hardest_technique: Option<Technique>,
+    /// 各技巧推出的格子数（目录顺序，略去未用到的技巧；单步反证按次数计）。
+    pub techniques: Vec<TechniqueCount>,
     pub stats: DifficultyStats,
 }
 
@@ -87,25 +84,22 @@ pub struct HumanDifficultyStats {
 }
 
 fn human_rule_weight(rule: &RuleType) -> u32 {
-    // 经验权重（1~7）：用于刻画“人类直觉上”不同规则的推理负担。
-    // - 红/蓝/青偏局部；绿/黄偏全局计数；紫/橙（奇偶）对人更“反直觉”。
+    // 经验权重（1~8）：用于刻画“人类直觉上”不同规则的推理负担。
+    // - 红/蓝/青偏局部；绿/黄偏全局计数；紫/橙（奇偶）对人更“反直觉”，多格奇偶联立最难。
     match rule {
-        RuleType::Red => 2,
-        RuleType::Blue => 3,
-        RuleType::Cyan => 4,
-        RuleType::Green => 5,
-        RuleType::Yellow => 6,
-        RuleType::Purple => 7,
-        RuleType::Orange => 7,
+        RuleType::Cell(color) => rule_for(*color).map_or(1, |r| r.human_weight()),
+        RuleType::Parity => 8,
+        RuleType::CountChain => 7,
         RuleType::FiveInRow => 5,
     }
 }
 
-fn difficulty_score_human(h: &HumanDifficultyAnalysis) -> u32 {
-    if h.exhausted_budget {
+fn difficulty_score_human(human: &Budgeted<HumanDifficultyAnalysis>) -> u32 {
+    if !human.is_finished() {
         // 预算耗尽说明分析不完整；宁可偏保守给高分，避免出现“看似很简单但实际卡住”的误判。
         return 100;
     }
+    let h = &human.value;
 
     let logic_rounds = h.logic_propagate_rounds.saturating_sub(1) as f64;
     let logic_assignments = h.logic_assignments_propagated as f64;
@@ -170,13 +164,11 @@ fn difficulty_score_human(h: &HumanDifficultyAnalysis) -> u32 {
 
 fn rule_type_to_string(rule_type: &RuleType) -> String {
     match rule_type {
-        RuleType::Green => "Green".to_string(),
-        RuleType::Yellow => "Yellow".to_string(),
-        RuleType::Red => "Red".to_string(),
-        RuleType::Blue => "Blue".to_string(),
-        RuleType::Purple => "Purple".to_string(),
-        RuleType::Orange => "Orange".to_string(),
-        RuleType::Cyan => "Cyan".to_string(),
+        RuleType::Cell(Color::Number(n)) => format!("Number{n}"),
+        RuleType::Cell(Color::Variant(base, hood)) => format!("{base:?}{hood:?}"),
+        RuleType::Cell(color) => format!("{color:?}"),
+        RuleType::Parity => "Parity".to_string(),
+        RuleType::CountChain => "CountChain".to_string(),
         RuleType::FiveInRow => "FiveInRow".to_string(),
     }
 }
@@ -194,33 +186,31 @@ fn calculate_logic_chain_start_difficulty(first_trigger_counts: &std::collection
     (total_weighted / total_count).min(u32::MAX as u64) as u32
 }
 
-pub fn difficulty_report(color_grid: &[u8]) -> Result<DifficultyReport, DifficultyError> {
-    let cell_count = color_grid.len();
-    let size = (cell_count as f64).sqrt() as usize;
-    if size * size != cell_count {
-        return Err(DifficultyError::BadGridLength {
-            expected: size * size,
-            actual: cell_count,
-        });
-    }
+/// 人类逻辑难度分析的节点预算（每次传播记一个节点）。
+const HUMAN_ANALYSIS_NODES: u64 = 50_000;
 
-    let mut colors = Vec::with_capacity(cell_count);
-    for (i, &v) in color_grid.iter().enumerate() {
-        colors.push(Color::from_u8(v).ok_or(DifficultyError::BadColor { index: i, value: v })?);
-    }
+pub fn difficulty_report(
+    color_grid: &[u8],
+    win: WinCondition,
+) -> Result<DifficultyReport, EngineError> {
+    let (size, colors) = parse_grid(color_grid)?;
 
-    let solver = Solver::new(size, colors);
+    let solver = Solver::new(size, colors, win);
 
     // 先做“人类逻辑难度”分析：不依赖求解器枚举分支的工作量统计。
-    let human = solver.analyze_human_difficulty();
+    let analysis = solver.analyze_human_difficulty(&mut SolveBudget {
+        max_nodes: HUMAN_ANALYSIS_NODES,
+        ..SolveBudget::default()
+    });
 
     let mut stats = SolveStats::default();
     let solutions = solver.solve_masks_limit_with_stats(1, &mut stats);
     if solutions.is_empty() {
-        return Err(DifficultyError::NoSolution);
+        return Err(EngineError::Unsolvable);
     }
 
-    let difficulty_score = difficulty_score_human(&human);
+    let difficulty_score = difficulty_score_human(&analysis);
+    let human = &analysis.value;
     
     // 计算推理入口隐蔽度
     let logic_chain_start_difficulty =
@@ -249,8 +239,12 @@ pub fn difficulty_report(color_grid: &[u8]) -> Result<DifficultyReport, Difficul
     let total_backtrack_distance: u32 = stats.backtrack_distances.iter().sum();
     let max_backtrack_distance: u32 = stats.backtrack_distances.iter().max().copied().unwrap_or(0);
 
+    let techniques = technique_histogram(&human.technique_counts);
+
     Ok(DifficultyReport {
         difficulty_score,
+        hardest_technique: hardest_technique(&techniques),
+        techniques,
         stats: DifficultyStats {
             node_visits: stats.node_visits.min(u32::MAX as u64) as u32,
             decision_points: stats.decision_points.min(u32::MAX as u64) as u32,
@@ -272,7 +266,7 @@ pub fn difficulty_report(color_grid: &[u8]) -> Result<DifficultyReport, Difficul
 
             human: HumanDifficultyStats {
                 solved: human.solved,
-                exhausted_budget: human.exhausted_budget,
+                exhausted_budget: !analysis.is_finished(),
                 variable_cells: human.variable_cells,
                 initial_unknown_after_logic: human.initial_unknown_after_logic,
                 bingo_segments_total: human.bingo_segments_total,
@@ -311,11 +305,14 @@ pub fn difficulty_report(color_grid: &[u8]) -> Result<DifficultyReport, Difficul
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::colors::Color;
+    use crate::masks::CELL_COUNT;
 
     #[test]
     fn all_black_is_trivially_easy() {
         let grid = vec![Color::Black.to_u8(); CELL_COUNT];
-        let report = difficulty_report(&grid).expect("should have solution");
+        let report =
+            difficulty_report(&grid, WinCondition::default()).expect("should have solution");
 
         assert_eq!(report.stats.decision_points, 0);
         assert_eq!(report.stats.dead_ends, 0);
@@ -332,16 +329,28 @@ mod tests {
             grid[i] = Color::Black.to_u8();
         }
 
-        let err = difficulty_report(&grid).expect_err("should be unsat");
-        assert!(matches!(err, DifficultyError::NoSolution));
+        let err = difficulty_report(&grid, WinCondition::default()).expect_err("should be unsat");
+        assert!(matches!(err, EngineError::Unsolvable));
     }
-    
+
+    #[test]
+    fn rejects_grids_outside_the_supported_sizes() {
+        // 空棋盘与超过 MAX_GRID_SIZE 的正方形都应报错，而不是在构造求解器时 panic。
+        for len in [0, 17 * 17] {
+            let err = difficulty_report(&vec![Color::White.to_u8(); len], WinCondition::default())
+                .unwrap_err();
+            assert_eq!(err.code(), "grid_not_square");
+        }
+        let err = difficulty_report(&[34; 25], WinCondition::default()).unwrap_err();
+        assert_eq!(err, EngineError::InvalidColor { index: 0, value: 34 });
+    }
+
     #[test]
     fn logic_chain_start_difficulty_basic() {
         // 测试推理入口隐蔽度基本功能
         // 创建一个有颜色规则的网格，确保有规则被触发
         let mut grid = vec![Color::White.to_u8(); CELL_COUNT];
-        
+
         // 用 Blue 的“邻域勾选数 <= 2”制造一次确定传播：
         // - 中心放 Blue
         // - 在其 8 邻域里只放 2 个 Black（强制勾选）
@@ -349,28 +358,62 @@ mod tests {
         grid[12] = Color::Blue.to_u8(); // (2,2)
         grid[6] = Color::Black.to_u8(); // (1,1)
         grid[7] = Color::Black.to_u8(); // (1,2)
-        
-        let report = difficulty_report(&grid).expect("should have solution");
-        
+
+        let report =
+            difficulty_report(&grid, WinCondition::default()).expect("should have solution");
+
         // 检查规则触发计数和推理入口隐蔽度是否被正确计算
-        assert!(report.stats.first_trigger_rule_counts.len() > 0);
+        assert!(!report.stats.first_trigger_rule_counts.is_empty());
         assert!(report.stats.logic_chain_start_difficulty > 0);
     }
-    
+
+    #[test]
+    fn reports_techniques_and_the_hardest_one() {
+        // 同上：蓝格饱和会推出其余 6 个邻居不勾选。
+        let mut grid = vec![Color::White.to_u8(); CELL_COUNT];
+        grid[12] = Color::Blue.to_u8();
+        grid[6] = Color::Black.to_u8();
+        grid[7] = Color::Black.to_u8();
+
+        let report =
+            difficulty_report(&grid, WinCondition::default()).expect("should have solution");
+        let blue = report
+            .techniques
+            .iter()
+            .find(|c| c.technique == Technique::BlueSaturation)
+            .expect("blue saturation is used");
+        assert_eq!(blue.count, 6);
+
+        let hardest = report.hardest_technique.expect("at least one technique");
+        assert!(report.techniques.iter().all(|c| c.tier <= hardest.tier()));
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["hardest_technique"], hardest.id());
+        assert_eq!(
+            json["techniques"][0]["technique"],
+            report.techniques[0].technique.id()
+        );
+
+        // 全黑盘面无需任何推理。
+        let grid = vec![Color::Black.to_u8(); CELL_COUNT];
+        let report = difficulty_report(&grid, WinCondition::default()).unwrap();
+        assert!(report.techniques.is_empty());
+        assert_eq!(report.hardest_technique, None);
+    }
+
     #[test]
     fn backtrack_distance_basic() {
         // 测试回溯距离基本功能
         // 创建一个需要回溯的简单网格
         let mut grid = vec![Color::White.to_u8(); CELL_COUNT];
-        
+
         // 设置一些强制约束，导致需要回溯
-        grid[0] = Color::Blue.to_u8();  // 蓝色规则：邻域勾选数 <= 2
-        grid[1] = Color::Black.to_u8();  // 强制勾选
-        grid[5] = Color::Black.to_u8();  // 强制勾选
-        grid[6] = Color::Black.to_u8();  // 强制勾选
-        
+        grid[0] = Color::Blue.to_u8(); // 蓝色规则：邻域勾选数 <= 2
+        grid[1] = Color::Black.to_u8(); // 强制勾选
+        grid[5] = Color::Black.to_u8(); // 强制勾选
+        grid[6] = Color::Black.to_u8(); // 强制勾选
+
         // 这个网格应该需要回溯
-        if let Ok(report) = difficulty_report(&grid) {
+        if let Ok(report) = difficulty_report(&grid, WinCondition::default()) {
             // 检查回溯距离指标是否被正确计算
             assert!(report.stats.total_backtrack_distance > 0);
             assert!(report.stats.avg_backtrack_distance > 0.0);
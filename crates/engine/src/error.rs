@@ -0,0 +1,283 @@
+//! 引擎统一的错误类型。
+//!
+//! - 每个变体有稳定的错误码（[`EngineError::code`]，snake_case），前端按错误码分支，不要匹配文字；
+//! - 结构化字段（`index`、`value`、`expected`、`actual` 等）序列化为 `details`，
+//!   与消息参数同名，WASM 边界上抛出 `{code, message, details}`（见 `lib.rs` 的 `js_error`）；
+//! - 文字走 i18n 目录（[`EngineError::message`]），zh-CN 渲染与 `Display` 一致。
+
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::i18n::{msg, Message};
+use crate::win::WinCondition;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error, Serialize)]
+#[serde(tag = "code", content = "details", rename_all = "snake_case")]
+pub enum EngineError {
+    /// 颜色布局不是边长 `1..=max` 的正方形。
+    #[error("color_grid 必须是边长 1..={max} 的正方形，得到长度：{actual}")]
+    GridNotSquare { actual: usize, max: usize },
+    #[error("color_grid 含非法颜色编码：index={index}, value={value}")]
+    InvalidColor { index: usize, value: u8 },
+    /// 生成器的边长超出范围。
+    #[error("size 必须在 {min}..={max}，得到：{actual}")]
+    SizeOutOfRange {
+        actual: usize,
+        min: usize,
+        max: usize,
+    },
+    #[error("win_condition 解析失败：{detail}")]
+    InvalidWinCondition { detail: String },
+    /// 胜利条件在该尺寸下没有任何可行连线。
+    #[error("胜利条件在 {size}x{size} 棋盘上没有任何可行连线：{win:?}")]
+    NoWinLines { size: usize, win: WinCondition },
+    /// `seed` 以十进制字符串给出（超过 JS 安全整数范围时不丢精度）。
+    #[error("在限制次数内未找到至少 1 个解的题目（seed={seed}, attempts={attempts}）")]
+    GenerationFailed { seed: String, attempts: usize },
+    #[error("该题目无解，无法计算难度分")]
+    Unsolvable,
+    #[error("格子下标越界：cell={index}, 共 {cell_count} 格")]
+    CellOutOfRange { index: usize, cell_count: usize },
+    /// 黑格固定勾选，会话中不能把它标记为排除。
+    #[error("黑格固定勾选，不能排除：cell={index}")]
+    FixedCellExcluded { index: usize },
+    /// mask 字节数超过 `Mask` 的容量。
+    #[error("{name} 超出棋盘容量：得到 {actual} 字节，最多 {max} 字节")]
+    MaskTooLarge {
+        name: &'static str,
+        actual: usize,
+        max: usize,
+    },
+    /// mask 含棋盘外的格子。
+    #[error("{name} 含棋盘外的格子：index={index}, 共 {cell_count} 格")]
+    MaskOutOfRange {
+        name: &'static str,
+        index: usize,
+        cell_count: usize,
+    },
+    #[error("日期格式错误：期望 YYYY-MM-DD，得到：{value}")]
+    InvalidDate { value: String },
+    #[error("日期字段非法：{field}={value}")]
+    InvalidDateField { field: &'static str, value: String },
+    #[error("日期早于 1970-01-01：{value}")]
+    DateBeforeEpoch { value: String },
+    #[error("budget 必须是对象")]
+    BudgetNotObject,
+    #[error("budget.{field} 必须是非负数字")]
+    InvalidBudgetNumber { field: &'static str },
+    #[error("budget.onProgress 必须是函数")]
+    InvalidProgressCallback,
+    #[error("depth 解析失败：{detail}")]
+    InvalidDepth { detail: String },
+    #[error("生成选项解析失败：{detail}")]
+    InvalidGenerateOptions { detail: String },
+    #[error("locale 必须是字符串")]
+    InvalidLocale,
+    /// 结果转为 JS 值失败（引擎内部错误）。
+    #[error("结果序列化失败：{detail}")]
+    Serialization { detail: String },
+}
+
+impl EngineError {
+    /// 稳定的错误码（与序列化后的 `code` 一致）。
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::GridNotSquare { .. } => "grid_not_square",
+            Self::InvalidColor { .. } => "invalid_color",
+            Self::SizeOutOfRange { .. } => "size_out_of_range",
+            Self::InvalidWinCondition { .. } => "invalid_win_condition",
+            Self::NoWinLines { .. } => "no_win_lines",
+            Self::GenerationFailed { .. } => "generation_failed",
+            Self::Unsolvable => "unsolvable",
+            Self::CellOutOfRange { .. } => "cell_out_of_range",
+            Self::FixedCellExcluded { .. } => "fixed_cell_excluded",
+            Self::MaskTooLarge { .. } => "mask_too_large",
+            Self::MaskOutOfRange { .. } => "mask_out_of_range",
+            Self::InvalidDate { .. } => "invalid_date",
+            Self::InvalidDateField { .. } => "invalid_date_field",
+            Self::DateBeforeEpoch { .. } => "date_before_epoch",
+            Self::BudgetNotObject => "budget_not_object",
+            Self::InvalidBudgetNumber { .. } => "invalid_budget_number",
+            Self::InvalidProgressCallback => "invalid_progress_callback",
+            Self::InvalidDepth { .. } => "invalid_depth",
+            Self::InvalidGenerateOptions { .. } => "invalid_generate_options",
+            Self::InvalidLocale => "invalid_locale",
+            Self::Serialization { .. } => "serialization",
+        }
+    }
+
+    /// 可本地化的错误信息（zh-CN 渲染与 `Display` 一致）。
+    pub fn message(&self) -> Message {
+        match self {
+            Self::GridNotSquare { actual, max } => {
+                msg!("error.grid.square", actual = *actual, max = *max)
+            }
+            Self::InvalidColor { index, value } => {
+                msg!("error.color", index = *index, value = *value)
+            }
+            Self::SizeOutOfRange { actual, min, max } => {
+                msg!("error.size", actual = *actual, min = *min, max = *max)
+            }
+            Self::InvalidWinCondition { detail } => {
+                msg!("error.win.parse", detail = detail.clone())
+            }
+            Self::NoWinLines { size, win } => msg!(
+                "error.win.no_segments",
+                size = *size,
+                win = format!("{win:?}")
+            ),
+            Self::GenerationFailed { seed, attempts } => msg!(
+                "error.generate.no_puzzle",
+                seed = seed.clone(),
+                attempts = *attempts
+            ),
+            Self::Unsolvable => msg!("error.difficulty.no_solution"),
+            Self::CellOutOfRange { index, cell_count } => {
+                msg!("error.cell", index = *index, cell_count = *cell_count)
+            }
+            Self::FixedCellExcluded { index } => msg!("error.cell.fixed", index = *index),
+            Self::MaskTooLarge { name, actual, max } => msg!(
+                "error.mask.capacity",
+                name = name.to_string(),
+                actual = *actual,
+                max = *max
+            ),
+            Self::MaskOutOfRange {
+                name,
+                index,
+                cell_count,
+            } => msg!(
+                "error.mask.cell",
+                name = name.to_string(),
+                index = *index,
+                cell_count = *cell_count
+            ),
+            Self::InvalidDate { value } => msg!("error.date.format", value = value.clone()),
+            Self::InvalidDateField { field, value } => msg!(
+                "error.date.field",
+                field = field.to_string(),
+                value = value.clone()
+            ),
+            Self::DateBeforeEpoch { value } => {
+                msg!("error.date.before_epoch", value = value.clone())
+            }
+            Self::BudgetNotObject => msg!("error.budget.object"),
+            Self::InvalidBudgetNumber { field } => {
+                msg!("error.budget.number", field = field.to_string())
+            }
+            Self::InvalidProgressCallback => msg!("error.budget.on_progress"),
+            Self::InvalidDepth { detail } => msg!("error.depth.parse", detail = detail.clone()),
+            Self::InvalidGenerateOptions { detail } => {
+                msg!("error.generate.options", detail = detail.clone())
+            }
+            Self::InvalidLocale => msg!("error.locale"),
+            Self::Serialization { detail } => msg!("error.serialize", detail = detail.clone()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn samples() -> Vec<EngineError> {
+        vec![
+            EngineError::GridNotSquare { actual: 7, max: 16 },
+            EngineError::InvalidColor {
+                index: 3,
+                value: 42,
+            },
+            EngineError::SizeOutOfRange {
+                actual: 3,
+                min: 5,
+                max: 16,
+            },
+            EngineError::InvalidWinCondition {
+                detail: "unknown variant".to_string(),
+            },
+            EngineError::NoWinLines {
+                size: 5,
+                win: WinCondition::InARow {
+                    length: 6,
+                    diagonals: true,
+                },
+            },
+            EngineError::GenerationFailed {
+                seed: u64::MAX.to_string(),
+                attempts: 9,
+            },
+            EngineError::Unsolvable,
+            EngineError::CellOutOfRange {
+                index: 30,
+                cell_count: 25,
+            },
+            EngineError::FixedCellExcluded { index: 6 },
+            EngineError::MaskTooLarge {
+                name: "checked_mask",
+                actual: 40,
+                max: 32,
+            },
+            EngineError::MaskOutOfRange {
+                name: "unchecked_mask",
+                index: 27,
+                cell_count: 25,
+            },
+            EngineError::InvalidDate {
+                value: "2025/1/1".to_string(),
+            },
+            EngineError::InvalidDateField {
+                field: "month",
+                value: "13".to_string(),
+            },
+            EngineError::DateBeforeEpoch {
+                value: "1969-12-31".to_string(),
+            },
+            EngineError::BudgetNotObject,
+            EngineError::InvalidBudgetNumber { field: "maxNodes" },
+            EngineError::InvalidProgressCallback,
+            EngineError::InvalidDepth {
+                detail: "unknown variant".to_string(),
+            },
+            EngineError::InvalidGenerateOptions {
+                detail: "invalid type".to_string(),
+            },
+            EngineError::InvalidLocale,
+            EngineError::Serialization {
+                detail: "oops".to_string(),
+            },
+        ]
+    }
+
+    #[test]
+    fn serializes_code_and_details() {
+        let e = EngineError::InvalidColor {
+            index: 3,
+            value: 42,
+        };
+        assert_eq!(
+            serde_json::to_value(&e).unwrap(),
+            serde_json::json!({"code": "invalid_color", "details": {"index": 3, "value": 42}})
+        );
+        assert_eq!(
+            serde_json::to_value(EngineError::Unsolvable).unwrap(),
+            serde_json::json!({"code": "unsolvable"})
+        );
+        for e in samples() {
+            let v = serde_json::to_value(&e).unwrap();
+            assert_eq!(v["code"], e.code(), "{e:?}");
+        }
+    }
+
+    #[test]
+    fn messages_match_display_and_details() {
+        for e in samples() {
+            let m = e.message();
+            assert_eq!(m.zh(), e.to_string());
+            // 消息参数与 details 字段同名，前端可以直接用 details 套自己的文案。
+            let details = serde_json::to_value(&e).unwrap()["details"].clone();
+            for (name, _) in &m.params {
+                assert!(details.get(*name).is_some(), "{}: {name}", e.code());
+            }
+        }
+    }
+}
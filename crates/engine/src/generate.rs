@@ -1,93 +1,116 @@
 use rand::{seq::SliceRandom, Rng, SeedableRng};
 use rand_chacha::ChaCha8Rng;
-use thiserror::Error;
+use serde::Deserialize;
+
+use crate::colors::Color;
+use crate::error::EngineError;
+use crate::masks::{Neighborhood, MAX_GRID_SIZE};
+use crate::solver::{clue_colors, registry, Filled, Solver};
+use crate::win::WinCondition;
+
+/// 生成器支持的边长：`5..=MAX_GRID_SIZE`（WASM 入口在解析胜利条件之前先用它校验）。
+pub(crate) fn check_size(size: usize) -> Result<(), EngineError> {
+    if !(5..=MAX_GRID_SIZE).contains(&size) {
+        return Err(EngineError::SizeOutOfRange {
+            actual: size,
+            min: 5,
+            max: MAX_GRID_SIZE,
+        });
+    }
+    Ok(())
+}
 
-use crate::colors::{Color, NON_WHITE_COLORS};
-use crate::masks::{cell_index, CELL_COUNT, GRID_SIZE};
-use crate::solver::Solver;
+/// 生成器选项。
+///
+/// 默认值与加入数字格之前的生成器一致：同一 seed 得到的题目（含按日期存档的每日一题）保持不变。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct GenerateOptions {
+    /// 是否出数字格（N 取目标解中该格八邻的勾选数）；开启后同一 seed 得到的是另一道题。
+    pub number_clues: bool,
+}
 
-#[derive(Debug, Error)]
-pub enum GenerateError {
-    #[error("在限制次数内未找到至少 1 个解的题目（seed={seed}, attempts={attempts}）")]
-    NoSatisfiablePuzzle { seed: u64, attempts: usize },
+/// 默认选项下的 [`generate_puzzle_with`]（测试用）。
+#[cfg(test)]
+pub(crate) fn generate_puzzle(
+    seed: u64,
+    size: usize,
+    win: WinCondition,
+) -> Result<Vec<Vec<u8>>, EngineError> {
+    generate_puzzle_with(seed, size, win, GenerateOptions::default())
 }
 
 /// 生成指定尺寸的颜色布局（u8），采用“反向构造”算法：
-/// 1. 随机生成一个满足“五连线”的目标解 (Target Mask)。
+/// 1. 随机生成一个满足胜利条件（默认“五连线”）的目标解 (Target Mask)。
 /// 2. 根据目标解反推每个格子可用的颜色。
 /// 3. 随机挑选 3~5 个格子设为白色。
-pub fn generate_puzzle(seed: u64, size: usize) -> Result<Vec<Vec<u8>>, GenerateError> {
+///
+/// 数字格只在 `options.number_clues` 开启时出现。
+pub fn generate_puzzle_with(
+    seed: u64,
+    size: usize,
+    win: WinCondition,
+    options: GenerateOptions,
+) -> Result<Vec<Vec<u8>>, EngineError> {
     use crate::masks::BoardMasks;
 
+    check_size(size)?;
+
     let bm = BoardMasks::new(size);
+    let win_lines = win.segment_masks(size);
+    if win_lines.is_empty() {
+        return Err(EngineError::NoWinLines { size, win });
+    }
     let cell_count = bm.cell_count;
     let mut rng = ChaCha8Rng::seed_from_u64(seed);
     let max_attempts = 2000usize;
+    // 关闭数字格时候选列表与旧版完全相同，随机数序列也就不变。
+    let offered = |color: Color| options.number_clues || !matches!(color, Color::Number(_));
 
     for _attempt in 0..max_attempts {
         // --- 步骤 1: 构造目标解 ---
-        let mut target_mask = *bm.line_masks.choose(&mut rng).unwrap();
+        let mut target_mask = *win_lines.choose(&mut rng).unwrap();
         for i in 0..cell_count {
-            if (target_mask & (1 << i)) == 0 && rng.gen_bool(0.4) {
-                target_mask |= 1 << i;
+            if !target_mask.contains(i) && rng.gen_bool(0.4) {
+                target_mask.insert(i);
             }
         }
 
-        let mut row_counts = vec![0u8; size];
-        let mut col_counts = vec![0u8; size];
-        for r in 0..size {
-            row_counts[r] = (target_mask & bm.row_masks[r]).count_ones() as u8;
-        }
-        for c in 0..size {
-            col_counts[c] = (target_mask & bm.col_masks[c]).count_ones() as u8;
-        }
-        let diag_count = bm.diag_down_masks.len();
-        let mut diag_down_counts = vec![0u8; diag_count];
-        let mut diag_up_counts = vec![0u8; diag_count];
-        for d in 0..diag_count {
-            diag_down_counts[d] = (target_mask & bm.diag_down_masks[d]).count_ones() as u8;
-            diag_up_counts[d] = (target_mask & bm.diag_up_masks[d]).count_ones() as u8;
-        }
+        let board = Filled::new(size, &bm, target_mask);
 
         // --- 步骤 2: 反向填充颜色 (多解融合) ---
         let mut colors = vec![Color::White; cell_count];
-        for i in 0..cell_count {
-            let x = i / size;
-            let y = i % size;
-            let is_checked = (target_mask & (1 << i)) != 0;
-
-            let mut candidates = Vec::with_capacity(8);
-            let n8_count = (target_mask & bm.neighbors_8[i]).count_ones();
-
-            if n8_count >= 1 { candidates.push(Color::Red); }
-            if n8_count <= 2 { candidates.push(Color::Blue); }
-            if row_counts[x] == col_counts[y] { candidates.push(Color::Green); }
-            let d_down = x + (size - 1) - y;
-            let d_up = x + y;
-            if diag_down_counts[d_down] == diag_up_counts[d_up] { candidates.push(Color::Yellow); }
-            if n8_count % 2 == 1 { candidates.push(Color::Purple); }
-            if n8_count % 2 == 0 { candidates.push(Color::Orange); }
-            if !is_checked || (target_mask & bm.neighbors_4[i]).count_ones() >= 1 { candidates.push(Color::Cyan); }
-            if is_checked { candidates.push(Color::Black); }
-
-            if candidates.is_empty() {
-                colors[i] = Color::White;
+        for (i, color) in colors.iter_mut().enumerate() {
+            // 候选颜色按注册表顺序列出（顺序决定同一 seed 的结果）；变体格不自动出。
+            let candidates: Vec<Color> = registry()
+                .iter()
+                .filter(|rule| {
+                    !rule.is_variant() && offered(rule.color()) && rule.fits(&board, i)
+                })
+                .map(|rule| rule.color())
+                .collect();
+
+            if candidates.is_empty() || rng.gen_bool(0.1) {
+                *color = Color::White;
             } else {
-                if rng.gen_bool(0.1) {
-                    colors[i] = Color::White;
-                } else {
-                    colors[i] = *candidates.choose(&mut rng).unwrap();
-                }
+                *color = *candidates.choose(&mut rng).unwrap();
             }
         }
 
         // --- 步骤 3: 扰动填充 ---
+        // 先等概率挑规则族；抽到数字格时 N 取目标解中该格八邻的勾选数。
+        let clue_colors: Vec<Color> = clue_colors().into_iter().filter(|&c| offered(c)).collect();
         for _ in 0..5 {
             let idx = rng.gen_range(0..cell_count);
             let old_color = colors[idx];
-            let new_color = *NON_WHITE_COLORS.choose(&mut rng).unwrap();
+            let new_color = match *clue_colors.choose(&mut rng).unwrap() {
+                Color::Number(_) => Color::Number(
+                    (target_mask & bm.neighbors(Neighborhood::King, idx)).count_ones() as u8,
+                ),
+                color => color,
+            };
             colors[idx] = new_color;
-            let solver = Solver::new(size, colors.clone());
+            let solver = Solver::new(size, colors.clone(), win);
             if solver.solve_masks_limit(1).is_empty() {
                 colors[idx] = old_color;
             }
@@ -105,7 +128,7 @@ pub fn generate_puzzle(seed: u64, size: usize) -> Result<Vec<Vec<u8>>, GenerateE
         apply_symmetry(&mut colors, &mut rng, size);
 
         // --- 步骤 6: 校验解的存在性 ---
-        let solver = Solver::new(size, colors.clone());
+        let solver = Solver::new(size, colors.clone(), win);
         if !solver.solve_masks_limit(1).is_empty() {
             let mut grid: Vec<Vec<u8>> = Vec::with_capacity(size);
             for x in 0..size {
@@ -119,8 +142,8 @@ pub fn generate_puzzle(seed: u64, size: usize) -> Result<Vec<Vec<u8>>, GenerateE
         }
     }
 
-    Err(GenerateError::NoSatisfiablePuzzle {
-        seed,
+    Err(EngineError::GenerationFailed {
+        seed: seed.to_string(),
         attempts: max_attempts,
     })
 }
@@ -148,3 +171,39 @@ fn apply_symmetry(colors: &mut Vec<Color>, rng: &mut ChaCha8Rng, size: usize) {
     }
     *colors = new_colors;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_options_keep_existing_seeds() {
+        // 每日一题按日期存档：默认选项下同一 seed 的题目不能变。
+        let grid = generate_puzzle(0, 5, WinCondition::default()).unwrap();
+        assert_eq!(
+            grid,
+            [
+                [1, 4, 0, 6, 6],
+                [8, 6, 2, 5, 1],
+                [7, 0, 6, 7, 3],
+                [8, 6, 7, 0, 4],
+                [1, 8, 6, 2, 8],
+            ]
+        );
+    }
+
+    #[test]
+    fn number_clues_are_opt_in() {
+        let has_number = |options| {
+            (0..10).any(|seed| {
+                generate_puzzle_with(seed, 5, WinCondition::default(), options)
+                    .unwrap()
+                    .into_iter()
+                    .flatten()
+                    .any(|v| matches!(Color::from_u8(v), Some(Color::Number(_))))
+            })
+        };
+        assert!(!has_number(GenerateOptions::default()));
+        assert!(has_number(GenerateOptions { number_clues: true }));
+    }
+}
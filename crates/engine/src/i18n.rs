@@ -0,0 +1,688 @@
+//! 本地化：引擎输出的所有文字都先构造成“消息键 + 类型化参数”（[`Message`]），再按语言渲染。
+//!
+//! - 键稳定（如 `hint.forced.check {row, col}`），前端可以直接按键自行翻译；
+//! - 内置 zh-CN 与 en 两份目录；zh-CN 的渲染结果与引擎历来输出的文字一致；
+//! - 结果结构体里的字符串字段默认是 zh-CN 渲染，WASM 导出按 `locale` 参数调用 [`Localize`] 重新渲染。
+//!
+//! 模板中的 `{name}` 由同名参数替换；参数可以是数字、原样文本、嵌套消息或消息列表（列表用分隔符消息连接）。
+
+use serde::ser::{SerializeMap, SerializeSeq};
+use serde::{Serialize, Serializer};
+
+/// 渲染语言。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Locale {
+    /// 简体中文（默认）。
+    #[default]
+    ZhCn,
+    /// 英文。
+    En,
+}
+
+impl Locale {
+    /// 按 BCP 47 语言标签选择目录：`zh*` 为中文，其余语言（目录里还没有的）退回英文。
+    pub fn from_tag(tag: &str) -> Self {
+        if tag.to_ascii_lowercase().starts_with("zh") {
+            Self::ZhCn
+        } else {
+            Self::En
+        }
+    }
+
+    fn catalog(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Self::ZhCn => ZH_CN,
+            Self::En => EN,
+        }
+    }
+}
+
+/// 一条待渲染的消息：稳定的键与类型化参数。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    pub key: &'static str,
+    pub params: Vec<(&'static str, Param)>,
+}
+
+/// 消息参数。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Param {
+    Int(i64),
+    /// 原样插入、不翻译的文本（如解析错误的细节）。
+    Text(String),
+    Msg(Message),
+    /// 依次渲染后用分隔符消息（`sep` 为其键）连接。
+    List {
+        sep: &'static str,
+        items: Vec<Message>,
+    },
+}
+
+impl From<usize> for Param {
+    fn from(v: usize) -> Self {
+        Self::Int(v as i64)
+    }
+}
+
+impl From<u32> for Param {
+    fn from(v: u32) -> Self {
+        Self::Int(v as i64)
+    }
+}
+
+impl From<u8> for Param {
+    fn from(v: u8) -> Self {
+        Self::Int(v as i64)
+    }
+}
+
+impl From<String> for Param {
+    fn from(v: String) -> Self {
+        Self::Text(v)
+    }
+}
+
+impl From<Message> for Param {
+    fn from(v: Message) -> Self {
+        Self::Msg(v)
+    }
+}
+
+impl Param {
+    /// 消息列表（`sep` 为分隔符消息的键，如 `sep.comma`）。
+    pub fn list(sep: &'static str, items: Vec<Message>) -> Self {
+        Self::List { sep, items }
+    }
+}
+
+/// 构造消息：`msg!("hint.forced.check", row = 1, col = 2)`。
+macro_rules! msg {
+    ($key:literal $(, $name:ident = $value:expr)* $(,)?) => {
+        $crate::i18n::Message::new($key)$(.with(stringify!($name), $value))*
+    };
+}
+pub(crate) use msg;
+
+impl Message {
+    pub fn new(key: &'static str) -> Self {
+        Self {
+            key,
+            params: Vec::new(),
+        }
+    }
+
+    pub fn with(mut self, name: &'static str, value: impl Into<Param>) -> Self {
+        self.params.push((name, value.into()));
+        self
+    }
+
+    /// 按 `locale` 渲染；目录里没有的键退回 zh-CN，仍没有则原样输出键。
+    pub fn render(&self, locale: Locale) -> String {
+        let template = lookup(locale, self.key)
+            .or_else(|| lookup(Locale::ZhCn, self.key))
+            .unwrap_or(self.key);
+        let mut out = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            out.push_str(&rest[..start]);
+            let Some(len) = rest[start..].find('}') else {
+                break;
+            };
+            let name = &rest[start + 1..start + len];
+            match self.params.iter().find(|(n, _)| *n == name) {
+                Some((_, param)) => param.render_into(locale, &mut out),
+                None => out.push_str(&rest[start..=start + len]),
+            }
+            rest = &rest[start + len + 1..];
+        }
+        out.push_str(rest);
+        out
+    }
+
+    /// zh-CN 渲染（结构体里字符串字段的默认值）。
+    pub fn zh(&self) -> String {
+        self.render(Locale::ZhCn)
+    }
+}
+
+impl Param {
+    fn render_into(&self, locale: Locale, out: &mut String) {
+        match self {
+            Self::Int(v) => out.push_str(&v.to_string()),
+            Self::Text(s) => out.push_str(s),
+            Self::Msg(m) => out.push_str(&m.render(locale)),
+            Self::List { sep, items } => {
+                let sep = Message::new(sep).render(locale);
+                let parts: Vec<String> = items.iter().map(|m| m.render(locale)).collect();
+                out.push_str(&parts.join(&sep));
+            }
+        }
+    }
+}
+
+fn lookup(locale: Locale, key: &str) -> Option<&'static str> {
+    locale
+        .catalog()
+        .iter()
+        .find(|(k, _)| *k == key)
+        .map(|(_, v)| *v)
+}
+
+/// 序列化为 `{key, params?}`，参数为对象（数字 / 字符串 / 嵌套消息 / 消息数组）。
+impl Serialize for Message {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        struct Params<'a>(&'a [(&'static str, Param)]);
+        impl Serialize for Params<'_> {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                let mut map = serializer.serialize_map(Some(self.0.len()))?;
+                for (name, value) in self.0 {
+                    map.serialize_entry(name, value)?;
+                }
+                map.end()
+            }
+        }
+
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("key", self.key)?;
+        if !self.params.is_empty() {
+            map.serialize_entry("params", &Params(&self.params))?;
+        }
+        map.end()
+    }
+}
+
+impl Serialize for Param {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Int(v) => serializer.serialize_i64(*v),
+            Self::Text(s) => serializer.serialize_str(s),
+            Self::Msg(m) => m.serialize(serializer),
+            Self::List { items, .. } => {
+                let mut seq = serializer.serialize_seq(Some(items.len()))?;
+                for item in items {
+                    seq.serialize_element(item)?;
+                }
+                seq.end()
+            }
+        }
+    }
+}
+
+/// 按语言重新渲染结构体里的文字字段（依据同一结构体里保存的消息键）。
+pub trait Localize {
+    fn localize(&mut self, locale: Locale);
+}
+
+impl<T: Localize> Localize for Option<T> {
+    fn localize(&mut self, locale: Locale) {
+        if let Some(v) = self {
+            v.localize(locale);
+        }
+    }
+}
+
+impl<T: Localize> Localize for Vec<T> {
+    fn localize(&mut self, locale: Locale) {
+        for v in self {
+            v.localize(locale);
+        }
+    }
+}
+
+/// zh-CN 目录：与引擎历来输出的文字保持一致。
+const ZH_CN: &[(&str, &str)] = &[
+    // 规则
+    ("rule.green", "绿格（行列计数相等）"),
+    ("rule.yellow", "黄格（对角线计数相等）"),
+    ("rule.red", "红格（八邻至少一格勾选）"),
+    ("rule.blue", "蓝格（八邻最多两格勾选）"),
+    ("rule.purple", "紫格（八邻勾选数为奇数）"),
+    ("rule.orange", "橙格（八邻勾选数为偶数）"),
+    ("rule.cyan", "青格（四邻约束）"),
+    ("rule.black", "黑格（必须勾选）"),
+    ("rule.number", "数字格（八邻恰好 {n} 格勾选）"),
+    ("rule.red.hood", "红格（{hood}至少一格勾选）"),
+    ("rule.blue.hood", "蓝格（{hood}最多两格勾选）"),
+    ("rule.purple.hood", "紫格（{hood}勾选数为奇数）"),
+    ("rule.orange.hood", "橙格（{hood}勾选数为偶数）"),
+    ("rule.parity", "紫/橙格奇偶联立"),
+    ("rule.count_chain", "绿/黄格计数相等链"),
+    ("rule.five_in_row", "五连线（Bingo）"),
+    ("rule.generic", "规则"),
+    ("rule.short.red", "红格"),
+    ("rule.short.blue", "蓝格"),
+    ("rule.short.black", "黑格"),
+    ("rule.short.green", "绿格"),
+    ("rule.short.yellow", "黄格"),
+    ("rule.short.purple", "紫格"),
+    ("rule.short.orange", "橙格"),
+    ("rule.short.cyan", "青格"),
+    ("rule.short.number", "数字格"),
+    ("rule.short.variant", "{rule}（{hood}）"),
+    ("rule.short.bingo", "连线"),
+    ("hood.king", "八邻"),
+    ("hood.orthogonal", "四邻"),
+    ("hood.knight", "马步格"),
+    ("hood.line2", "同行同列两格内"),
+    ("hood.diagonal", "斜向四邻"),
+    ("win.five", "五连线"),
+    ("win.full_line", "整行/整列连线"),
+    ("win.in_a_row", "{length} 连线"),
+    // 格子与计数变量
+    ("cell.coord", "({row},{col})"),
+    ("cell.checked", "({row},{col}) 勾选"),
+    ("cell.unchecked", "({row},{col}) 不勾选"),
+    ("count.row", "第 {row} 行"),
+    ("count.col", "第 {col} 列"),
+    ("count.diag_down", "过 ({row},{col}) 的 \\ 对角线"),
+    ("count.diag_up", "过 ({row},{col}) 的 / 对角线"),
+    ("sep.comma", "，"),
+    ("sep.enum", "、"),
+    ("sep.equals", " = "),
+    // 提示
+    ("hint.forced.check", "根据当前信息可推出：({row},{col}) 必须勾选。"),
+    ("hint.forced.uncheck", "根据当前信息可推出：({row},{col}) 必须不勾选。"),
+    ("hint.conclude.check", "结论：({row},{col}) 必须勾选。"),
+    ("hint.conclude.uncheck", "结论：({row},{col}) 必须不勾选。"),
+    ("hint.contradiction.check", "通过反证可推出：({row},{col}) 必须勾选。"),
+    ("hint.contradiction.uncheck", "通过反证可推出：({row},{col}) 必须不勾选。"),
+    ("hint.contradiction.assume", "反证：如果选择相反操作，会在传播中走到矛盾/无解。"),
+    ("hint.contradiction.conclude.check", "结论：相反取值必然矛盾，因此 ({row},{col}) 必须勾选。"),
+    ("hint.contradiction.conclude.uncheck", "结论：相反取值必然矛盾，因此 ({row},{col}) 必须不勾选。"),
+    ("hint.suggest", "给出一个可能有帮助的下一步：尝试勾选 ({row},{col})。"),
+    ("hint.suggest.goal", "目标：达成至少一条{line}（Bingo）。"),
+    ("hint.suggest.step", "建议：先勾选 ({row},{col}) 作为推进方向（不保证唯一）。"),
+    ("hint.no_step", "当前勾选已能补全成解，但暂无可直接给出的“一步提示”。"),
+    ("hint.exhausted.cancelled", "提示计算已取消。"),
+    ("hint.exhausted.time", "提示计算超时：当前局面过于复杂，请先多确定一些格子再试。"),
+    ("hint.exhausted.budget", "提示计算超出搜索预算：当前局面过于复杂，请先多确定一些格子再试。"),
+    ("hint.repair.one", "当前勾选无法补全成解。建议先取消勾选：{cell}"),
+    ("hint.repair.many", "当前勾选无法补全成解。至少需要取消 {count} 处勾选：{cells}"),
+    ("hint.repair.exhausted", "当前勾选无法补全成解：请尝试撤销部分勾选或排除标记后再求提示。"),
+    ("hint.repair.exclusions", "即使不考虑勾选也无解：请撤销部分排除标记后再求提示。"),
+    ("hint.repair.too_many", "当前勾选无法补全成解：标出的 {count} 处勾选互相冲突，且需要撤销的勾选超过 {max} 处，请撤销多处勾选或排除标记后再求提示。"),
+    ("hint.parity.odd", "约束：({row},{col}) 的{hood}勾选数为奇数。"),
+    ("hint.parity.even", "约束：({row},{col}) 的{hood}勾选数为偶数。"),
+    ("hint.parity.combine", "联立：把这 {count} 条奇偶约束相加，公共邻格两两抵消，只剩 ({row},{col}) 未定。"),
+    ("hint.count_chain.equation", "等式：({row},{col}) 要求 {a} 与 {b} 的勾选数相等。"),
+    ("hint.count_chain.chain", "串联：{vars} 的勾选数全部相等，取各自可行范围的交集。"),
+    ("hint.bingo.forced", "推导：仍可能完成的{line}都经过 ({row},{col})，要达成 Bingo 它必须勾选。"),
+    ("hint.propagate.rule", "推导：{rule} 触发了传播。"),
+    ("hint.derive", "推导：{rule} ⇒ {conclusions}。"),
+    ("justify.givens", "前提：已确定的 {count} 格（黑格与已勾选/排除的格子）。"),
+    ("justify.omitted", "（省略更早的 {count} 步推导）"),
+    ("refute.assume", "假设：{assignment}。"),
+    ("refute.no_line", "矛盾：已没有可能完成的{line}。"),
+    ("refute.violated", "矛盾：{rule} 无法满足。"),
+    ("refute.stuck", "矛盾：传播无法继续。"),
+    // 修复
+    ("repair.unsolvable", "当前勾选已无解：建议先回到“可解状态”再继续推理。"),
+    ("repair.conflict", "冲突：这 {count} 处勾选放在一起就无解（少任何一处都不矛盾）。"),
+    ("repair.via", "经由：{rule}"),
+    ("repair.count", "至少需要改动 {count} 处才能恢复可解："),
+    ("repair.uncheck", "尝试取消勾选：({row},{col})（可恢复到可解状态）"),
+    ("repair.release", "尝试把排除的 ({row},{col}) 改为勾选（可恢复到可解状态）"),
+    // 批量推导
+    ("deduce.rule", "由{rule}直接推出。"),
+    ("deduce.propagate", "由约束传播直接推出。"),
+    ("deduce.probe.check", "反证：假设 {cell} 勾选会传播出矛盾。"),
+    ("deduce.probe.uncheck", "反证：假设 {cell} 不勾选会传播出矛盾。"),
+    ("deduce.probe.follow", "由 {cell} 的反证结论继续传播推出。"),
+    ("deduce.backbone", "所有解中取值都相同（需要多步假设才能推出）。"),
+    // 技巧
+    ("technique.red_last_neighbour", "红格：最后一个邻居"),
+    ("technique.red_last_neighbour.desc", "红格八邻还没有勾选、只剩一格未定时，这一格必须勾选。"),
+    ("technique.blue_saturation", "蓝格饱和"),
+    ("technique.blue_saturation.desc", "蓝格八邻已有两格勾选时，其余邻居都不能勾选。"),
+    ("technique.cyan_last_neighbour", "青格：最后一个邻居"),
+    ("technique.cyan_last_neighbour.desc", "已勾选的青格四邻还没有勾选、只剩一格未定时，这一格必须勾选。"),
+    ("technique.cyan_isolated", "青格孤立"),
+    ("technique.cyan_isolated.desc", "青格四邻全部不勾选时，青格自身不能勾选。"),
+    ("technique.number_count", "数字计数"),
+    ("technique.number_count.desc", "数字格八邻已有 N 格勾选时其余邻居都不勾选；未知邻居恰好补足 N 格时全部勾选。"),
+    ("technique.purple_last_neighbour", "紫格奇数补齐"),
+    ("technique.purple_last_neighbour.desc", "紫格八邻只剩一格未定时，按勾选数为奇数决定这一格。"),
+    ("technique.orange_last_neighbour", "橙格偶数补齐"),
+    ("technique.orange_last_neighbour.desc", "橙格八邻只剩一格未定时，按勾选数为偶数决定这一格。"),
+    ("technique.green_squeeze", "绿格行列夹逼"),
+    ("technique.green_squeeze.desc", "绿格所在行与列的勾选数相等；一方的上限等于另一方的下限时，两条线都被卡死。"),
+    ("technique.yellow_squeeze", "黄格对角线夹逼"),
+    ("technique.yellow_squeeze.desc", "黄格的两条对角线勾选数相等；一条的上限等于另一条的下限时，两条线都被卡死。"),
+    ("technique.bingo_common_cells", "连线公共格"),
+    ("technique.bingo_common_cells.desc", "仍可能完成的连线都经过同一格时，这一格必须勾选。"),
+    ("technique.count_chain", "计数相等链"),
+    ("technique.count_chain.desc", "多个绿/黄格把几条线串成勾选数全部相等的一组，取各自可行范围的交集。"),
+    ("technique.parity_pair", "奇偶对"),
+    ("technique.parity_pair.desc", "两个紫/橙格的奇偶约束相加，公共邻格抵消后只剩一格未定。"),
+    ("technique.parity_chain", "奇偶链"),
+    ("technique.parity_chain.desc", "三个以上紫/橙格的奇偶约束联立消元，只剩一格未定。"),
+    ("technique.single_assumption_contradiction", "单步反证"),
+    ("technique.single_assumption_contradiction.desc", "假设某格取一值，传播下去出现矛盾，因此它只能取另一值。"),
+    // 分级提示
+    ("region.top_left", "左上角"),
+    ("region.top", "上方"),
+    ("region.top_right", "右上角"),
+    ("region.left", "左侧"),
+    ("region.center", "中央"),
+    ("region.right", "右侧"),
+    ("region.bottom_left", "左下角"),
+    ("region.bottom", "下方"),
+    ("region.bottom_right", "右下角"),
+    ("region.board", "整个棋盘"),
+    ("graded.propagate.nudge", "留意{region}一带的{rule}，那里已经能推出确定的结论。"),
+    ("graded.propagate.focus", "看看高亮的{rule}：结合已确定的格子，它能直接推出一格。"),
+    ("graded.contradiction.nudge", "{region}一带有一格只能取一种值：试着假设相反取值，看{rule}会不会矛盾。"),
+    ("graded.contradiction.focus", "假设目标格取相反值后，高亮的{rule}会一路推出矛盾。"),
+    ("graded.suggest.nudge", "暂无必然结论：可以朝{region}一带的连线推进。"),
+    ("graded.suggest.focus", "高亮的这条线仍有可能完成，可以沿着它尝试。"),
+    ("graded.repair.nudge", "当前勾选已经无解：检查与{rule}有关的勾选。"),
+    ("graded.repair.focus", "高亮的 {count} 处勾选互相冲突，需要撤销其中一部分。"),
+    ("graded.target.forced", "关键在 ({row},{col})：它的取值已经确定。"),
+    ("graded.target.suggest", "可以从 ({row},{col}) 入手。"),
+    ("graded.target.repair", "考虑撤销 ({row},{col}) 的勾选。"),
+    // 校验
+    ("validate.black", "黑格必须被勾选"),
+    ("validate.red", "周围需至少 1 个勾选，当前为 {count}"),
+    ("validate.blue", "周围勾选不得超过 2 个，当前为 {count}"),
+    ("validate.green", "行勾选({row}) 与 列勾选({col}) 不相等"),
+    ("validate.yellow", "两条对角线勾选数不相等 ({down} vs {up})"),
+    ("validate.purple", "周围勾选数需为奇数，当前为 {count}"),
+    ("validate.orange", "周围勾选数需为偶数，当前为 {count}"),
+    ("validate.cyan", "被勾选时，上下左右需至少 1 个勾选，当前为 {count}"),
+    ("validate.number", "周围需恰好 {n} 个勾选，当前为 {count}"),
+    // 错误
+    ("error.grid.square", "color_grid 必须是边长 1..={max} 的正方形，得到长度：{actual}"),
+    ("error.color", "color_grid 含非法颜色编码：index={index}, value={value}"),
+    ("error.size", "size 必须在 {min}..={max}，得到：{actual}"),
+    ("error.date.format", "日期格式错误：期望 YYYY-MM-DD，得到：{value}"),
+    ("error.date.field", "日期字段非法：{field}={value}"),
+    ("error.date.before_epoch", "日期早于 1970-01-01：{value}"),
+    ("error.difficulty.no_solution", "该题目无解，无法计算难度分"),
+    ("error.generate.no_puzzle", "在限制次数内未找到至少 1 个解的题目（seed={seed}, attempts={attempts}）"),
+    ("error.win.no_segments", "胜利条件在 {size}x{size} 棋盘上没有任何可行连线：{win}"),
+    ("error.win.parse", "win_condition 解析失败：{detail}"),
+    ("error.cell", "格子下标越界：cell={index}, 共 {cell_count} 格"),
+    ("error.cell.fixed", "黑格固定勾选，不能排除：cell={index}"),
+    ("error.budget.object", "budget 必须是对象"),
+    ("error.budget.number", "budget.{field} 必须是非负数字"),
+    ("error.budget.on_progress", "budget.onProgress 必须是函数"),
+    ("error.mask.capacity", "{name} 超出棋盘容量：得到 {actual} 字节，最多 {max} 字节"),
+    ("error.mask.cell", "{name} 含棋盘外的格子：index={index}, 共 {cell_count} 格"),
+    ("error.depth.parse", "depth 解析失败：{detail}"),
+    ("error.generate.options", "生成选项解析失败：{detail}"),
+    ("error.locale", "locale 必须是字符串"),
+    ("error.serialize", "结果序列化失败：{detail}"),
+];
+
+/// en 目录。
+const EN: &[(&str, &str)] = &[
+    // Rules
+    ("rule.green", "Green (equal row and column counts)"),
+    ("rule.yellow", "Yellow (equal diagonal counts)"),
+    ("rule.red", "Red (at least one of 8 neighbours checked)"),
+    ("rule.blue", "Blue (at most two of 8 neighbours checked)"),
+    ("rule.purple", "Purple (odd number of 8 neighbours checked)"),
+    ("rule.orange", "Orange (even number of 8 neighbours checked)"),
+    ("rule.cyan", "Cyan (4-neighbour constraint)"),
+    ("rule.black", "Black (must be checked)"),
+    ("rule.number", "Number (exactly {n} of 8 neighbours checked)"),
+    ("rule.red.hood", "Red (at least one of its {hood} checked)"),
+    ("rule.blue.hood", "Blue (at most two of its {hood} checked)"),
+    ("rule.purple.hood", "Purple (odd number of its {hood} checked)"),
+    ("rule.orange.hood", "Orange (even number of its {hood} checked)"),
+    ("rule.parity", "combined Purple/Orange parity"),
+    ("rule.count_chain", "chain of equal Green/Yellow counts"),
+    ("rule.five_in_row", "five in a row (Bingo)"),
+    ("rule.generic", "a rule"),
+    ("rule.short.red", "Red cell"),
+    ("rule.short.blue", "Blue cell"),
+    ("rule.short.black", "Black cell"),
+    ("rule.short.green", "Green cell"),
+    ("rule.short.yellow", "Yellow cell"),
+    ("rule.short.purple", "Purple cell"),
+    ("rule.short.orange", "Orange cell"),
+    ("rule.short.cyan", "Cyan cell"),
+    ("rule.short.number", "Number cell"),
+    ("rule.short.variant", "{rule} ({hood})"),
+    ("rule.short.bingo", "line"),
+    ("hood.king", "neighbours"),
+    ("hood.orthogonal", "orthogonal neighbours"),
+    ("hood.knight", "knight-move cells"),
+    ("hood.line2", "cells within two in its row and column"),
+    ("hood.diagonal", "diagonal neighbours"),
+    ("win.five", "five-in-a-row line"),
+    ("win.full_line", "full row/column line"),
+    ("win.in_a_row", "{length}-in-a-row line"),
+    // Cells and count variables
+    ("cell.coord", "({row},{col})"),
+    ("cell.checked", "({row},{col}) checked"),
+    ("cell.unchecked", "({row},{col}) unchecked"),
+    ("count.row", "row {row}"),
+    ("count.col", "column {col}"),
+    ("count.diag_down", "the \\ diagonal through ({row},{col})"),
+    ("count.diag_up", "the / diagonal through ({row},{col})"),
+    ("sep.comma", ", "),
+    ("sep.enum", ", "),
+    ("sep.equals", " = "),
+    // Hints
+    ("hint.forced.check", "From what is known, ({row},{col}) must be checked."),
+    ("hint.forced.uncheck", "From what is known, ({row},{col}) must stay unchecked."),
+    ("hint.conclude.check", "Conclusion: ({row},{col}) must be checked."),
+    ("hint.conclude.uncheck", "Conclusion: ({row},{col}) must stay unchecked."),
+    ("hint.contradiction.check", "By contradiction, ({row},{col}) must be checked."),
+    ("hint.contradiction.uncheck", "By contradiction, ({row},{col}) must stay unchecked."),
+    ("hint.contradiction.assume", "Contradiction: the opposite choice propagates into a contradiction."),
+    ("hint.contradiction.conclude.check", "Conclusion: the opposite value always fails, so ({row},{col}) must be checked."),
+    ("hint.contradiction.conclude.uncheck", "Conclusion: the opposite value always fails, so ({row},{col}) must stay unchecked."),
+    ("hint.suggest", "A step that may help: try checking ({row},{col})."),
+    ("hint.suggest.goal", "Goal: complete at least one {line} (Bingo)."),
+    ("hint.suggest.step", "Suggestion: check ({row},{col}) to make progress (not necessarily unique)."),
+    ("hint.no_step", "The current marks can still be completed, but there is no single step to suggest."),
+    ("hint.exhausted.cancelled", "Hint calculation was cancelled."),
+    ("hint.exhausted.time", "Hint calculation timed out: the position is too open. Fix a few more cells and try again."),
+    ("hint.exhausted.budget", "Hint calculation exceeded the search budget: the position is too open. Fix a few more cells and try again."),
+    ("hint.repair.one", "The current checks cannot be completed. Try unchecking {cell} first."),
+    ("hint.repair.many", "The current checks cannot be completed. At least {count} checks must be undone: {cells}"),
+    ("hint.repair.exhausted", "The current checks cannot be completed: undo some checks or exclusions and ask again."),
+    ("hint.repair.exclusions", "There is no solution even ignoring the checks: undo some exclusions and ask again."),
+    ("hint.repair.too_many", "The current checks cannot be completed: the {count} highlighted checks conflict, and more than {max} would have to be undone. Undo several checks or exclusions and ask again."),
+    ("hint.parity.odd", "Constraint: ({row},{col}) has an odd number of checked {hood}."),
+    ("hint.parity.even", "Constraint: ({row},{col}) has an even number of checked {hood}."),
+    ("hint.parity.combine", "Combine: adding these {count} parity constraints cancels the shared neighbours, leaving only ({row},{col}) open."),
+    ("hint.count_chain.equation", "Equation: ({row},{col}) requires {a} and {b} to have the same number of checks."),
+    ("hint.count_chain.chain", "Chain: {vars} all have the same number of checks; intersect their feasible ranges."),
+    ("hint.bingo.forced", "Deduction: every {line} that can still be completed passes through ({row},{col}), so it must be checked for a Bingo."),
+    ("hint.propagate.rule", "Deduction: {rule} triggers propagation."),
+    ("hint.derive", "Deduction: {rule} ⇒ {conclusions}."),
+    ("justify.givens", "Given: {count} known cells (black cells and checked/excluded cells)."),
+    ("justify.omitted", "({count} earlier steps omitted)"),
+    ("refute.assume", "Assume: {assignment}."),
+    ("refute.no_line", "Contradiction: no {line} can be completed any more."),
+    ("refute.violated", "Contradiction: {rule} cannot be satisfied."),
+    ("refute.stuck", "Contradiction: propagation cannot continue."),
+    // Repair
+    ("repair.unsolvable", "The current checks have no solution: get back to a solvable state before reasoning further."),
+    ("repair.conflict", "Conflict: these {count} checks together have no solution (removing any one resolves it)."),
+    ("repair.via", "Via: {rule}"),
+    ("repair.count", "At least {count} changes are needed to become solvable again:"),
+    ("repair.uncheck", "Try unchecking ({row},{col}) (restores a solvable state)"),
+    ("repair.release", "Try checking the excluded ({row},{col}) (restores a solvable state)"),
+    // Deductions
+    ("deduce.rule", "Follows directly from {rule}."),
+    ("deduce.propagate", "Follows directly from propagation."),
+    ("deduce.probe.check", "Contradiction: assuming {cell} is checked propagates into a contradiction."),
+    ("deduce.probe.uncheck", "Contradiction: assuming {cell} is unchecked propagates into a contradiction."),
+    ("deduce.probe.follow", "Follows by propagating the contradiction result for {cell}."),
+    ("deduce.backbone", "Takes the same value in every solution (needs several assumptions to deduce)."),
+    // Techniques
+    ("technique.red_last_neighbour", "Red: last neighbour"),
+    ("technique.red_last_neighbour.desc", "When none of a Red cell's 8 neighbours is checked and only one is undecided, that one must be checked."),
+    ("technique.blue_saturation", "Blue saturation"),
+    ("technique.blue_saturation.desc", "Once two of a Blue cell's 8 neighbours are checked, the rest must stay unchecked."),
+    ("technique.cyan_last_neighbour", "Cyan: last neighbour"),
+    ("technique.cyan_last_neighbour.desc", "When none of a checked Cyan cell's 4 neighbours is checked and only one is undecided, that one must be checked."),
+    ("technique.cyan_isolated", "Isolated Cyan"),
+    ("technique.cyan_isolated.desc", "When all 4 neighbours of a Cyan cell are unchecked, the Cyan cell itself cannot be checked."),
+    ("technique.number_count", "Number count"),
+    ("technique.number_count.desc", "Once a Number cell has N checked neighbours the rest are unchecked; when the unknown neighbours are exactly enough to reach N they are all checked."),
+    ("technique.purple_last_neighbour", "Purple odd completion"),
+    ("technique.purple_last_neighbour.desc", "When only one of a Purple cell's 8 neighbours is undecided, the odd count decides it."),
+    ("technique.orange_last_neighbour", "Orange even completion"),
+    ("technique.orange_last_neighbour.desc", "When only one of an Orange cell's 8 neighbours is undecided, the even count decides it."),
+    ("technique.green_squeeze", "Green row/column squeeze"),
+    ("technique.green_squeeze.desc", "A Green cell's row and column hold equal counts; when one's maximum meets the other's minimum, both lines are fixed."),
+    ("technique.yellow_squeeze", "Yellow diagonal squeeze"),
+    ("technique.yellow_squeeze.desc", "A Yellow cell's two diagonals hold equal counts; when one's maximum meets the other's minimum, both lines are fixed."),
+    ("technique.bingo_common_cells", "Common line cells"),
+    ("technique.bingo_common_cells.desc", "When every line that can still be completed passes through a cell, that cell must be checked."),
+    ("technique.count_chain", "Equal-count chain"),
+    ("technique.count_chain.desc", "Several Green/Yellow cells link lines into a group with equal counts; intersect their feasible ranges."),
+    ("technique.parity_pair", "Parity pair"),
+    ("technique.parity_pair.desc", "Add the parity constraints of two Purple/Orange cells; the shared neighbours cancel and one cell is left."),
+    ("technique.parity_chain", "Parity chain"),
+    ("technique.parity_chain.desc", "Combine the parity constraints of three or more Purple/Orange cells until one cell is left."),
+    ("technique.single_assumption_contradiction", "Single-assumption contradiction"),
+    ("technique.single_assumption_contradiction.desc", "Assume one value for a cell; propagation runs into a contradiction, so it must take the other."),
+    // Graded hints
+    ("region.top_left", "the top-left corner"),
+    ("region.top", "the top edge"),
+    ("region.top_right", "the top-right corner"),
+    ("region.left", "the left side"),
+    ("region.center", "the centre"),
+    ("region.right", "the right side"),
+    ("region.bottom_left", "the bottom-left corner"),
+    ("region.bottom", "the bottom edge"),
+    ("region.bottom_right", "the bottom-right corner"),
+    ("region.board", "the whole board"),
+    ("graded.propagate.nudge", "Look at the {rule} around {region}: something definite follows there."),
+    ("graded.propagate.focus", "Look at the highlighted {rule}: together with the known cells it forces a cell."),
+    ("graded.contradiction.nudge", "A cell around {region} can take only one value: assume the opposite and see whether the {rule} breaks."),
+    ("graded.contradiction.focus", "If the target cell takes the opposite value, the highlighted {rule} leads to a contradiction."),
+    ("graded.suggest.nudge", "Nothing is forced yet: work towards a line around {region}."),
+    ("graded.suggest.focus", "The highlighted line can still be completed; try following it."),
+    ("graded.repair.nudge", "The current checks have no solution: check the marks related to the {rule}."),
+    ("graded.repair.focus", "The {count} highlighted checks conflict; some of them must be undone."),
+    ("graded.target.forced", "The key is ({row},{col}): its value is already determined."),
+    ("graded.target.suggest", "You could start from ({row},{col})."),
+    ("graded.target.repair", "Consider unchecking ({row},{col})."),
+    // Validation
+    ("validate.black", "Black cells must be checked"),
+    ("validate.red", "Needs at least 1 checked neighbour, currently {count}"),
+    ("validate.blue", "At most 2 neighbours may be checked, currently {count}"),
+    ("validate.green", "Row checks ({row}) and column checks ({col}) differ"),
+    ("validate.yellow", "The two diagonals have different check counts ({down} vs {up})"),
+    ("validate.purple", "Checked neighbours must be odd, currently {count}"),
+    ("validate.orange", "Checked neighbours must be even, currently {count}"),
+    ("validate.cyan", "When checked, needs at least 1 checked cell above, below, left or right, currently {count}"),
+    ("validate.number", "Needs exactly {n} checked neighbours, currently {count}"),
+    // Errors
+    ("error.grid.square", "color_grid must be a square with side 1..={max}, got length {actual}"),
+    ("error.color", "color_grid contains an invalid color code: index={index}, value={value}"),
+    ("error.size", "size must be in {min}..={max}, got {actual}"),
+    ("error.date.format", "invalid date format: expected YYYY-MM-DD, got {value}"),
+    ("error.date.field", "invalid date field: {field}={value}"),
+    ("error.date.before_epoch", "date is before 1970-01-01: {value}"),
+    ("error.difficulty.no_solution", "the puzzle has no solution, so no difficulty score can be computed"),
+    ("error.generate.no_puzzle", "no puzzle with at least 1 solution found within the attempt limit (seed={seed}, attempts={attempts})"),
+    ("error.win.no_segments", "the win condition has no possible line on a {size}x{size} board: {win}"),
+    ("error.win.parse", "failed to parse win_condition: {detail}"),
+    ("error.cell", "cell index out of range: cell={index}, {cell_count} cells in total"),
+    ("error.cell.fixed", "black cells are always checked and cannot be excluded: cell={index}"),
+    ("error.budget.object", "budget must be an object"),
+    ("error.budget.number", "budget.{field} must be a non-negative number"),
+    ("error.budget.on_progress", "budget.onProgress must be a function"),
+    ("error.mask.capacity", "{name} exceeds the board capacity: got {actual} bytes, at most {max}"),
+    ("error.mask.cell", "{name} contains a cell outside the board: index={index}, {cell_count} cells in total"),
+    ("error.depth.parse", "failed to parse depth: {detail}"),
+    ("error.generate.options", "failed to parse generator options: {detail}"),
+    ("error.locale", "locale must be a string"),
+    ("error.serialize", "failed to serialize the result: {detail}"),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn placeholders(template: &str) -> Vec<&str> {
+        let mut names: Vec<&str> = template
+            .split('{')
+            .skip(1)
+            .filter_map(|rest| rest.split_once('}').map(|(name, _)| name))
+            .collect();
+        names.sort_unstable();
+        names.dedup();
+        names
+    }
+
+    #[test]
+    fn catalogs_cover_the_same_keys_and_placeholders() {
+        for (key, zh) in ZH_CN {
+            assert_eq!(ZH_CN.iter().filter(|(k, _)| k == key).count(), 1, "{key}");
+            let en = lookup(Locale::En, key).unwrap_or_else(|| panic!("en 缺少 {key}"));
+            assert_eq!(placeholders(zh), placeholders(en), "{key}");
+        }
+        assert_eq!(ZH_CN.len(), EN.len());
+    }
+
+    #[test]
+    fn renders_nested_params_and_lists() {
+        let cells = vec![
+            msg!("cell.coord", row = 1usize, col = 2usize),
+            msg!("cell.coord", row = 3usize, col = 4usize),
+        ];
+        let m = msg!(
+            "hint.repair.many",
+            count = 2usize,
+            cells = Param::list("sep.enum", cells)
+        );
+        assert_eq!(
+            m.zh(),
+            "当前勾选无法补全成解。至少需要取消 2 处勾选：(1,2)、(3,4)"
+        );
+        assert_eq!(
+            m.render(Locale::En),
+            "The current checks cannot be completed. At least 2 checks must be undone: (1,2), (3,4)"
+        );
+        let nested = msg!("hint.propagate.rule", rule = msg!("rule.blue"));
+        assert_eq!(
+            nested.render(Locale::En),
+            "Deduction: Blue (at most two of 8 neighbours checked) triggers propagation."
+        );
+        // 未知的键原样输出，缺少的参数保留占位符。
+        assert_eq!(
+            Message::new("no.such.key").render(Locale::En),
+            "no.such.key"
+        );
+        assert_eq!(msg!("count.row").zh(), "第 {row} 行");
+    }
+
+    #[test]
+    fn locale_tags_fall_back_to_english() {
+        assert_eq!(Locale::from_tag("zh-CN"), Locale::ZhCn);
+        assert_eq!(Locale::from_tag("zh-Hant-TW"), Locale::ZhCn);
+        assert_eq!(Locale::from_tag("en-US"), Locale::En);
+        assert_eq!(Locale::from_tag("fr"), Locale::En);
+    }
+
+    #[test]
+    fn serializes_key_and_typed_params() {
+        let m = msg!(
+            "hint.derive",
+            rule = msg!("rule.red"),
+            conclusions = Param::list(
+                "sep.comma",
+                vec![msg!("cell.checked", row = 1usize, col = 1usize)]
+            )
+        );
+        assert_eq!(
+            serde_json::to_value(&m).unwrap(),
+            serde_json::json!({
+                "key": "hint.derive",
+                "params": {
+                    "rule": {"key": "rule.red"},
+                    "conclusions": [{"key": "cell.checked", "params": {"row": 1, "col": 1}}],
+                },
+            })
+        );
+    }
+}
@@ -2,148 +2,865 @@
 //!
 //! - 颜色与规则语义：见仓库根目录 `rules.json`
 //! - 位序：坐标 (0,0) 为 bit0（LSB），按行优先（row-major）排序
+//! - mask 在 WASM 边界上编码为小端字节数组（`Uint8Array`）：byte k 的 bit j 对应格子 `8k + j`
 
 mod colors;
+mod count;
 mod date_seed;
 mod difficulty;
+mod error;
 mod generate;
+mod i18n;
 mod masks;
+mod session;
 mod solver;
+mod unsolvable;
 mod validate;
+mod win;
 
 use serde::Serialize;
 use wasm_bindgen::prelude::*;
 
-pub use colors::{Color, COLOR_COUNT};
-pub use difficulty::{DifficultyError, DifficultyReport};
-pub use generate::GenerateError;
-pub use solver::{HintAction, HintMove, HintReason, HintReasonKind, HintResult, HintStatus};
-pub use validate::{ValidateError, ValidateResult};
+pub use colors::{Color, CountColor, COLOR_COUNT};
+pub use count::{ExactSolutionCountResult, SolutionMarginalsResult};
+pub use difficulty::DifficultyReport;
+pub use error::EngineError;
+pub use generate::GenerateOptions;
+pub use i18n::{Locale, Localize, Message, Param};
+pub use masks::{Bitset, Mask, Neighborhood, MAX_GRID_SIZE};
+pub use session::PuzzleSession;
+pub use solver::{
+    Backbone, BudgetStop, Budgeted, ConflictCore, ConflictRule, ConflictingClue, DeducedCell, DeductionDepth, GradedHint, HintAction, HintMove, HintReason, HintReasonKind, HintResult,
+    HintStatus, HintTier, Marginals, Repair, SolveBudget, SolveProgress, Technique, TechniqueCount,
+    TechniqueInfo, TechniqueTier, MAX_HINT_LEVEL, MAX_REPAIR_MOVES,
+};
+pub use unsolvable::UnsolvableReport;
+pub use validate::ValidateResult;
+pub use win::WinCondition;
 
-#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Default, Serialize, PartialEq, Eq)]
 pub struct SolutionCountResult {
     pub count: u32,
     pub truncated: bool,
+    /// 预算耗尽的原因；此时 `count` 只是已找到的解数（下界）。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exhausted: Option<BudgetStop>,
 }
 
-fn parse_colors(color_grid: &[u8]) -> Result<Vec<Color>, JsValue> {
-    let mut colors = Vec::with_capacity(color_grid.len());
-    for (i, &v) in color_grid.iter().enumerate() {
-        colors.push(Color::from_u8(v).ok_or_else(|| {
-            JsValue::from_str(&format!("color_grid 含非法颜色编码：index={i}, value={v}"))
-        })?);
+impl SolutionCountResult {
+    fn from_budgeted(solutions: Budgeted<Vec<Mask>>, limit: usize) -> Self {
+        Self {
+            count: solutions.value.len() as u32,
+            truncated: limit != 0 && solutions.value.len() >= limit,
+            exhausted: solutions.exhausted,
+        }
+    }
+}
+
+/// 骨干计算结果（见 `backbone`）。
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq)]
+pub struct BackboneResult {
+    /// 已证明无解（此时 `checked`/`unchecked` 为空）。
+    pub no_solution: bool,
+    /// 所有解中都勾选的格子（含黑格与已确认勾选的格子），row-major 递增。
+    pub checked: Vec<usize>,
+    /// 所有解中都不勾选的格子（含已排除的格子）。
+    pub unchecked: Vec<usize>,
+    /// 预算耗尽的原因；此时只含已确认的格子（真实骨干的子集）。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exhausted: Option<BudgetStop>,
+}
+
+impl BackboneResult {
+    fn from_budgeted(res: Budgeted<Option<Backbone>>) -> Self {
+        let (checked, unchecked) = res
+            .value
+            .map_or((Vec::new(), Vec::new()), |b| {
+                (b.checked.iter_ones().collect(), b.unchecked.iter_ones().collect())
+            });
+        Self {
+            no_solution: res.value.is_none() && res.exhausted.is_none(),
+            checked,
+            unchecked,
+            exhausted: res.exhausted,
+        }
+    }
+}
+
+/// 批量推导结果（见 `deductions`）。
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct DeductionsResult {
+    /// 已证明无解（此时 `cells` 为空）。
+    pub no_solution: bool,
+    /// 已能确定、但尚未标记的格子，按深度分组、组内按推出先后排列。
+    pub cells: Vec<DeducedCell>,
+    /// 预算耗尽的原因；此时只含已确定的部分。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exhausted: Option<BudgetStop>,
+}
+
+impl DeductionsResult {
+    fn from_budgeted(res: Budgeted<Option<Vec<DeducedCell>>>) -> Self {
+        Self {
+            no_solution: res.value.is_none() && res.exhausted.is_none(),
+            cells: res.value.unwrap_or_default(),
+            exhausted: res.exhausted,
+        }
+    }
+}
+
+/// 最小修复结果（见 `minimal_repair`）。
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RepairResult {
+    /// 当前局面可解（此时无需修复，`moves` 为空）。
+    pub solvable: bool,
+    /// 改动最少的一组操作，最近的操作在前；找不到修复时为空。
+    pub moves: Vec<HintMove>,
+    /// 修复的解释（`kind` 为 `repair`，含原局面的冲突解释）。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<HintReason>,
+    /// 预算耗尽的原因。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exhausted: Option<BudgetStop>,
+}
+
+impl Localize for DeductionsResult {
+    fn localize(&mut self, locale: Locale) {
+        self.cells.localize(locale);
+    }
+}
+
+impl RepairResult {
+    fn from_budgeted(res: Budgeted<Option<Repair>>) -> Self {
+        match res.value {
+            Some(repair) => Self {
+                solvable: false,
+                moves: repair.moves,
+                reason: Some(repair.reason),
+                exhausted: res.exhausted,
+            },
+            None => Self {
+                solvable: res.exhausted.is_none(),
+                exhausted: res.exhausted,
+                ..Self::default()
+            },
+        }
+    }
+}
+
+/// 冲突解释结果（见 `conflict_core`）。
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq)]
+pub struct ConflictCoreResult {
+    /// 当前局面可解（此时 `checks`/`rules` 为空）。
+    pub solvable: bool,
+    /// 互相冲突的极小勾选子集，row-major 递增。
+    pub checks: Vec<usize>,
+    /// 推出矛盾所经过的规则实例（最后一条为发现矛盾的规则）。
+    pub rules: Vec<ConflictRule>,
+    /// 预算耗尽的原因；收缩中途耗尽时 `checks` 仍无解但不保证极小。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exhausted: Option<BudgetStop>,
+}
+
+impl Localize for RepairResult {
+    fn localize(&mut self, locale: Locale) {
+        self.reason.localize(locale);
+    }
+}
+
+impl Localize for ConflictCoreResult {
+    fn localize(&mut self, locale: Locale) {
+        self.rules.localize(locale);
+    }
+}
+
+impl ConflictCoreResult {
+    fn from_budgeted(res: Budgeted<Option<ConflictCore>>) -> Self {
+        let solvable = res.value.is_none() && res.exhausted.is_none();
+        let (checks, rules) = res.value.map_or((Vec::new(), Vec::new()), |c| (c.checks, c.rules));
+        Self {
+            solvable,
+            checks,
+            rules,
+            exhausted: res.exhausted,
+        }
+    }
+}
+
+/// 解析可选的语言标签（BCP 47，如 `"zh-CN"`、`"en"`）：`undefined`/`null` 为 zh-CN，
+/// 目录里没有的语言退回英文（见 `i18n`）。
+fn parse_locale(locale: &JsValue) -> Result<Locale, JsValue> {
+    if locale.is_undefined() || locale.is_null() {
+        return Ok(Locale::default());
+    }
+    locale
+        .as_string()
+        .map(|tag| Locale::from_tag(&tag))
+        .ok_or_else(|| js_error(EngineError::InvalidLocale, Locale::default()))
+}
+
+/// 引擎错误在 JS 侧抛出为 `Error`（`name` 为 `EngineError`），另带稳定的 `code` 与结构化的 `details`。
+fn js_error(err: EngineError, locale: Locale) -> JsValue {
+    let error = js_sys::Error::new(&err.message().render(locale));
+    error.set_name("EngineError");
+    if let Ok(fields) = err.serialize(&serializer()) {
+        js_sys::Object::assign(&error, fields.unchecked_ref());
+    }
+    error.into()
+}
+
+/// WASM 边界上的序列化约定：map（如消息参数）转为普通对象而不是 ES `Map`。
+fn serializer() -> serde_wasm_bindgen::Serializer {
+    serde_wasm_bindgen::Serializer::new().serialize_maps_as_objects(true)
+}
+
+fn to_js<T: Serialize>(value: &T, locale: Locale) -> Result<JsValue, JsValue> {
+    value.serialize(&serializer()).map_err(|e| {
+        js_error(
+            EngineError::Serialization {
+                detail: e.to_string(),
+            },
+            locale,
+        )
+    })
+}
+
+/// 按 `locale` 重新渲染结果里的文字，再转为 JS 值。
+fn to_js_localized<T: Serialize + Localize>(
+    mut value: T,
+    locale: Locale,
+) -> Result<JsValue, JsValue> {
+    value.localize(locale);
+    to_js(&value, locale)
+}
+
+fn parse_colors(color_grid: &[u8], locale: Locale) -> Result<Vec<Color>, JsValue> {
+    colors::parse_grid(color_grid)
+        .map(|(_, colors)| colors)
+        .map_err(|e| js_error(e, locale))
+}
+
+fn parse_size(color_grid: &[u8], locale: Locale) -> Result<usize, JsValue> {
+    colors::grid_size(color_grid.len()).map_err(|e| js_error(e, locale))
+}
+
+/// 解析可选的胜利条件：`undefined`/`null` 取默认（五连线）；在该尺寸下没有任何连线则报错。
+fn parse_win_condition(
+    win_condition: JsValue,
+    size: usize,
+    locale: Locale,
+) -> Result<WinCondition, JsValue> {
+    let win = if win_condition.is_undefined() || win_condition.is_null() {
+        WinCondition::default()
+    } else {
+        serde_wasm_bindgen::from_value(win_condition).map_err(|e| {
+            js_error(
+                EngineError::InvalidWinCondition {
+                    detail: e.to_string(),
+                },
+                locale,
+            )
+        })?
+    };
+    if win.segments(size).is_empty() {
+        return Err(js_error(EngineError::NoWinLines { size, win }, locale));
     }
-    Ok(colors)
+    Ok(win)
+}
+
+fn parse_generate_options(options: JsValue, locale: Locale) -> Result<GenerateOptions, JsValue> {
+    if options.is_undefined() || options.is_null() {
+        return Ok(GenerateOptions::default());
+    }
+    serde_wasm_bindgen::from_value(options).map_err(|e| {
+        js_error(
+            EngineError::InvalidGenerateOptions {
+                detail: e.to_string(),
+            },
+            locale,
+        )
+    })
+}
+
+/// 读取预算对象上的非负数字段（缺省为 0，即不限制）。
+fn budget_number(budget: &JsValue, key: &'static str, locale: Locale) -> Result<u64, JsValue> {
+    let v = js_sys::Reflect::get(budget, &JsValue::from_str(key))?;
+    if v.is_undefined() || v.is_null() {
+        return Ok(0);
+    }
+    match v.as_f64() {
+        Some(n) if n.is_finite() && n >= 0.0 => Ok(n as u64),
+        _ => Err(js_error(
+            EngineError::InvalidBudgetNumber { field: key },
+            locale,
+        )),
+    }
+}
+
+/// 解析可选的求解预算：`{maxNodes?, maxMillis?, progressEvery?, onProgress?}`，`undefined`/`null` 为不限制。
+///
+/// `onProgress` 每隔 `progressEvery` 个节点以 `{nodes, solutions, elapsedMs}` 调用一次；
+/// 返回 `false` 或抛出异常表示取消。
+fn parse_budget(budget: &JsValue, locale: Locale) -> Result<SolveBudget<'static>, JsValue> {
+    if budget.is_undefined() || budget.is_null() {
+        return Ok(SolveBudget::default());
+    }
+    if !budget.is_object() {
+        return Err(js_error(EngineError::BudgetNotObject, locale));
+    }
+
+    let on_progress = js_sys::Reflect::get(budget, &JsValue::from_str("onProgress"))?;
+    let progress: Option<Box<dyn FnMut(SolveProgress) -> bool>> =
+        if on_progress.is_undefined() || on_progress.is_null() {
+            None
+        } else {
+            let f: js_sys::Function = on_progress
+                .dyn_into()
+                .map_err(|_| js_error(EngineError::InvalidProgressCallback, locale))?;
+            Some(Box::new(move |p: SolveProgress| {
+                let Ok(arg) = serde_wasm_bindgen::to_value(&p) else {
+                    return true;
+                };
+                match f.call1(&JsValue::NULL, &arg) {
+                    Ok(ret) => ret.as_bool() != Some(false),
+                    Err(_) => false,
+                }
+            }))
+        };
+
+    Ok(SolveBudget {
+        max_nodes: budget_number(budget, "maxNodes", locale)?,
+        max_millis: budget_number(budget, "maxMillis", locale)?,
+        progress_every: budget_number(budget, "progressEvery", locale)?,
+        progress,
+        cancel: None,
+    })
+}
+
+/// 解析 mask：字节数不超过 `Mask` 的容量，且不含 `cell_count` 之外的格子。
+fn parse_mask(
+    name: &'static str,
+    bytes: &[u8],
+    cell_count: usize,
+    locale: Locale,
+) -> Result<Mask, JsValue> {
+    let mask = Mask::from_bytes(bytes).ok_or_else(|| {
+        js_error(
+            EngineError::MaskTooLarge {
+                name,
+                actual: bytes.len(),
+                max: masks::MAX_CELLS / 8,
+            },
+            locale,
+        )
+    })?;
+    if let Some(index) = mask.iter_ones().find(|&i| i >= cell_count) {
+        return Err(js_error(
+            EngineError::MaskOutOfRange {
+                name,
+                index,
+                cell_count,
+            },
+            locale,
+        ));
+    }
+    Ok(mask)
+}
+
+/// Rust 原生接口：日期字符串（YYYY-MM-DD）转每日一题的 seed。
+pub fn date_to_seed_ymd_native(date_ymd: &str) -> Result<u64, EngineError> {
+    date_seed::date_to_seed_ymd(date_ymd)
 }
 
 /// Rust 原生接口：生成颜色布局（u8）。
-pub fn generate_puzzle_grid(seed: u64, size: usize) -> Result<Vec<Vec<u8>>, GenerateError> {
-    generate::generate_puzzle(seed, size)
+pub fn generate_puzzle_grid(
+    seed: u64,
+    size: usize,
+    win: WinCondition,
+    options: GenerateOptions,
+) -> Result<Vec<Vec<u8>>, EngineError> {
+    generate::generate_puzzle_with(seed, size, win, options)
 }
 
 /// Rust 原生接口：校验当前状态与颜色布局。
 pub fn validate_state_native(
-    checked_mask: u64,
+    checked_mask: Mask,
     color_grid: &[u8],
-) -> Result<ValidateResult, ValidateError> {
-    validate::validate_state(checked_mask, color_grid)
+    win: WinCondition,
+) -> Result<ValidateResult, EngineError> {
+    validate::validate_state(checked_mask, color_grid, win)
+}
+
+/// Rust 原生接口：精确统计解的数量（不枚举解；`max_states=0` 表示不限制 DP 状态数）。
+pub fn solution_count_exact_native(
+    checked_mask: Mask,
+    unchecked_mask: Mask,
+    color_grid: &[u8],
+    win: WinCondition,
+    max_states: usize,
+    budget: &mut SolveBudget,
+) -> Result<ExactSolutionCountResult, EngineError> {
+    count::solution_count_exact(checked_mask, unchecked_mask, color_grid, win, max_states, budget)
+}
+
+/// Rust 原生接口：逐格边际概率（解少时精确，解多时均匀抽样估计）。
+pub fn solution_marginals_native(
+    checked_mask: Mask,
+    unchecked_mask: Mask,
+    color_grid: &[u8],
+    win: WinCondition,
+    samples: u32,
+    max_states: usize,
+    budget: &mut SolveBudget,
+) -> Result<SolutionMarginalsResult, EngineError> {
+    count::solution_marginals(
+        checked_mask,
+        unchecked_mask,
+        color_grid,
+        win,
+        samples,
+        max_states,
+        budget,
+    )
 }
 
 /// Rust 原生接口：计算题目难度分（偏向“人类逻辑难度”：传播 + 反证 + Bingo 约束强度）。
-pub fn difficulty_report_native(color_grid: &[u8]) -> Result<DifficultyReport, DifficultyError> {
-    difficulty::difficulty_report(color_grid)
+pub fn difficulty_report_native(
+    color_grid: &[u8],
+    win: WinCondition,
+) -> Result<DifficultyReport, EngineError> {
+    difficulty::difficulty_report(color_grid, win)
+}
+
+/// Rust 原生接口：解释自定义题目为何无解（互相冲突的极小线索集合）。
+pub fn explain_unsolvable_native(
+    color_grid: &[u8],
+    win: WinCondition,
+    budget: &mut SolveBudget,
+) -> Result<UnsolvableReport, EngineError> {
+    unsolvable::explain_unsolvable(color_grid, win, budget)
 }
 
 #[wasm_bindgen]
-pub fn date_to_seed_ymd(date_ymd: &str) -> Result<u64, JsValue> {
-    date_seed::date_to_seed_ymd(date_ymd).map_err(|e| JsValue::from_str(&e.to_string()))
+pub fn date_to_seed_ymd(date_ymd: &str, locale: JsValue) -> Result<u64, JsValue> {
+    let locale = parse_locale(&locale)?;
+    date_to_seed_ymd_native(date_ymd).map_err(|e| js_error(e, locale))
 }
 
 /// 生成颜色布局（确定性：同 seed 必然得到同一题）。
 ///
 /// 返回值为可被 JS 直接使用的 JSON（`number[size][size]`，每个元素为颜色 u8）。
+///
+/// `win_condition` 省略时为默认的五连线；下同。
+///
+/// `options`：`{numberClues?}`，省略时与旧版生成器一致（不出数字格，已有 seed 的题目不变）。
 #[wasm_bindgen]
-pub fn generate_puzzle(seed: u64, size: usize) -> Result<JsValue, JsValue> {
-    let grid = generate_puzzle_grid(seed, size).map_err(|e| JsValue::from_str(&e.to_string()))?;
-    serde_wasm_bindgen::to_value(&grid).map_err(|e| JsValue::from_str(&e.to_string()))
+pub fn generate_puzzle(
+    seed: u64,
+    size: usize,
+    win_condition: JsValue,
+    options: JsValue,
+    locale: JsValue,
+) -> Result<JsValue, JsValue> {
+    let locale = parse_locale(&locale)?;
+    generate::check_size(size).map_err(|e| js_error(e, locale))?;
+    let win = parse_win_condition(win_condition, size, locale)?;
+    let options = parse_generate_options(options, locale)?;
+    let grid = generate_puzzle_grid(seed, size, win, options).map_err(|e| js_error(e, locale))?;
+    to_js(&grid, locale)
 }
 
 /// 校验当前勾选状态与颜色布局。
 ///
-/// - `checked_mask`：bitmask（u64），bit i 表示第 i 个格子是否勾选
+/// - `checked_mask`：bitmask（小端字节数组），bit i 表示第 i 个格子是否勾选
 /// - `color_grid`：颜色数组（u8，row-major）
+/// - `win_condition`：胜利条件（可省略）
 #[wasm_bindgen]
-pub fn validate_state(checked_mask: u64, color_grid: Vec<u8>) -> Result<JsValue, JsValue> {
-    let res = validate_state_native(checked_mask, &color_grid)
-        .map_err(|e| JsValue::from_str(&e.to_string()))?;
-    serde_wasm_bindgen::to_value(&res).map_err(|e| JsValue::from_str(&e.to_string()))
+pub fn validate_state(
+    checked_mask: Vec<u8>,
+    color_grid: Vec<u8>,
+    win_condition: JsValue,
+    locale: JsValue,
+) -> Result<JsValue, JsValue> {
+    let locale = parse_locale(&locale)?;
+    let size = parse_size(&color_grid, locale)?;
+    let win = parse_win_condition(win_condition, size, locale)?;
+    let checked_mask = parse_mask("checked_mask", &checked_mask, color_grid.len(), locale)?;
+    let res =
+        validate_state_native(checked_mask, &color_grid, win).map_err(|e| js_error(e, locale))?;
+    to_js_localized(res, locale)
 }
 
 /// 计算题目难度分（返回 JSON 对象）。
 #[wasm_bindgen]
-pub fn difficulty_report(color_grid: Vec<u8>) -> Result<JsValue, JsValue> {
-    let report =
-        difficulty_report_native(&color_grid).map_err(|e| JsValue::from_str(&e.to_string()))?;
-    serde_wasm_bindgen::to_value(&report).map_err(|e| JsValue::from_str(&e.to_string()))
+pub fn difficulty_report(
+    color_grid: Vec<u8>,
+    win_condition: JsValue,
+    locale: JsValue,
+) -> Result<JsValue, JsValue> {
+    let locale = parse_locale(&locale)?;
+    let size = parse_size(&color_grid, locale)?;
+    let win = parse_win_condition(win_condition, size, locale)?;
+    let report = difficulty_report_native(&color_grid, win).map_err(|e| js_error(e, locale))?;
+    to_js(&report, locale)
+}
+
+/// 推理技巧目录：`{id, tier, title, titleKey, description, descriptionKey}[]`，按档位由易到难。
+///
+/// `id` 与提示 `reason.technique`、难度报告 `hardest_technique` / `techniques` 中的技巧 id 一致。
+#[wasm_bindgen]
+pub fn technique_catalog(locale: JsValue) -> Result<JsValue, JsValue> {
+    let locale = parse_locale(&locale)?;
+    to_js_localized(solver::technique_catalog(), locale)
+}
+
+/// 解释自定义题目为何无解：互相冲突的极小线索集合（供编辑器高亮）。
+///
+/// - `budget` 同 `hint_next`；
+/// - 返回值为 JSON：`{solvable, clues: {cell, ruleId}[], exhausted?}`，`ruleId` 与 `rules.json` 对齐。
+#[wasm_bindgen]
+pub fn explain_unsolvable(
+    color_grid: Vec<u8>,
+    win_condition: JsValue,
+    budget: JsValue,
+    locale: JsValue,
+) -> Result<JsValue, JsValue> {
+    let locale = parse_locale(&locale)?;
+    let size = parse_size(&color_grid, locale)?;
+    let win = parse_win_condition(win_condition, size, locale)?;
+    let mut budget = parse_budget(&budget, locale)?;
+    let report = explain_unsolvable_native(&color_grid, win, &mut budget)
+        .map_err(|e| js_error(e, locale))?;
+    to_js(&report, locale)
 }
 
 /// 给前端的“提示”接口：返回下一步建议/强制结论。
 ///
 /// 约定：
-/// - `checked_mask` 视为“已确认勾选”的集合，其余格子仍视为未知；
-/// - 返回值为 JSON：`{status,message,move?}`。
+/// - `checked_mask` 视为“已确认勾选”的集合，`unchecked_mask` 视为“已确认不勾选”（排除标记），其余格子仍视为未知；
+/// - `budget`：求解预算 `{maxNodes?, maxMillis?, progressEvery?, onProgress?}`（可省略，见 `parse_budget`）；
+/// - 返回值为 JSON：`{status,message,move?,exhausted?}`；预算耗尽时 `status="exhausted"` 或带 `exhausted` 的部分结论。
 #[wasm_bindgen]
-pub fn hint_next(checked_mask: u64, color_grid: Vec<u8>) -> Result<JsValue, JsValue> {
-    let cell_count = color_grid.len();
-    let size = (cell_count as f64).sqrt() as usize;
-    if size * size != cell_count {
-        return Err(JsValue::from_str("color_grid 必须是正方形"));
-    }
+pub fn hint_next(
+    checked_mask: Vec<u8>,
+    unchecked_mask: Vec<u8>,
+    color_grid: Vec<u8>,
+    win_condition: JsValue,
+    budget: JsValue,
+    locale: JsValue,
+) -> Result<JsValue, JsValue> {
+    let locale = parse_locale(&locale)?;
+    let size = parse_size(&color_grid, locale)?;
+    let win = parse_win_condition(win_condition, size, locale)?;
+    let checked_mask = parse_mask("checked_mask", &checked_mask, color_grid.len(), locale)?;
+    let unchecked_mask = parse_mask("unchecked_mask", &unchecked_mask, color_grid.len(), locale)?;
+    let colors = parse_colors(&color_grid, locale)?;
+    let mut budget = parse_budget(&budget, locale)?;
+    let solver = solver::Solver::new(size, colors, win);
+    let res = solver.hint_next_with_budget(checked_mask, unchecked_mask, &mut budget);
+    to_js_localized(res, locale)
+}
 
-    let colors = parse_colors(&color_grid)?;
-    let solver = solver::Solver::new(size, colors);
-    let res = solver.hint_next(checked_mask);
-    serde_wasm_bindgen::to_value(&res).map_err(|e| JsValue::from_str(&e.to_string()))
+/// 分级提示：同一条提示拆成由浅到深的若干层（区域/规则 → 规则实例 → 目标格 → 完整答案）。
+///
+/// - 参数同 `hint_next`，`level` 取 1..=4（超出范围时截断）；
+/// - 返回值为 JSON：`{status,level,message,tiers,exhausted?}`，`tiers` 含全部层级，UI 升级时无需再次调用。
+#[wasm_bindgen]
+pub fn hint_with_level(
+    checked_mask: Vec<u8>,
+    unchecked_mask: Vec<u8>,
+    color_grid: Vec<u8>,
+    win_condition: JsValue,
+    level: u8,
+    budget: JsValue,
+    locale: JsValue,
+) -> Result<JsValue, JsValue> {
+    let locale = parse_locale(&locale)?;
+    let size = parse_size(&color_grid, locale)?;
+    let win = parse_win_condition(win_condition, size, locale)?;
+    let checked_mask = parse_mask("checked_mask", &checked_mask, color_grid.len(), locale)?;
+    let unchecked_mask = parse_mask("unchecked_mask", &unchecked_mask, color_grid.len(), locale)?;
+    let colors = parse_colors(&color_grid, locale)?;
+    let mut budget = parse_budget(&budget, locale)?;
+    let solver = solver::Solver::new(size, colors, win);
+    let res = solver.hint_with_level(checked_mask, unchecked_mask, level, &mut budget);
+    to_js_localized(res, locale)
+}
+
+/// 最小修复：当前标记无解时，改动最少的一组操作（撤销勾选，可选地把排除改为勾选）使局面恢复可解。
+///
+/// - 参数同 `hint_next`；`history` 为玩家操作过的格子（按时间先后，可为空），同样少时优先撤销最近的操作；
+/// - `release_exclusions`：是否允许把排除标记改为勾选；
+/// - 返回值为 JSON：`{solvable, moves: HintMove[], reason?, exhausted?}`；
+///   无解但找不到修复（超过 `MAX_REPAIR_MOVES` 处）时 `moves` 为空，`reason.conflict` 仍给出冲突解释。
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn minimal_repair(
+    checked_mask: Vec<u8>,
+    unchecked_mask: Vec<u8>,
+    color_grid: Vec<u8>,
+    win_condition: JsValue,
+    history: Vec<u32>,
+    release_exclusions: bool,
+    budget: JsValue,
+    locale: JsValue,
+) -> Result<JsValue, JsValue> {
+    let locale = parse_locale(&locale)?;
+    let size = parse_size(&color_grid, locale)?;
+    let win = parse_win_condition(win_condition, size, locale)?;
+    let checked_mask = parse_mask("checked_mask", &checked_mask, color_grid.len(), locale)?;
+    let unchecked_mask = parse_mask("unchecked_mask", &unchecked_mask, color_grid.len(), locale)?;
+    let colors = parse_colors(&color_grid, locale)?;
+    let mut budget = parse_budget(&budget, locale)?;
+    let history: Vec<usize> = history.into_iter().map(|c| c as usize).collect();
+    let solver = solver::Solver::new(size, colors, win);
+    let res = RepairResult::from_budgeted(solver.minimal_repair(
+        checked_mask,
+        unchecked_mask,
+        &history,
+        release_exclusions,
+        &mut budget,
+    ));
+    to_js_localized(res, locale)
 }
 
 /// 统计解的数量（最多枚举到 `limit` 个解）。
 ///
 /// 说明：
 /// - 当 `limit=2` 时，足以区分：无解 / 唯一解 / 多解（>=2）；
-/// - 返回的 `count` 不会超过 `limit`（当 `limit=0` 时表示不限制，并返回真实数量，但可能很慢）。
-#[wasm_bindgen]
-pub fn solution_count(color_grid: Vec<u8>, limit: u32) -> Result<JsValue, JsValue> {
-    let cell_count = color_grid.len();
-    let size = (cell_count as f64).sqrt() as usize;
-    let colors = parse_colors(&color_grid)?;
-    let solver = solver::Solver::new(size, colors);
-    let solutions = solver.solve_masks_limit(limit as usize);
-    let truncated = limit != 0 && (solutions.len() as u32) >= limit;
-    let res = SolutionCountResult {
-        count: solutions.len() as u32,
-        truncated,
-    };
-    serde_wasm_bindgen::to_value(&res).map_err(|e| JsValue::from_str(&e.to_string()))
+/// - 返回的 `count` 不会超过 `limit`（当 `limit=0` 时表示不限制，并返回真实数量，但可能很慢）；
+/// - `budget` 同 `hint_next`；预算耗尽时返回已找到的解数，并带上 `exhausted`（停止原因）；
+/// - 所有计数入口（含 `solution_count_with_checked` 与 `PuzzleSession.count`）都使用冲突驱动搜索
+///   （见 `Solver::solve_masks_cdcl_with_budget`），`maxNodes` 一律按决策次数计。
+#[wasm_bindgen]
+pub fn solution_count(
+    color_grid: Vec<u8>,
+    limit: u32,
+    win_condition: JsValue,
+    budget: JsValue,
+    locale: JsValue,
+) -> Result<JsValue, JsValue> {
+    let locale = parse_locale(&locale)?;
+    let size = parse_size(&color_grid, locale)?;
+    let win = parse_win_condition(win_condition, size, locale)?;
+    let colors = parse_colors(&color_grid, locale)?;
+    let mut budget = parse_budget(&budget, locale)?;
+    let solver = solver::Solver::new(size, colors, win);
+    let solutions =
+        solver.solve_masks_cdcl_with_budget(Mask::EMPTY, Mask::EMPTY, limit as usize, &mut budget);
+    let res = SolutionCountResult::from_budgeted(solutions, limit as usize);
+    to_js(&res, locale)
 }
 
-/// 在“已有部分勾选”的前提下统计解数量（最多枚举到 `limit` 个解）。
+/// 在“已有部分勾选/排除”的前提下统计解数量（最多枚举到 `limit` 个解）。
+///
+/// 搜索与预算约定同 `solution_count`。
 #[wasm_bindgen]
 pub fn solution_count_with_checked(
-    checked_mask: u64,
+    checked_mask: Vec<u8>,
+    unchecked_mask: Vec<u8>,
     color_grid: Vec<u8>,
     limit: u32,
+    win_condition: JsValue,
+    budget: JsValue,
+    locale: JsValue,
 ) -> Result<JsValue, JsValue> {
-    let cell_count = color_grid.len();
-    let size = (cell_count as f64).sqrt() as usize;
-    let colors = parse_colors(&color_grid)?;
-    let solver = solver::Solver::new(size, colors);
-    let solutions = solver.solve_masks_limit_with_checked_mask(checked_mask, limit as usize);
-    let truncated = limit != 0 && (solutions.len() as u32) >= limit;
-    let res = SolutionCountResult {
-        count: solutions.len() as u32,
-        truncated,
-    };
-    serde_wasm_bindgen::to_value(&res).map_err(|e| JsValue::from_str(&e.to_string()))
+    let locale = parse_locale(&locale)?;
+    let size = parse_size(&color_grid, locale)?;
+    let win = parse_win_condition(win_condition, size, locale)?;
+    let checked_mask = parse_mask("checked_mask", &checked_mask, color_grid.len(), locale)?;
+    let unchecked_mask = parse_mask("unchecked_mask", &unchecked_mask, color_grid.len(), locale)?;
+    let colors = parse_colors(&color_grid, locale)?;
+    let mut budget = parse_budget(&budget, locale)?;
+    let solver = solver::Solver::new(size, colors, win);
+    let solutions = solver.solve_masks_cdcl_with_budget(
+        checked_mask,
+        unchecked_mask,
+        limit as usize,
+        &mut budget,
+    );
+    let res = SolutionCountResult::from_budgeted(solutions, limit as usize);
+    to_js(&res, locale)
+}
+
+/// 逐格边际概率（热力图）：每个格子在全部解中被勾选的比例。
+///
+/// - mask 约定同 `solution_count_with_checked`；
+/// - `samples`：解数不超过它时精确枚举，否则抽取这么多个均匀样本估计（固定种子）；0 表示总是精确枚举；
+/// - `max_states` 同 `solution_count_exact`，`budget` 同 `hint_next`；
+/// - 返回值为 JSON：`{cells: number[], count?: bigint, sampled, samples, exhausted?}`；放弃计算时 `cells` 为空。
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn solution_marginals(
+    checked_mask: Vec<u8>,
+    unchecked_mask: Vec<u8>,
+    color_grid: Vec<u8>,
+    samples: u32,
+    max_states: u32,
+    win_condition: JsValue,
+    budget: JsValue,
+    locale: JsValue,
+) -> Result<JsValue, JsValue> {
+    let locale = parse_locale(&locale)?;
+    let size = parse_size(&color_grid, locale)?;
+    let win = parse_win_condition(win_condition, size, locale)?;
+    let checked_mask = parse_mask("checked_mask", &checked_mask, color_grid.len(), locale)?;
+    let unchecked_mask = parse_mask("unchecked_mask", &unchecked_mask, color_grid.len(), locale)?;
+    let mut budget = parse_budget(&budget, locale)?;
+    let res = solution_marginals_native(
+        checked_mask,
+        unchecked_mask,
+        &color_grid,
+        win,
+        samples,
+        max_states as usize,
+        &mut budget,
+    )
+    .map_err(|e| js_error(e, locale))?;
+    to_js(&res, locale)
+}
+
+/// 计算骨干：在所有解中取值都相同的格子（不枚举解，逐格定向求解）。
+///
+/// - mask 约定同 `solution_count_with_checked`，`budget` 同 `hint_next`；
+/// - 返回值为 JSON：`{no_solution, checked: number[], unchecked: number[], exhausted?}`；
+///   预算耗尽时只含已确认的格子。
+#[wasm_bindgen]
+pub fn backbone(
+    checked_mask: Vec<u8>,
+    unchecked_mask: Vec<u8>,
+    color_grid: Vec<u8>,
+    win_condition: JsValue,
+    budget: JsValue,
+    locale: JsValue,
+) -> Result<JsValue, JsValue> {
+    let locale = parse_locale(&locale)?;
+    let size = parse_size(&color_grid, locale)?;
+    let win = parse_win_condition(win_condition, size, locale)?;
+    let checked_mask = parse_mask("checked_mask", &checked_mask, color_grid.len(), locale)?;
+    let unchecked_mask = parse_mask("unchecked_mask", &unchecked_mask, color_grid.len(), locale)?;
+    let colors = parse_colors(&color_grid, locale)?;
+    let mut budget = parse_budget(&budget, locale)?;
+    let solver = solver::Solver::new(size, colors, win);
+    let res = BackboneResult::from_budgeted(solver.backbone(
+        checked_mask,
+        unchecked_mask,
+        &mut budget,
+    ));
+    to_js(&res, locale)
+}
+
+/// 解析推理深度：`"propagate" | "probe" | "backbone"`（可省略，默认 `probe`）。
+fn parse_deduction_depth(depth: JsValue, locale: Locale) -> Result<DeductionDepth, JsValue> {
+    if depth.is_undefined() || depth.is_null() {
+        return Ok(DeductionDepth::Probe);
+    }
+    serde_wasm_bindgen::from_value(depth).map_err(|e| {
+        js_error(
+            EngineError::InvalidDepth {
+                detail: e.to_string(),
+            },
+            locale,
+        )
+    })
+}
+
+/// 批量推导：列出当前局面下所有已能确定、但玩家尚未标记的格子（用于辅助模式/揭示逻辑下一步）。
+///
+/// - mask 约定同 `solution_count_with_checked`，`budget` 同 `hint_next`；
+/// - `depth`：`"propagate"`（只传播）/ `"probe"`（再加单步反证，默认）/ `"backbone"`（完整骨干）；
+/// - 返回值为 JSON：`{noSolution, cells: {cell, checked, depth, ruleId?, reason}[], exhausted?}`。
+#[wasm_bindgen]
+pub fn deductions(
+    checked_mask: Vec<u8>,
+    unchecked_mask: Vec<u8>,
+    color_grid: Vec<u8>,
+    win_condition: JsValue,
+    depth: JsValue,
+    budget: JsValue,
+    locale: JsValue,
+) -> Result<JsValue, JsValue> {
+    let locale = parse_locale(&locale)?;
+    let size = parse_size(&color_grid, locale)?;
+    let win = parse_win_condition(win_condition, size, locale)?;
+    let checked_mask = parse_mask("checked_mask", &checked_mask, color_grid.len(), locale)?;
+    let unchecked_mask = parse_mask("unchecked_mask", &unchecked_mask, color_grid.len(), locale)?;
+    let depth = parse_deduction_depth(depth, locale)?;
+    let colors = parse_colors(&color_grid, locale)?;
+    let mut budget = parse_budget(&budget, locale)?;
+    let solver = solver::Solver::new(size, colors, win);
+    let res = DeductionsResult::from_budgeted(solver.deductions(
+        checked_mask,
+        unchecked_mask,
+        depth,
+        &mut budget,
+    ));
+    to_js_localized(res, locale)
+}
+
+/// 无解时的冲突解释：玩家勾选中互相冲突的极小子集，以及推出矛盾所经过的规则。
+///
+/// - mask 约定同 `solution_count_with_checked`，`budget` 同 `hint_next`；
+/// - 返回值为 JSON：`{solvable, checks: number[], rules: {ruleId, title, cells}[], exhausted?}`；
+///   `checks` 为空而 `solvable=false` 表示排除标记本身已导致无解。
+#[wasm_bindgen]
+pub fn conflict_core(
+    checked_mask: Vec<u8>,
+    unchecked_mask: Vec<u8>,
+    color_grid: Vec<u8>,
+    win_condition: JsValue,
+    budget: JsValue,
+    locale: JsValue,
+) -> Result<JsValue, JsValue> {
+    let locale = parse_locale(&locale)?;
+    let size = parse_size(&color_grid, locale)?;
+    let win = parse_win_condition(win_condition, size, locale)?;
+    let checked_mask = parse_mask("checked_mask", &checked_mask, color_grid.len(), locale)?;
+    let unchecked_mask = parse_mask("unchecked_mask", &unchecked_mask, color_grid.len(), locale)?;
+    let colors = parse_colors(&color_grid, locale)?;
+    let mut budget = parse_budget(&budget, locale)?;
+    let solver = solver::Solver::new(size, colors, win);
+    let res = ConflictCoreResult::from_budgeted(solver.conflict_core(
+        checked_mask,
+        unchecked_mask,
+        &mut budget,
+    ));
+    to_js_localized(res, locale)
+}
+
+/// 精确统计解的数量（不枚举解，适合解很多的宽松棋盘）。
+///
+/// - mask 约定同 `solution_count_with_checked`；
+/// - `max_states`：DP 单层状态数上限（0 表示不限制）；超出时返回 `{exhausted: "states"}`，避免大而空旷的棋盘卡死页面；
+/// - `budget` 同 `hint_next`（节点数按 DP 状态转移计）；
+/// - 返回值为 JSON：`{count?: bigint, exhausted?: string}`。
+#[wasm_bindgen]
+pub fn solution_count_exact(
+    checked_mask: Vec<u8>,
+    unchecked_mask: Vec<u8>,
+    color_grid: Vec<u8>,
+    max_states: u32,
+    win_condition: JsValue,
+    budget: JsValue,
+    locale: JsValue,
+) -> Result<JsValue, JsValue> {
+    let locale = parse_locale(&locale)?;
+    let size = parse_size(&color_grid, locale)?;
+    let win = parse_win_condition(win_condition, size, locale)?;
+    let checked_mask = parse_mask("checked_mask", &checked_mask, color_grid.len(), locale)?;
+    let unchecked_mask = parse_mask("unchecked_mask", &unchecked_mask, color_grid.len(), locale)?;
+    let mut budget = parse_budget(&budget, locale)?;
+    let res = solution_count_exact_native(
+        checked_mask,
+        unchecked_mask,
+        &color_grid,
+        win,
+        max_states as usize,
+        &mut budget,
+    )
+    .map_err(|e| js_error(e, locale))?;
+    to_js(&res, locale)
 }
 
 #[cfg(test)]
@@ -153,8 +870,9 @@ mod solution_count_tests {
     #[test]
     fn solution_count_detects_unique_for_all_black() {
         let grid = vec![Color::Black.to_u8(); crate::masks::CELL_COUNT];
-        let colors = parse_colors(&grid).expect("parse ok");
-        let solver = crate::solver::Solver::new(crate::masks::GRID_SIZE, colors);
+        let colors = parse_colors(&grid, Locale::default()).expect("parse ok");
+        let solver =
+            crate::solver::Solver::new(crate::masks::GRID_SIZE, colors, WinCondition::default());
 
         let solutions = solver.solve_masks_limit(2);
         assert_eq!(solutions.len(), 1);
@@ -164,12 +882,13 @@ mod solution_count_tests {
     fn solution_count_limit2_reports_multiple_when_possible() {
         // 第一行全黑：起始即满足 Bingo，其余格子为白色（几乎无约束），因此必然存在大量解
         let mut grid = vec![Color::White.to_u8(); crate::masks::CELL_COUNT];
-        for i in 0..crate::masks::GRID_SIZE {
-            grid[i] = Color::Black.to_u8();
+        for cell in grid.iter_mut().take(crate::masks::GRID_SIZE) {
+            *cell = Color::Black.to_u8();
         }
 
-        let colors = parse_colors(&grid).expect("parse ok");
-        let solver = crate::solver::Solver::new(crate::masks::GRID_SIZE, colors);
+        let colors = parse_colors(&grid, Locale::default()).expect("parse ok");
+        let solver =
+            crate::solver::Solver::new(crate::masks::GRID_SIZE, colors, WinCondition::default());
 
         let solutions = solver.solve_masks_limit(2);
         assert_eq!(solutions.len(), 2);
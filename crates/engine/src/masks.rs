@@ -1,10 +1,201 @@
-use once_cell::sync::Lazy;
+use std::fmt;
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, Not};
 
-pub type Mask = u64;
+use serde::{Deserialize, Serialize};
 
+use crate::i18n::{msg, Message};
+
+/// 定长位集：`W` 个 u64 字，最多表示 `W * 64` 个格子。
+///
+/// 位序与旧版 `u64` mask 一致：bit i 对应 row-major 的第 i 个格子（bit0 为 (0,0)）。
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Bitset<const W: usize> {
+    words: [u64; W],
+}
+
+/// 棋盘 mask：最多 256 格（16x16）。
+pub type Mask = Bitset<MASK_WORDS>;
+
+pub const MASK_WORDS: usize = 4;
+/// `Mask` 能容纳的最大格子数。
+pub const MAX_CELLS: usize = MASK_WORDS * 64;
+/// 引擎支持的最大边长（`MAX_GRID_SIZE^2 <= MAX_CELLS`）。
+pub const MAX_GRID_SIZE: usize = 16;
+
+#[cfg(test)]
 pub const GRID_SIZE: usize = 5;
+#[cfg(test)]
 pub const CELL_COUNT: usize = GRID_SIZE * GRID_SIZE;
 
+impl<const W: usize> Bitset<W> {
+    pub const EMPTY: Self = Self { words: [0; W] };
+
+    /// 前 `n` 位全为 1 的位集（用于“棋盘内全部格子”）。
+    pub fn full(n: usize) -> Self {
+        let mut out = Self::EMPTY;
+        for (w, word) in out.words.iter_mut().enumerate() {
+            let lo = w * 64;
+            if n >= lo + 64 {
+                *word = u64::MAX;
+            } else if n > lo {
+                *word = (1u64 << (n - lo)) - 1;
+            }
+        }
+        out
+    }
+
+    #[inline]
+    pub fn bit(i: usize) -> Self {
+        let mut out = Self::EMPTY;
+        out.words[i / 64] = 1u64 << (i % 64);
+        out
+    }
+
+    /// 仅用低 64 位构造（便于从旧版 `u64` mask 迁移）。
+    #[inline]
+    pub fn from_u64(v: u64) -> Self {
+        let mut out = Self::EMPTY;
+        out.words[0] = v;
+        out
+    }
+
+    #[inline]
+    pub fn contains(&self, i: usize) -> bool {
+        (self.words[i / 64] >> (i % 64)) & 1 != 0
+    }
+
+    #[inline]
+    pub fn insert(&mut self, i: usize) {
+        self.words[i / 64] |= 1u64 << (i % 64);
+    }
+
+    #[inline]
+    pub fn remove(&mut self, i: usize) {
+        self.words[i / 64] &= !(1u64 << (i % 64));
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|&w| w == 0)
+    }
+
+    #[inline]
+    pub fn count_ones(&self) -> u32 {
+        self.words.iter().map(|w| w.count_ones()).sum()
+    }
+
+    /// `self` 是否为 `other` 的子集。
+    #[inline]
+    pub fn is_subset(&self, other: &Self) -> bool {
+        self.words.iter().zip(other.words.iter()).all(|(a, b)| a & !b == 0)
+    }
+
+    /// 按升序遍历所有置位的下标。
+    pub fn iter_ones(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words.iter().enumerate().flat_map(|(w, &word)| {
+            let mut rest = word;
+            std::iter::from_fn(move || {
+                if rest == 0 {
+                    return None;
+                }
+                let b = rest.trailing_zeros() as usize;
+                rest &= rest - 1;
+                Some(w * 64 + b)
+            })
+        })
+    }
+
+    /// 小端字节编码：byte k 的 bit j 对应格子 `8k + j`；长度为 `ceil(n/8)`。
+    pub fn to_bytes(&self, n: usize) -> Vec<u8> {
+        (0..n.div_ceil(8))
+            .map(|k| (self.words[k / 8] >> ((k % 8) * 8)) as u8)
+            .collect()
+    }
+
+    /// `to_bytes` 的逆操作；超出容量的字节返回 None。
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() > W * 8 {
+            return None;
+        }
+        let mut out = Self::EMPTY;
+        for (k, &b) in bytes.iter().enumerate() {
+            out.words[k / 8] |= (b as u64) << ((k % 8) * 8);
+        }
+        Some(out)
+    }
+}
+
+impl<const W: usize> Default for Bitset<W> {
+    fn default() -> Self {
+        Self::EMPTY
+    }
+}
+
+impl<const W: usize> BitAnd for Bitset<W> {
+    type Output = Self;
+    #[inline]
+    fn bitand(mut self, rhs: Self) -> Self {
+        self &= rhs;
+        self
+    }
+}
+
+impl<const W: usize> BitAndAssign for Bitset<W> {
+    #[inline]
+    fn bitand_assign(&mut self, rhs: Self) {
+        for (a, b) in self.words.iter_mut().zip(rhs.words) {
+            *a &= b;
+        }
+    }
+}
+
+impl<const W: usize> BitOr for Bitset<W> {
+    type Output = Self;
+    #[inline]
+    fn bitor(mut self, rhs: Self) -> Self {
+        self |= rhs;
+        self
+    }
+}
+
+impl<const W: usize> BitOrAssign for Bitset<W> {
+    #[inline]
+    fn bitor_assign(&mut self, rhs: Self) {
+        for (a, b) in self.words.iter_mut().zip(rhs.words) {
+            *a |= b;
+        }
+    }
+}
+
+impl<const W: usize> BitXor for Bitset<W> {
+    type Output = Self;
+    #[inline]
+    fn bitxor(mut self, rhs: Self) -> Self {
+        for (a, b) in self.words.iter_mut().zip(rhs.words) {
+            *a ^= b;
+        }
+        self
+    }
+}
+
+/// 按全部 `W * 64` 位取反；需要限定在棋盘内时请与 `Bitset::full(cell_count)` 求交。
+impl<const W: usize> Not for Bitset<W> {
+    type Output = Self;
+    #[inline]
+    fn not(mut self) -> Self {
+        for w in self.words.iter_mut() {
+            *w = !*w;
+        }
+        self
+    }
+}
+
+impl<const W: usize> fmt::Debug for Bitset<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter_ones()).finish()
+    }
+}
+
 #[inline]
 pub fn cell_index(x: usize, y: usize, size: usize) -> usize {
     x * size + y
@@ -12,75 +203,139 @@ pub fn cell_index(x: usize, y: usize, size: usize) -> usize {
 
 #[inline]
 pub fn cell_bit(i: usize) -> Mask {
-    1u64 << i
+    Mask::bit(i)
+}
+
+/// 计数类线索看的邻域（相对线索格的一组偏移，越界的格子不算）。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Neighborhood {
+    /// 八邻（王步）：红/蓝/紫/橙/数字格的默认邻域。
+    King,
+    /// 上下左右四邻（青格）。
+    Orthogonal,
+    /// 马步可达的八格。
+    Knight,
+    /// 同行、同列距离不超过 2 的八格。
+    Line2,
+    /// 斜向四邻（X 形）。
+    Diagonal,
+}
+
+impl Neighborhood {
+    /// 全部邻域（`BoardMasks`/`RuleSet` 按此顺序建表）。
+    pub const ALL: [Self; 5] = [
+        Self::King,
+        Self::Orthogonal,
+        Self::Knight,
+        Self::Line2,
+        Self::Diagonal,
+    ];
+
+    /// 在 `ALL` 中的下标。
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    /// 相对偏移 `(dr, dc)`；顺序即邻居列表的顺序。
+    pub fn offsets(self) -> &'static [(i32, i32)] {
+        match self {
+            Self::King => &[
+                (-1, -1),
+                (-1, 0),
+                (-1, 1),
+                (0, -1),
+                (0, 1),
+                (1, -1),
+                (1, 0),
+                (1, 1),
+            ],
+            Self::Orthogonal => &[(-1, 0), (1, 0), (0, -1), (0, 1)],
+            Self::Knight => &[
+                (-2, -1),
+                (-2, 1),
+                (-1, -2),
+                (-1, 2),
+                (1, -2),
+                (1, 2),
+                (2, -1),
+                (2, 1),
+            ],
+            Self::Line2 => &[
+                (-2, 0),
+                (-1, 0),
+                (0, -2),
+                (0, -1),
+                (0, 1),
+                (0, 2),
+                (1, 0),
+                (2, 0),
+            ],
+            Self::Diagonal => &[(-1, -1), (-1, 1), (1, -1), (1, 1)],
+        }
+    }
+
+    /// 棋盘内 `(row, col)` 的邻居（按 `offsets` 顺序，row-major 编号）。
+    pub fn cells(self, row: usize, col: usize, size: usize) -> impl Iterator<Item = usize> {
+        self.offsets().iter().filter_map(move |&(dr, dc)| {
+            let r = row.checked_add_signed(dr as isize).filter(|&r| r < size)?;
+            let c = col.checked_add_signed(dc as isize).filter(|&c| c < size)?;
+            Some(cell_index(r, c, size))
+        })
+    }
+
+    /// 提示与规则名称里的叫法（`hood.*`），如“八邻”“马步格”。
+    pub fn noun(self) -> Message {
+        match self {
+            Self::King => msg!("hood.king"),
+            Self::Orthogonal => msg!("hood.orthogonal"),
+            Self::Knight => msg!("hood.knight"),
+            Self::Line2 => msg!("hood.line2"),
+            Self::Diagonal => msg!("hood.diagonal"),
+        }
+    }
 }
 
 pub struct BoardMasks {
-    pub size: usize,
     pub cell_count: usize,
-    pub neighbors_8: Vec<Mask>,
-    pub neighbors_4: Vec<Mask>,
+    /// 各邻域下每格的邻居，按 `Neighborhood::index` 索引（见 [`BoardMasks::neighbors`]）。
+    pub neighborhoods: Vec<Vec<Mask>>,
     pub row_masks: Vec<Mask>,
     pub col_masks: Vec<Mask>,
     pub diag_down_masks: Vec<Mask>,
     pub diag_up_masks: Vec<Mask>,
-    pub line_masks: Vec<Mask>,
 }
 
 impl BoardMasks {
     pub fn new(size: usize) -> Self {
+        assert!(
+            size <= MAX_GRID_SIZE,
+            "size 必须不超过 {MAX_GRID_SIZE}，得到：{size}"
+        );
         let cell_count = size * size;
         let diag_count = if size > 0 { size * 2 - 1 } else { 0 };
-        let line_count = size * 2 + 2;
 
-        let mut neighbors_8 = vec![0u64; cell_count];
-        let mut neighbors_4 = vec![0u64; cell_count];
-        for x in 0..size {
-            for y in 0..size {
-                let idx = cell_index(x, y, size);
-                let mut m8 = 0u64;
-                let mut m4 = 0u64;
-                for dx in -1..=1 {
-                    for dy in -1..=1 {
-                        if dx == 0 && dy == 0 {
-                            continue;
-                        }
-                        let nx = x as i32 + dx;
-                        let ny = y as i32 + dy;
-                        if nx >= 0 && nx < size as i32 && ny >= 0 && ny < size as i32 {
-                            let n_idx = cell_index(nx as usize, ny as usize, size);
-                            m8 |= cell_bit(n_idx);
-                            if dx.abs() + dy.abs() == 1 {
-                                m4 |= cell_bit(n_idx);
-                            }
-                        }
-                    }
-                }
-                neighbors_8[idx] = m8;
-                neighbors_4[idx] = m4;
-            }
-        }
+        let neighborhoods = Neighborhood::ALL
+            .iter()
+            .map(|&hood| {
+                (0..cell_count)
+                    .map(|idx| {
+                        hood.cells(idx / size, idx % size, size)
+                            .fold(Mask::EMPTY, |m, n| m | cell_bit(n))
+                    })
+                    .collect()
+            })
+            .collect();
 
-        let mut row_masks = vec![0u64; size];
-        for r in 0..size {
-            let mut m = 0u64;
-            for c in 0..size {
-                m |= cell_bit(cell_index(r, c, size));
-            }
-            row_masks[r] = m;
-        }
+        let row_masks: Vec<Mask> = (0..size)
+            .map(|r| (0..size).fold(Mask::EMPTY, |m, c| m | cell_bit(cell_index(r, c, size))))
+            .collect();
+        let col_masks: Vec<Mask> = (0..size)
+            .map(|c| (0..size).fold(Mask::EMPTY, |m, r| m | cell_bit(cell_index(r, c, size))))
+            .collect();
 
-        let mut col_masks = vec![0u64; size];
-        for c in 0..size {
-            let mut m = 0u64;
-            for r in 0..size {
-                m |= cell_bit(cell_index(r, c, size));
-            }
-            col_masks[c] = m;
-        }
-
-        let mut diag_down_masks = vec![0u64; diag_count];
-        let mut diag_up_masks = vec![0u64; diag_count];
+        let mut diag_down_masks = vec![Mask::EMPTY; diag_count];
+        let mut diag_up_masks = vec![Mask::EMPTY; diag_count];
         for x in 0..size {
             for y in 0..size {
                 let d_down = x + (size - 1) - y;
@@ -90,32 +345,80 @@ impl BoardMasks {
             }
         }
 
-        let mut line_masks = vec![0u64; line_count];
-        for r in 0..size {
-            line_masks[r] = row_masks[r];
-        }
-        for c in 0..size {
-            line_masks[size + c] = col_masks[c];
-        }
-        let mut diag_main = 0u64;
-        let mut diag_anti = 0u64;
-        for k in 0..size {
-            diag_main |= cell_bit(cell_index(k, k, size));
-            diag_anti |= cell_bit(cell_index(k, size - 1 - k, size));
-        }
-        line_masks[size * 2] = diag_main;
-        line_masks[size * 2 + 1] = diag_anti;
-
         Self {
-            size,
             cell_count,
-            neighbors_8,
-            neighbors_4,
+            neighborhoods,
             row_masks,
             col_masks,
             diag_down_masks,
             diag_up_masks,
-            line_masks,
         }
     }
+
+    /// `cell` 在邻域 `hood` 下的邻居。
+    #[inline]
+    pub fn neighbors(&self, hood: Neighborhood, cell: usize) -> Mask {
+        self.neighborhoods[hood.index()][cell]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bitset_handles_cells_beyond_64() {
+        let mut m = Mask::EMPTY;
+        m.insert(3);
+        m.insert(80);
+        m.insert(143);
+        assert!(m.contains(80) && m.contains(143) && !m.contains(79));
+        assert_eq!(m.count_ones(), 3);
+        assert_eq!(m.iter_ones().collect::<Vec<_>>(), vec![3, 80, 143]);
+
+        let full = Mask::full(81);
+        assert_eq!(full.count_ones(), 81);
+        assert!(m.is_subset(&Mask::full(144)));
+        assert!(!m.is_subset(&full));
+        assert_eq!((Mask::full(144) & !full).count_ones(), 63);
+    }
+
+    #[test]
+    fn bitset_bytes_roundtrip() {
+        let mut m = Mask::EMPTY;
+        for i in [0usize, 7, 8, 63, 64, 100, 143] {
+            m.insert(i);
+        }
+        let bytes = m.to_bytes(144);
+        assert_eq!(bytes.len(), 18);
+        assert_eq!(bytes[0], 0b1000_0001);
+        assert_eq!(bytes[1], 0b0000_0001);
+        assert_eq!(Mask::from_bytes(&bytes), Some(m));
+        assert_eq!(Mask::from_bytes(&[0u8; MAX_CELLS / 8 + 1]), None);
+    }
+
+    #[test]
+    fn board_masks_for_9x9() {
+        let bm = BoardMasks::new(9);
+        assert_eq!(bm.row_masks[8].iter_ones().collect::<Vec<_>>(), (72..81).collect::<Vec<_>>());
+        assert_eq!(bm.col_masks[8].count_ones(), 9);
+        assert!(bm.col_masks[8].contains(80));
+        assert_eq!(bm.neighbors(Neighborhood::King, 80).count_ones(), 3);
+    }
+
+    #[test]
+    fn neighborhoods_on_5x5() {
+        let bm = BoardMasks::new(5);
+        let ones = |hood, cell| bm.neighbors(hood, cell).iter_ones().collect::<Vec<_>>();
+        // 中心 (2,2)
+        assert_eq!(ones(Neighborhood::King, 12), vec![6, 7, 8, 11, 13, 16, 17, 18]);
+        assert_eq!(ones(Neighborhood::Orthogonal, 12), vec![7, 11, 13, 17]);
+        assert_eq!(ones(Neighborhood::Knight, 12), vec![1, 3, 5, 9, 15, 19, 21, 23]);
+        assert_eq!(ones(Neighborhood::Line2, 12), vec![2, 7, 10, 11, 13, 14, 17, 22]);
+        assert_eq!(ones(Neighborhood::Diagonal, 12), vec![6, 8, 16, 18]);
+        // 角上 (0,0)：越界的偏移不算
+        assert_eq!(ones(Neighborhood::Knight, 0), vec![7, 11]);
+        assert_eq!(ones(Neighborhood::Line2, 0), vec![1, 2, 5, 10]);
+        assert_eq!(ones(Neighborhood::Diagonal, 0), vec![6]);
+    }
 }
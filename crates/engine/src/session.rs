@@ -0,0 +1,516 @@
+//! 解题会话：每道题创建一次，随玩家操作增量维护求解状态。
+//!
+//! - 构造时只建一次 `Solver`（规则表、邻域、决策顺序）；
+//! - `assign`/`retract` 在快照栈上推进/回退：每次赋值只从该格出发增量传播；
+//! - 提示/计数/校验都基于栈顶快照回答，并复用“与当前赋值相容的解”缓存，
+//!   大多数操作下提示无需重新搜索。
+
+use wasm_bindgen::prelude::*;
+
+use crate::colors::{parse_grid, Color};
+use crate::error::EngineError;
+use crate::i18n::Locale;
+use crate::masks::Mask;
+use crate::solver::{
+    rule_for, DeductionDepth, GradedHint, HintResult, SolveBudget, Solver, TracedState,
+};
+use crate::validate::{validate_colors, ValidateResult};
+use crate::win::WinCondition;
+use crate::{BackboneResult, DeductionsResult, RepairResult, SolutionCountResult};
+
+/// 一个快照：某次赋值后的传播结果与解缓存。
+#[derive(Clone)]
+struct Snapshot {
+    /// 传播到不动点后的状态；None 表示从这一步起已经矛盾。
+    traced: Option<TracedState>,
+    /// 与该状态相容的一个解：None 为尚未计算，Some(None) 为确定无解。
+    solution: Option<Option<Mask>>,
+}
+
+struct Frame {
+    cell: usize,
+    checked: bool,
+    snapshot: Snapshot,
+}
+
+#[wasm_bindgen]
+pub struct PuzzleSession {
+    solver: Solver,
+    size: usize,
+    colors: Vec<Color>,
+    win: WinCondition,
+    cell_count: usize,
+    black_mask: Mask,
+    base: Snapshot,
+    frames: Vec<Frame>,
+    /// WASM 方法返回的文字与错误信息所用的语言。
+    locale: Locale,
+}
+
+impl PuzzleSession {
+    /// Rust 原生构造：校验颜色布局并建立只含黑格的初始快照。
+    pub fn from_grid(color_grid: &[u8], win: WinCondition) -> Result<Self, EngineError> {
+        let (size, colors) = parse_grid(color_grid)?;
+        let cell_count = colors.len();
+        let mut black_mask = Mask::EMPTY;
+        for (i, &color) in colors.iter().enumerate() {
+            if rule_for(color).is_some_and(|rule| rule.fixes_checked()) {
+                black_mask.insert(i);
+            }
+        }
+
+        let solver = Solver::new(size, colors.clone(), win);
+        let traced = solver.traced_initial();
+        let solution = if traced.is_none() { Some(None) } else { None };
+        Ok(Self {
+            solver,
+            size,
+            colors,
+            win,
+            cell_count,
+            black_mask,
+            base: Snapshot { traced, solution },
+            frames: Vec::new(),
+            locale: Locale::default(),
+        })
+    }
+
+    fn top(&self) -> &Snapshot {
+        self.frames.last().map_or(&self.base, |f| &f.snapshot)
+    }
+
+    fn check_cell(&self, cell: usize) -> Result<(), EngineError> {
+        if cell >= self.cell_count {
+            return Err(EngineError::CellOutOfRange {
+                index: cell,
+                cell_count: self.cell_count,
+            });
+        }
+        Ok(())
+    }
+
+    /// 在 `prev` 之上推进一步赋值；上一步的解若与新赋值相容则直接沿用。
+    fn step(&self, prev: &Snapshot, cell: usize, checked: bool) -> Snapshot {
+        let traced = prev
+            .traced
+            .as_ref()
+            .and_then(|t| self.solver.traced_assign(t, cell, checked));
+        let solution = match (&traced, prev.solution) {
+            (None, _) => Some(None),
+            (Some(_), Some(Some(sol))) if sol.contains(cell) == checked => Some(Some(sol)),
+            _ => None,
+        };
+        Snapshot { traced, solution }
+    }
+
+    /// 玩家确认勾选（`checked=true`）或排除（`checked=false`）某格；已有相反赋值时先撤回。
+    ///
+    /// 黑格固定勾选：排除黑格返回错误（无状态接口里勾选优先于排除，会话不能与之分歧）。
+    pub fn assign_cell(&mut self, cell: usize, checked: bool) -> Result<(), EngineError> {
+        self.check_cell(cell)?;
+        if !checked && self.black_mask.contains(cell) {
+            return Err(EngineError::FixedCellExcluded { index: cell });
+        }
+        if let Some(frame) = self.frames.iter().find(|f| f.cell == cell) {
+            if frame.checked == checked {
+                return Ok(());
+            }
+            self.retract_cell(cell)?;
+        }
+        let snapshot = self.step(self.top(), cell, checked);
+        self.frames.push(Frame {
+            cell,
+            checked,
+            snapshot,
+        });
+        Ok(())
+    }
+
+    /// 撤回某格的赋值（恢复为未知）；只会重放该格之后的赋值。
+    pub fn retract_cell(&mut self, cell: usize) -> Result<(), EngineError> {
+        self.check_cell(cell)?;
+        let Some(pos) = self.frames.iter().position(|f| f.cell == cell) else {
+            return Ok(());
+        };
+
+        let replay: Vec<(usize, bool)> = self
+            .frames
+            .drain(pos..)
+            .skip(1)
+            .map(|f| (f.cell, f.checked))
+            .collect();
+        for (cell, checked) in replay {
+            let snapshot = self.step(self.top(), cell, checked);
+            self.frames.push(Frame {
+                cell,
+                checked,
+                snapshot,
+            });
+        }
+        Ok(())
+    }
+
+    /// 当前“已确认勾选”的格子（含黑格）。
+    pub fn checked(&self) -> Mask {
+        self.frames
+            .iter()
+            .filter(|f| f.checked)
+            .fold(self.black_mask, |m, f| m | Mask::bit(f.cell))
+    }
+
+    /// 当前“已确认不勾选”（排除）的格子。
+    pub fn unchecked(&self) -> Mask {
+        self.frames
+            .iter()
+            .filter(|f| !f.checked)
+            .fold(Mask::EMPTY, |m, f| m | Mask::bit(f.cell))
+    }
+
+    /// 玩家的操作顺序（按时间先后，撤回的操作不计）。
+    pub fn history(&self) -> Vec<usize> {
+        self.frames.iter().map(|f| f.cell).collect()
+    }
+
+    /// 当前标记无解时的最小修复（见 `Solver::minimal_repair`），同样少时优先撤销最近的操作。
+    pub fn repair_result(&self, release_exclusions: bool, budget: &mut SolveBudget) -> RepairResult {
+        RepairResult::from_budgeted(self.solver.minimal_repair(
+            self.checked(),
+            self.unchecked(),
+            &self.history(),
+            release_exclusions,
+            budget,
+        ))
+    }
+
+    /// 当前赋值在传播层面是否仍无矛盾（不代表一定有解）。
+    pub fn consistent(&self) -> bool {
+        self.top().traced.is_some()
+    }
+
+    /// 与 `Solver::hint_next_with_budget` 相同的提示，但基于栈顶快照，并缓存求得的解。
+    pub fn hint_result(&mut self, budget: &mut SolveBudget) -> HintResult {
+        let (checked, unchecked) = (self.checked(), self.unchecked());
+        let history = self.history();
+        let top = self.frames.last_mut().map_or(&mut self.base, |f| &mut f.snapshot);
+        self.solver.traced_hint(
+            checked,
+            unchecked,
+            top.traced.as_mut(),
+            &mut top.solution,
+            &history,
+            budget,
+        )
+    }
+
+    /// 基于栈顶快照的分级提示（见 `Solver::hint_with_level`）。
+    pub fn hint_level_result(&mut self, level: u8, budget: &mut SolveBudget) -> GradedHint {
+        GradedHint::from_result(self.size, self.hint_result(budget), level)
+    }
+
+    /// 在当前赋值下于预算内统计解（最多 `limit` 个，limit=0 表示不限制）。
+    ///
+    /// 与 `solution_count` 一样走冲突驱动搜索，但从栈顶快照的传播结果出发，并复用/回填解缓存。
+    pub fn count_result(&mut self, limit: usize, budget: &mut SolveBudget) -> SolutionCountResult {
+        let top = self.frames.last_mut().map_or(&mut self.base, |f| &mut f.snapshot);
+        SolutionCountResult::from_budgeted(
+            self.solver
+                .traced_count(top.traced.as_ref(), &mut top.solution, limit, budget),
+            limit,
+        )
+    }
+
+    /// 当前赋值下的骨干（所有解中取值都相同的格子），复用并回填快照上的解缓存。
+    pub fn backbone_result(&mut self, budget: &mut SolveBudget) -> BackboneResult {
+        let top = self.frames.last_mut().map_or(&mut self.base, |f| &mut f.snapshot);
+        BackboneResult::from_budgeted(self.solver.traced_backbone(
+            top.traced.as_ref(),
+            &mut top.solution,
+            budget,
+        ))
+    }
+
+    /// 当前赋值下的批量推导（见 `Solver::deductions`），复用并回填快照上的解缓存。
+    pub fn deductions_result(
+        &mut self,
+        depth: DeductionDepth,
+        budget: &mut SolveBudget,
+    ) -> DeductionsResult {
+        let givens = self.checked() | self.unchecked();
+        let top = self.frames.last_mut().map_or(&mut self.base, |f| &mut f.snapshot);
+        DeductionsResult::from_budgeted(self.solver.traced_deductions(
+            givens,
+            top.traced.as_ref(),
+            &mut top.solution,
+            depth,
+            budget,
+        ))
+    }
+
+    /// 按当前勾选（含黑格）校验颜色规则与胜利条件。
+    pub fn validate_result(&self) -> ValidateResult {
+        validate_colors(self.checked(), self.size, &self.colors, self.win)
+    }
+}
+
+#[wasm_bindgen]
+impl PuzzleSession {
+    /// 创建会话：`color_grid` 为颜色数组（u8，row-major），`win_condition`、`locale` 可省略。
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        color_grid: Vec<u8>,
+        win_condition: JsValue,
+        locale: JsValue,
+    ) -> Result<PuzzleSession, JsValue> {
+        let locale = crate::parse_locale(&locale)?;
+        let size = crate::parse_size(&color_grid, locale)?;
+        let win = crate::parse_win_condition(win_condition, size, locale)?;
+        let session = Self::from_grid(&color_grid, win).map_err(|e| crate::js_error(e, locale))?;
+        Ok(Self { locale, ..session })
+    }
+
+    /// 切换之后返回的文字所用的语言（`"zh-CN"`、`"en"` 等）。
+    pub fn set_locale(&mut self, locale: JsValue) -> Result<(), JsValue> {
+        self.locale = crate::parse_locale(&locale)?;
+        Ok(())
+    }
+
+    /// 确认某格为勾选（`checked=true`）或排除（`checked=false`）；排除黑格时抛出 `fixed_cell_excluded`。
+    pub fn assign(&mut self, cell: usize, checked: bool) -> Result<(), JsValue> {
+        self.assign_cell(cell, checked)
+            .map_err(|e| crate::js_error(e, self.locale))
+    }
+
+    /// 撤回某格的赋值。
+    pub fn retract(&mut self, cell: usize) -> Result<(), JsValue> {
+        self.retract_cell(cell)
+            .map_err(|e| crate::js_error(e, self.locale))
+    }
+
+    /// 当前赋值在传播层面是否无矛盾。
+    pub fn is_consistent(&self) -> bool {
+        self.consistent()
+    }
+
+    /// 当前勾选 mask（小端字节数组，含黑格）。
+    pub fn checked_mask(&self) -> Vec<u8> {
+        self.checked().to_bytes(self.cell_count)
+    }
+
+    /// 当前排除 mask（小端字节数组）。
+    pub fn unchecked_mask(&self) -> Vec<u8> {
+        self.unchecked().to_bytes(self.cell_count)
+    }
+
+    /// 提示下一步（返回值同 `hint_next`；`budget` 可省略，约定同 `hint_next`）。
+    pub fn hint(&mut self, budget: JsValue) -> Result<JsValue, JsValue> {
+        let mut budget = crate::parse_budget(&budget, self.locale)?;
+        let res = self.hint_result(&mut budget);
+        crate::to_js_localized(res, self.locale)
+    }
+
+    /// 分级提示（返回值同 `hint_with_level`；`budget` 可省略）。
+    pub fn hint_with_level(&mut self, level: u8, budget: JsValue) -> Result<JsValue, JsValue> {
+        let mut budget = crate::parse_budget(&budget, self.locale)?;
+        let res = self.hint_level_result(level, &mut budget);
+        crate::to_js_localized(res, self.locale)
+    }
+
+    /// 统计当前赋值下的解数量（参数与返回值同 `solution_count_with_checked`）。
+    pub fn solution_count(&mut self, limit: u32, budget: JsValue) -> Result<JsValue, JsValue> {
+        let mut budget = crate::parse_budget(&budget, self.locale)?;
+        let res = self.count_result(limit as usize, &mut budget);
+        crate::to_js(&res, self.locale)
+    }
+
+    /// 当前赋值下的骨干（返回值同 `backbone`；`budget` 可省略）。
+    pub fn backbone(&mut self, budget: JsValue) -> Result<JsValue, JsValue> {
+        let mut budget = crate::parse_budget(&budget, self.locale)?;
+        let res = self.backbone_result(&mut budget);
+        crate::to_js(&res, self.locale)
+    }
+
+    /// 批量推导（参数与返回值同 `deductions`；`depth`、`budget` 可省略）。
+    pub fn deductions(&mut self, depth: JsValue, budget: JsValue) -> Result<JsValue, JsValue> {
+        let depth = crate::parse_deduction_depth(depth, self.locale)?;
+        let mut budget = crate::parse_budget(&budget, self.locale)?;
+        let res = self.deductions_result(depth, &mut budget);
+        crate::to_js_localized(res, self.locale)
+    }
+
+    /// 最小修复（返回值同 `minimal_repair`；`budget` 可省略），按本会话的操作顺序排序。
+    pub fn repair(&self, release_exclusions: bool, budget: JsValue) -> Result<JsValue, JsValue> {
+        let mut budget = crate::parse_budget(&budget, self.locale)?;
+        let res = self.repair_result(release_exclusions, &mut budget);
+        crate::to_js_localized(res, self.locale)
+    }
+
+    /// 校验当前勾选（返回值同 `validate_state`）。
+    pub fn validate(&self) -> Result<JsValue, JsValue> {
+        let res = self.validate_result();
+        crate::to_js_localized(res, self.locale)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::masks::GRID_SIZE;
+    use crate::validate::validate_state;
+
+    fn generated_grid(seed: u64) -> Vec<u8> {
+        crate::generate::generate_puzzle(seed, GRID_SIZE, WinCondition::default())
+            .expect("generate ok")
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+
+    fn fresh_solver(grid: &[u8]) -> Solver {
+        let colors = grid.iter().map(|&v| Color::from_u8(v).unwrap()).collect();
+        Solver::new(GRID_SIZE, colors, WinCondition::default())
+    }
+
+    #[test]
+    fn session_matches_stateless_queries() {
+        let grid = generated_grid(123);
+        let solver = fresh_solver(&grid);
+        let mut session = PuzzleSession::from_grid(&grid, WinCondition::default()).unwrap();
+
+        // 排除黑格被拒绝：无状态接口里勾选优先于排除，会话不能因此进入矛盾。
+        let black = grid
+            .iter()
+            .position(|&v| v == Color::Black.to_u8())
+            .expect("generated puzzle has a black cell");
+        assert_eq!(
+            session.assign_cell(black, false),
+            Err(EngineError::FixedCellExcluded { index: black })
+        );
+        assert!(session.consistent() && !session.unchecked().contains(black));
+
+        // 按提示一步步推进，每一步都与无状态接口对照。
+        for _ in 0..GRID_SIZE * GRID_SIZE {
+            let (checked, unchecked) = (session.checked(), session.unchecked());
+            let expected = solver.hint_next(checked, unchecked);
+            let got = session.hint_result(&mut SolveBudget::default());
+            assert_eq!(
+                serde_json::to_value(&got.status).unwrap(),
+                serde_json::to_value(&expected.status).unwrap()
+            );
+            // 建议的格子、动作与理由也必须一致，而不只是状态。
+            let mv_of = |h: &HintResult| {
+                h.mv.as_ref()
+                    .map(|mv| (mv.cell, serde_json::to_value(mv.action).unwrap()))
+            };
+            assert_eq!(mv_of(&got), mv_of(&expected));
+            let reason_of = |h: &HintResult| {
+                h.reason
+                    .as_ref()
+                    .map(|r| (r.rule_id, r.affected_cells.clone()))
+            };
+            assert_eq!(reason_of(&got), reason_of(&expected));
+            let deduced = |cells: &[crate::solver::DeducedCell]| {
+                let mut v: Vec<(usize, bool)> = cells.iter().map(|d| (d.cell, d.checked)).collect();
+                v.sort_unstable();
+                v
+            };
+            let expected_deductions = solver.deductions(
+                checked,
+                unchecked,
+                DeductionDepth::Backbone,
+                &mut SolveBudget::default(),
+            );
+            let got_deductions =
+                session.deductions_result(DeductionDepth::Backbone, &mut SolveBudget::default());
+            assert_eq!(
+                deduced(&got_deductions.cells),
+                deduced(&expected_deductions.value.unwrap_or_default())
+            );
+            let graded = session.hint_level_result(2, &mut SolveBudget::default());
+            assert_eq!(
+                graded.tiers.len(),
+                GradedHint::from_result(GRID_SIZE, expected.clone(), 2).tiers.len()
+            );
+            assert_eq!(
+                session.count_result(0, &mut SolveBudget::default()).count as usize,
+                solver
+                    .solve_masks_limit_with_checked_mask(checked, unchecked, 0)
+                    .len()
+            );
+
+            let Some(mv) = got.mv else { break };
+            let checked = matches!(mv.action, crate::solver::HintAction::Check);
+            session.assign_cell(mv.cell, checked).unwrap();
+        }
+        let validate = validate_state(session.checked(), &grid, WinCondition::default()).unwrap();
+        assert_eq!(session.validate_result().is_valid, validate.is_valid);
+        assert_eq!(session.validate_result().is_bingo, validate.is_bingo);
+    }
+
+    #[test]
+    fn retract_replays_later_assignments() {
+        let grid = generated_grid(5);
+        let solver = fresh_solver(&grid);
+        let mut session = PuzzleSession::from_grid(&grid, WinCondition::default()).unwrap();
+
+        let cells: Vec<usize> = (0..GRID_SIZE * GRID_SIZE)
+            .filter(|&c| grid[c] != Color::Black.to_u8())
+            .take(4)
+            .collect();
+        for (k, &cell) in cells.iter().enumerate() {
+            session.assign_cell(cell, k % 2 == 0).unwrap();
+        }
+        // 撤回中间一格，并把另一格改成相反值。
+        session.retract_cell(cells[1]).unwrap();
+        session.assign_cell(cells[2], true).unwrap();
+
+        let (checked, unchecked) = (session.checked(), session.unchecked());
+        assert!(!checked.contains(cells[1]) && !unchecked.contains(cells[1]));
+        assert!(checked.contains(cells[2]));
+        assert_eq!(
+            session.count_result(0, &mut SolveBudget::default()).count as usize,
+            solver
+                .solve_masks_limit_with_checked_mask(checked, unchecked, 0)
+                .len()
+        );
+        assert!(matches!(
+            session.assign_cell(GRID_SIZE * GRID_SIZE, true),
+            Err(EngineError::CellOutOfRange { .. })
+        ));
+    }
+
+    #[test]
+    fn count_reuses_cached_solution() {
+        // 全白盘面：一个节点的预算搜不到解；提示缓存的解可以直接回答 limit=1 的计数。
+        let grid = vec![Color::White.to_u8(); GRID_SIZE * GRID_SIZE];
+        let mut session = PuzzleSession::from_grid(&grid, WinCondition::default()).unwrap();
+        let tight = || SolveBudget {
+            max_nodes: 1,
+            ..SolveBudget::default()
+        };
+        assert!(session.count_result(1, &mut tight()).exhausted.is_some());
+
+        session.hint_result(&mut SolveBudget::default());
+        let res = session.count_result(1, &mut tight());
+        assert_eq!((res.count, res.truncated, res.exhausted), (1, true, None));
+    }
+
+    #[test]
+    fn repair_prefers_latest_session_moves() {
+        // 中心蓝格最多两邻勾选，依次勾了四个邻居：应撤销最后勾的两个。
+        let mut grid = vec![Color::White.to_u8(); GRID_SIZE * GRID_SIZE];
+        let center = GRID_SIZE * (GRID_SIZE / 2) + GRID_SIZE / 2;
+        grid[center] = Color::Blue.to_u8();
+        let mut session = PuzzleSession::from_grid(&grid, WinCondition::default()).unwrap();
+        let order = [center - 1, center - GRID_SIZE, center + 1, center + GRID_SIZE];
+        for cell in order {
+            session.assign_cell(cell, true).unwrap();
+        }
+
+        let repair = session.repair_result(false, &mut SolveBudget::default());
+        assert!(!repair.solvable);
+        let cells: Vec<usize> = repair.moves.iter().map(|m| m.cell).collect();
+        assert_eq!(cells, vec![order[3], order[2]]);
+        let hint = session.hint_result(&mut SolveBudget::default());
+        assert_eq!(hint.mv.map(|m| m.cell), Some(order[3]));
+    }
+}
@@ -0,0 +1,215 @@
+//! 骨干（backbone）：在所有解中取值都相同的格子。
+//!
+//! 不枚举解：先传播，再求一个参考解；对每个仍是候选的格子，假设它取参考解的相反值做一次定向求解——
+//! 无解则该格属于骨干（立即固定下来并传播，加强后续求解）；有解则新解与参考解不同的格子全部移出候选。
+//! 求解次数至多为候选格数，实际通常少得多。
+
+use super::{
+    try_set_checked_id, try_set_unchecked_id, AssignReason, BudgetMeter, Budgeted, SolveBudget,
+    Solver, SolverState, TracedState,
+};
+use crate::masks::Mask;
+
+/// 骨干：所有解中都勾选 / 都不勾选的格子（含黑格与调用方已确认的格子）。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Backbone {
+    pub checked: Mask,
+    pub unchecked: Mask,
+}
+
+impl Backbone {
+    fn of(state: &SolverState) -> Self {
+        let cell_count = state.size() * state.size();
+        Self {
+            checked: state.to_row_major_mask(),
+            unchecked: (0..cell_count)
+                .filter(|&id| state.is_unchecked_id(id))
+                .fold(Mask::EMPTY, |m, id| m | Mask::bit(id)),
+        }
+    }
+}
+
+impl Solver {
+    /// 计算骨干（见模块文档）。
+    ///
+    /// - mask 约定同 [`Solver::solve_masks_cdcl_with_budget`]；
+    /// - `value` 为 None 表示已证明无解；
+    /// - 预算耗尽时只返回已确认的部分（真实骨干的子集），并在 `exhausted` 中标明停止原因。
+    pub fn backbone(
+        &self,
+        checked_mask: Mask,
+        unchecked_mask: Mask,
+        budget: &mut SolveBudget,
+    ) -> Budgeted<Option<Backbone>> {
+        let (checked_mask, unchecked_mask) = self.normalize_masks(checked_mask, unchecked_mask);
+        let mut meter = BudgetMeter::new(budget);
+        let mut obs = ();
+        let Some(mut state) = self.initial_state_with_masks(checked_mask, unchecked_mask, &mut obs)
+        else {
+            return meter.finish(None);
+        };
+        if !self.propagate_to_fixpoint(&mut state, &mut obs) {
+            return meter.finish(None);
+        }
+        let mut reference = None;
+        let value = self.backbone_from(state, &mut reference, &mut meter);
+        meter.finish(value)
+    }
+
+    /// 基于会话状态计算骨干；`solution` 为快照上的解缓存（约定同 `traced_hint`）。
+    pub(crate) fn traced_backbone(
+        &self,
+        traced: Option<&TracedState>,
+        solution: &mut Option<Option<Mask>>,
+        budget: &mut SolveBudget,
+    ) -> Budgeted<Option<Backbone>> {
+        let mut meter = BudgetMeter::new(budget);
+        let value = match (traced, *solution) {
+            (None, _) | (_, Some(None)) => None,
+            (Some(t), _) => self.backbone_from(t.state.clone(), solution, &mut meter),
+        };
+        meter.finish(value)
+    }
+
+    /// 从已传播到不动点的 `state` 出发计算骨干。
+    ///
+    /// `reference` 为参考解缓存：None 时先搜索一个解，并在预算内完成时写回。
+    pub(super) fn backbone_from(
+        &self,
+        mut state: SolverState,
+        reference: &mut Option<Option<Mask>>,
+        meter: &mut BudgetMeter,
+    ) -> Option<Backbone> {
+        let solution = match *reference {
+            Some(cached) => cached?,
+            None => {
+                let mut out = Vec::new();
+                self.search(state.clone(), 1, &mut out, 0, meter);
+                if meter.stopped().is_some() {
+                    return Some(Backbone::of(&state));
+                }
+                *reference = Some(out.first().copied());
+                out.first().copied()?
+            }
+        };
+
+        let cell_count = self.rules.size * self.rules.size;
+        let mut candidates = (0..cell_count)
+            .filter(|&id| state.is_unknown_id(id))
+            .fold(Mask::EMPTY, |m, id| m | Mask::bit(id));
+        loop {
+            let Some(cell) = candidates.iter_ones().next() else {
+                break;
+            };
+            candidates.remove(cell);
+            if !state.is_unknown_id(cell) {
+                continue;
+            }
+
+            // 定向求解：该格取参考解的相反值时是否仍有解。
+            let checked = solution.contains(cell);
+            let mut probe = state.clone();
+            let ok = if checked {
+                try_set_unchecked_id(&mut probe, cell, AssignReason::Guess, &mut ())
+            } else {
+                try_set_checked_id(&mut probe, cell, AssignReason::Guess, &mut ())
+            };
+            let mut out = Vec::new();
+            if ok {
+                self.search(probe, 1, &mut out, 0, meter);
+            }
+
+            if let Some(&other) = out.first() {
+                candidates &= !(other ^ solution);
+            } else if meter.stopped().is_some() {
+                break;
+            } else {
+                let ok = if checked {
+                    try_set_checked_id(&mut state, cell, AssignReason::Initial, &mut ())
+                } else {
+                    try_set_unchecked_id(&mut state, cell, AssignReason::Initial, &mut ())
+                };
+                // 参考解满足全部约束，固定骨干格后传播不会矛盾。
+                let propagated = ok && self.propagate_to_fixpoint(&mut state, &mut ());
+                debug_assert!(propagated, "骨干格与参考解一致");
+            }
+        }
+        Some(Backbone::of(&state))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::colors::Color;
+    use crate::solver::testing::{random_board, random_mask, test_rng, BASE_CLUES};
+    use crate::solver::BudgetStop;
+    use crate::win::WinCondition;
+
+    /// 由全部解直接求交/并得到的骨干。
+    fn enumerated(
+        solver: &Solver,
+        checked: Mask,
+        unchecked: Mask,
+        cell_count: usize,
+    ) -> Option<Backbone> {
+        let solutions = solver.solve_masks_limit_with_checked_mask(checked, unchecked, 0);
+        let first = *solutions.first()?;
+        let all = solutions.iter().fold(first, |m, &s| m & s);
+        let any = solutions.iter().fold(first, |m, &s| m | s);
+        Some(Backbone {
+            checked: all,
+            unchecked: Mask::full(cell_count) & !any,
+        })
+    }
+
+    #[test]
+    fn matches_enumeration_on_random_boards() {
+        let mut rng = test_rng(5);
+        for _ in 0..40 {
+            let colors = random_board(&mut rng, 5, &BASE_CLUES, 0.6);
+            let unchecked = random_mask(&mut rng, 25, 1.0 / 8.0, Mask::EMPTY);
+            let solver = Solver::new(5, colors, WinCondition::default());
+            let got = solver.backbone(Mask::EMPTY, unchecked, &mut SolveBudget::default());
+            assert!(got.is_finished());
+            assert_eq!(got.value, enumerated(&solver, Mask::EMPTY, unchecked, 25));
+        }
+    }
+
+    #[test]
+    fn exhausted_budget_returns_confirmed_subset() {
+        let solver = Solver::new(6, vec![Color::White; 36], WinCondition::default());
+        let unchecked = Mask::bit(0) | Mask::bit(7);
+        let mut budget = SolveBudget {
+            max_nodes: 1,
+            ..SolveBudget::default()
+        };
+        let got = solver.backbone(Mask::EMPTY, unchecked, &mut budget);
+        assert_eq!(got.exhausted, Some(BudgetStop::Nodes));
+        let backbone = got.value.expect("not proven unsolvable");
+        assert_eq!(backbone.unchecked, unchecked);
+        assert!(backbone.checked.is_empty());
+    }
+
+    #[test]
+    fn session_backbone_reuses_cached_solution() {
+        let grid = crate::generate::generate_puzzle(7, 5, WinCondition::default()).unwrap();
+        let colors: Vec<Color> = grid
+            .into_iter()
+            .flatten()
+            .map(|v| Color::from_u8(v).unwrap())
+            .collect();
+        let solver = Solver::new(5, colors, WinCondition::default());
+        let traced = solver.traced_initial();
+        let mut cache = None;
+        let got = solver.traced_backbone(traced.as_ref(), &mut cache, &mut SolveBudget::default());
+        assert!(matches!(cache, Some(Some(_))));
+        assert_eq!(got.value, enumerated(&solver, Mask::EMPTY, Mask::EMPTY, 25));
+        assert_eq!(
+            solver
+                .traced_backbone(traced.as_ref(), &mut cache, &mut SolveBudget::default())
+                .value,
+            got.value
+        );
+    }
+}
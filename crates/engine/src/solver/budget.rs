@@ -0,0 +1,289 @@
+//! 求解预算：节点数/耗时上限、进度回调与取消标记。
+//!
+//! 搜索通过 `SolveObserver::should_abort` 在每个节点询问 [`BudgetMeter`]，
+//! 超出预算时整棵搜索树立即收束，调用方拿到“已找到的部分结果 + 停止原因”。
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serde::Serialize;
+
+use super::SolveObserver;
+
+/// 未指定 `progress_every` 时，进度回调的默认间隔（节点数）。
+const DEFAULT_PROGRESS_EVERY: u64 = 10_000;
+/// 每隔多少个节点读一次时钟（WASM 上读时钟需要跨到 JS，开销不可忽略）。
+const CLOCK_EVERY: u64 = 256;
+
+/// 预算耗尽的原因。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetStop {
+    /// 搜索节点数达到 `max_nodes`。
+    Nodes,
+    /// 耗时达到 `max_millis`。
+    Time,
+    /// 取消标记被置位，或进度回调要求停止。
+    Cancelled,
+    /// 精确计数的 DP 状态数超过上限。
+    States,
+}
+
+/// 进度回调收到的快照。
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SolveProgress {
+    pub nodes: u64,
+    pub solutions: u64,
+    pub elapsed_ms: u64,
+}
+
+/// 求解预算。`0` 一律表示“不限制”，`SolveBudget::default()` 即无限预算。
+#[derive(Default)]
+pub struct SolveBudget<'a> {
+    pub max_nodes: u64,
+    pub max_millis: u64,
+    /// 进度回调间隔（节点数）；为 0 时取默认值。
+    pub progress_every: u64,
+    /// 进度回调：返回 false 表示取消。
+    pub progress: Option<Box<dyn FnMut(SolveProgress) -> bool + 'a>>,
+    pub cancel: Option<&'a AtomicBool>,
+}
+
+/// 带预算的求解结果：`exhausted` 为 None 表示正常完成，否则 `value` 只是部分结果。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Budgeted<T> {
+    pub value: T,
+    pub exhausted: Option<BudgetStop>,
+    /// 实际消耗的搜索节点数（精确计数为 DP 状态转移数）。
+    pub nodes: u64,
+}
+
+impl<T> Budgeted<T> {
+    pub fn is_finished(&self) -> bool {
+        self.exhausted.is_none()
+    }
+}
+
+fn now_ms() -> f64 {
+    #[cfg(target_arch = "wasm32")]
+    {
+        js_sys::Date::now()
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs_f64() * 1000.0)
+            .unwrap_or(0.0)
+    }
+}
+
+/// 一次求解过程中的预算计量（同一次提示/计数里的多次搜索共享同一个计量）。
+pub(crate) struct BudgetMeter<'b, 'a> {
+    budget: &'b mut SolveBudget<'a>,
+    start_ms: f64,
+    nodes: u64,
+    solutions: u64,
+    /// 上次检查时的节点数：同一节点上重复检查不会重复回调进度。
+    checked_at: u64,
+    stopped: Option<BudgetStop>,
+}
+
+impl<'b, 'a> BudgetMeter<'b, 'a> {
+    pub(crate) fn new(budget: &'b mut SolveBudget<'a>) -> Self {
+        Self {
+            budget,
+            start_ms: now_ms(),
+            nodes: 0,
+            solutions: 0,
+            checked_at: 0,
+            stopped: None,
+        }
+    }
+
+    pub(crate) fn stopped(&self) -> Option<BudgetStop> {
+        self.stopped
+    }
+
+    /// 记一个节点并检查预算；返回 true 表示应当停止。
+    pub(crate) fn tick(&mut self) -> bool {
+        self.nodes += 1;
+        self.check()
+    }
+
+    /// 主动标记停止（如精确计数的状态数超限）。
+    pub(crate) fn stop(&mut self, reason: BudgetStop) {
+        self.stopped.get_or_insert(reason);
+    }
+
+    pub(crate) fn finish<T>(self, value: T) -> Budgeted<T> {
+        Budgeted {
+            value,
+            exhausted: self.stopped,
+            nodes: self.nodes,
+        }
+    }
+
+    fn check(&mut self) -> bool {
+        if self.stopped.is_some() {
+            return true;
+        }
+        if self.nodes == self.checked_at {
+            return false;
+        }
+        self.checked_at = self.nodes;
+        let budget = &mut *self.budget;
+        if budget.max_nodes != 0 && self.nodes > budget.max_nodes {
+            self.stopped = Some(BudgetStop::Nodes);
+        } else if budget.cancel.is_some_and(|c| c.load(Ordering::Relaxed)) {
+            self.stopped = Some(BudgetStop::Cancelled);
+        } else if budget.max_millis != 0
+            && self.nodes.is_multiple_of(CLOCK_EVERY)
+            && now_ms() - self.start_ms >= budget.max_millis as f64
+        {
+            self.stopped = Some(BudgetStop::Time);
+        } else if let Some(progress) = budget.progress.as_mut() {
+            let every = if budget.progress_every == 0 {
+                DEFAULT_PROGRESS_EVERY
+            } else {
+                budget.progress_every
+            };
+            if self.nodes.is_multiple_of(every) {
+                let snapshot = SolveProgress {
+                    nodes: self.nodes,
+                    solutions: self.solutions,
+                    elapsed_ms: (now_ms() - self.start_ms).max(0.0) as u64,
+                };
+                if !progress(snapshot) {
+                    self.stopped = Some(BudgetStop::Cancelled);
+                }
+            }
+        }
+        self.stopped.is_some()
+    }
+}
+
+impl SolveObserver for BudgetMeter<'_, '_> {
+    fn on_node(&mut self, _depth: u32) {
+        self.nodes += 1;
+    }
+
+    fn on_solution(&mut self) {
+        self.solutions += 1;
+    }
+
+    fn should_abort(&mut self) -> bool {
+        self.check()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::colors::Color;
+    use crate::masks::Mask;
+    use crate::solver::{HintStatus, Solver};
+    use crate::win::WinCondition;
+
+    fn all_white(size: usize) -> Solver {
+        Solver::new(
+            size,
+            vec![Color::White; size * size],
+            WinCondition::default(),
+        )
+    }
+
+    #[test]
+    fn unlimited_budget_matches_plain_search() {
+        let solver = all_white(5);
+        let res = solver.solve_masks_limit_with_budget(
+            Mask::EMPTY,
+            Mask::EMPTY,
+            500,
+            &mut SolveBudget::default(),
+        );
+        assert!(res.is_finished());
+        assert_eq!(res.value, solver.solve_masks_limit(500));
+    }
+
+    #[test]
+    fn node_budget_returns_partial_answer() {
+        let solver = all_white(6);
+        let mut budget = SolveBudget {
+            max_nodes: 1_000,
+            ..SolveBudget::default()
+        };
+        let res = solver.solve_masks_limit_with_budget(Mask::EMPTY, Mask::EMPTY, 0, &mut budget);
+        assert_eq!(res.exhausted, Some(BudgetStop::Nodes));
+        assert!(res.nodes <= 1_001);
+        assert!(!res.value.is_empty());
+
+        let mut budget = SolveBudget {
+            max_nodes: 1,
+            ..SolveBudget::default()
+        };
+        let hint = solver.hint_next_with_budget(Mask::EMPTY, Mask::EMPTY, &mut budget);
+        assert!(matches!(hint.status, HintStatus::Exhausted));
+        assert_eq!(hint.exhausted, Some(BudgetStop::Nodes));
+
+        let mut budget = SolveBudget {
+            max_nodes: 10,
+            ..SolveBudget::default()
+        };
+        let exact = solver.count_solutions_exact(Mask::EMPTY, Mask::EMPTY, 0, &mut budget);
+        assert_eq!(
+            (exact.value, exact.exhausted),
+            (None, Some(BudgetStop::Nodes))
+        );
+        let exact =
+            solver.count_solutions_exact(Mask::EMPTY, Mask::EMPTY, 8, &mut SolveBudget::default());
+        assert_eq!(exact.exhausted, Some(BudgetStop::States));
+    }
+
+    #[test]
+    fn progress_callback_sees_nodes_and_can_cancel() {
+        let solver = all_white(6);
+        let mut seen = Vec::new();
+        let mut budget = SolveBudget {
+            progress_every: 100,
+            progress: Some(Box::new(|p: SolveProgress| {
+                seen.push(p.nodes);
+                seen.len() < 3
+            })),
+            ..SolveBudget::default()
+        };
+        let res = solver.solve_masks_limit_with_budget(Mask::EMPTY, Mask::EMPTY, 0, &mut budget);
+        assert_eq!(res.exhausted, Some(BudgetStop::Cancelled));
+        drop(budget);
+        assert_eq!(seen, vec![100, 200, 300]);
+    }
+
+    #[test]
+    fn cancel_flag_stops_search() {
+        let solver = all_white(6);
+        let cancel = AtomicBool::new(true);
+        let mut budget = SolveBudget {
+            cancel: Some(&cancel),
+            ..SolveBudget::default()
+        };
+        let res = solver.solve_masks_limit_with_budget(Mask::EMPTY, Mask::EMPTY, 0, &mut budget);
+        assert_eq!(res.exhausted, Some(BudgetStop::Cancelled));
+        assert!(res.value.is_empty());
+    }
+
+    #[test]
+    fn human_analysis_counts_propagations_on_the_meter() {
+        let solver = all_white(5);
+        let full = solver.analyze_human_difficulty(&mut SolveBudget::default());
+        assert!(full.is_finished());
+        assert!(full.nodes > 1);
+
+        let mut budget = SolveBudget {
+            max_nodes: 1,
+            ..SolveBudget::default()
+        };
+        let res = solver.analyze_human_difficulty(&mut budget);
+        assert_eq!(res.exhausted, Some(BudgetStop::Nodes));
+        assert!(!res.value.solved);
+    }
+}
@@ -0,0 +1,774 @@
+//! 冲突驱动的搜索（CDCL）：学习 nogood、非时序回跳、按活跃度选变量。
+//!
+//! 与 `Solver::search` 的区别：
+//! - 传播仍复用 `propagate_to_fixpoint`，但每个传播赋值都记下“由哪条规则实例推出”——
+//!   即该实例的作用范围（如红格的八邻、绿格的行 + 列、Bingo 的全部连线格）。
+//!   规则实例只依赖范围内格子的取值，所以“范围内在它之前已确定的格子”就是这次赋值的充分理由；
+//! - 冲突时沿赋值轨迹做 1-UIP 归结，得到一条 nogood（学习子句），回跳到子句中次高的决策层并立即断言；
+//! - 学习子句用双观察文字（two watched literals）做单元传播，与规则传播交替进行直到不动点；
+//! - 决策变量按 VSIDS 活跃度挑选（参与冲突越多越优先），同分时沿用静态的 `decision_order`；
+//! - 枚举多解时按时序回溯：找到解后把最深一层未翻转的决策翻转（该层标记为“已翻转”），
+//!   回跳不越过最高的已翻转层，已翻转层上的冲突改为时序回溯。学习子句只由规则推出、不含“已找到的解”，
+//!   因此每个解恰好被找到一次，解集与 `search` 完全相同（顺序可能不同）。
+
+use super::{
+    try_set_checked_id, try_set_unchecked_id, AssignReason, BudgetMeter, Budgeted, RuleType,
+    SolveBudget, SolveObserver, Solver, SolverState, TracedState,
+};
+use crate::colors::Color;
+use crate::masks::Mask;
+
+const NO_LEVEL: u32 = u32::MAX;
+/// 每次冲突后活跃度增量放大的倍数（等价于其余变量按 0.95 衰减）。
+const ACTIVITY_DECAY: f64 = 0.95;
+const ACTIVITY_RESCALE: f64 = 1e100;
+
+/// 文字编码：`cell * 2 + checked`，表示“该格取值为 checked”。
+type Lit = u32;
+
+fn lit(cell: usize, checked: bool) -> Lit {
+    (cell as u32) << 1 | checked as u32
+}
+
+fn lit_cell(l: Lit) -> usize {
+    (l >> 1) as usize
+}
+
+fn lit_checked(l: Lit) -> bool {
+    l & 1 == 1
+}
+
+fn lit_is_true(state: &SolverState, l: Lit) -> bool {
+    if lit_checked(l) {
+        state.is_checked_id(lit_cell(l))
+    } else {
+        state.is_unchecked_id(lit_cell(l))
+    }
+}
+
+fn lit_is_false(state: &SolverState, l: Lit) -> bool {
+    if lit_checked(l) {
+        state.is_unchecked_id(lit_cell(l))
+    } else {
+        state.is_checked_id(lit_cell(l))
+    }
+}
+
+/// 一个赋值的成因。
+#[derive(Clone, Copy, Debug)]
+enum Antecedent {
+    /// 初始赋值（黑格、调用方给定的 mask），位于第 0 层。
+    Root,
+    /// 决策（含翻转后的决策）。
+    Decision,
+    /// 规则传播：下标指向 `Trail::scopes`。
+    Rule(u32),
+    /// 学习子句的单元传播：下标指向 `Cdcl::clauses`。
+    Clause(u32),
+}
+
+/// 赋值轨迹；同时作为 `propagate_to_fixpoint` 的观察者，记录每个传播赋值的成因。
+struct Trail<'s> {
+    solver: &'s Solver,
+    /// 胜利条件涉及的全部格子（Bingo 传播的作用范围）。
+    win_scope: Mask,
+
+    level: Vec<u32>,
+    pos: Vec<u32>,
+    antecedent: Vec<Antecedent>,
+    cells: Vec<usize>,
+    /// 第 k+1 层开始时的轨迹长度。
+    level_starts: Vec<usize>,
+    /// 第 k+1 层的决策是否已翻转（另一分支的解已全部枚举）。
+    flipped: Vec<bool>,
+
+    /// 规则实例的作用范围，按首次产生赋值的顺序追加。
+    scopes: Vec<Mask>,
+    /// 第 k+1 层开始时的 `scopes` 长度（回跳时一并回收）。
+    scope_starts: Vec<usize>,
+    rule: Option<RuleType>,
+    focus: Vec<usize>,
+    /// 当前规则实例已登记的作用范围（同一实例的多次赋值共用）。
+    scope_id: Option<u32>,
+    /// 子句传播时由调用方设置：接下来的赋值归因于该子句。
+    clause_reason: Option<u32>,
+}
+
+impl<'s> Trail<'s> {
+    fn new(solver: &'s Solver) -> Self {
+        let cell_count = solver.rules.size * solver.rules.size;
+        Self {
+            solver,
+            win_scope: solver.win_scope(),
+            level: vec![NO_LEVEL; cell_count],
+            pos: vec![0; cell_count],
+            antecedent: vec![Antecedent::Root; cell_count],
+            cells: Vec::with_capacity(cell_count),
+            level_starts: Vec::new(),
+            flipped: Vec::new(),
+            scopes: Vec::new(),
+            scope_starts: Vec::new(),
+            rule: None,
+            focus: Vec::new(),
+            scope_id: None,
+            clause_reason: None,
+        }
+    }
+
+    fn decision_level(&self) -> u32 {
+        self.level_starts.len() as u32
+    }
+
+    fn new_level(&mut self, flipped: bool) {
+        self.level_starts.push(self.cells.len());
+        self.flipped.push(flipped);
+        self.scope_starts.push(self.scopes.len());
+    }
+
+    /// 撤销 `level` 层之上的全部赋值（状态本身由调用方从快照恢复）。
+    fn backjump(&mut self, level: u32) {
+        let level = level as usize;
+        if level >= self.level_starts.len() {
+            return;
+        }
+        for &cell in &self.cells[self.level_starts[level]..] {
+            self.level[cell] = NO_LEVEL;
+        }
+        self.cells.truncate(self.level_starts[level]);
+        self.scopes.truncate(self.scope_starts[level]);
+        self.level_starts.truncate(level);
+        self.flipped.truncate(level);
+        self.scope_starts.truncate(level);
+        self.scope_id = None;
+    }
+
+    /// 最高的已翻转层（没有时为 0）：回跳不能越过它，否则会重新枚举已找到的解。
+    fn highest_flipped(&self) -> u32 {
+        self.flipped.iter().rposition(|&f| f).map_or(0, |k| k as u32 + 1)
+    }
+
+    fn decisions(&self) -> Mask {
+        self.level_starts
+            .iter()
+            .fold(Mask::EMPTY, |m, &start| m | Mask::bit(self.cells[start]))
+    }
+
+    /// 把 `state` 中已确定的格子记为第 0 层的初始赋值（从已传播的快照出发时使用）。
+    fn assume_root(&mut self, state: &SolverState) {
+        let cell_count = self.solver.rules.size * self.solver.rules.size;
+        for cell in 0..cell_count {
+            if !state.is_unknown_id(cell) {
+                self.on_cell_assignment(cell, state.is_checked_id(cell), AssignReason::Initial);
+            }
+        }
+    }
+
+    /// 当前规则实例的作用范围（见 [`Solver::rule_scope`]）。
+    fn current_scope(&self) -> Mask {
+        self.solver.rule_scope(self.rule, &self.focus, self.win_scope)
+    }
+}
+
+impl Solver {
+    /// 胜利条件涉及的全部格子（Bingo 传播的作用范围）。
+    pub(super) fn win_scope(&self) -> Mask {
+        self.rules
+            .win_segments
+            .iter()
+            .flatten()
+            .fold(Mask::EMPTY, |m, &id| m | Mask::bit(id))
+    }
+
+    /// 规则实例（`rule` + 传播时上报的焦点格）的作用范围：实例只依赖这些格子的取值。
+    ///
+    /// `win_scope` 为 [`Solver::win_scope`] 的缓存。
+    pub(super) fn rule_scope(
+        &self,
+        rule: Option<RuleType>,
+        focus: &[usize],
+        win_scope: Mask,
+    ) -> Mask {
+        let rules = &self.rules;
+        let size = rules.size;
+        let line = |id: usize| -> Mask {
+            let (row, col) = (id / size, id % size);
+            (0..size).fold(Mask::EMPTY, |m, k| {
+                m | Mask::bit(row * size + k) | Mask::bit(k * size + col)
+            })
+        };
+        let diagonals = |id: usize| -> Mask {
+            let (row, col) = (id / size, id % size);
+            rules.diag_down_cells[row + (size - 1) - col]
+                .iter()
+                .chain(&rules.diag_up_cells[row + col])
+                .fold(Mask::EMPTY, |m, &c| m | Mask::bit(c))
+        };
+
+        match rule {
+            Some(RuleType::FiveInRow) => win_scope,
+            // 奇偶联立的焦点是参与组合的紫/橙格，作用范围是它们各自的邻域。
+            Some(RuleType::Parity) => focus
+                .iter()
+                .filter_map(|&id| Some(rules.neighbors(rules.parity_clue(id)?.1, id)))
+                .flatten()
+                .fold(Mask::EMPTY, |m, &c| m | Mask::bit(c)),
+            // 计数链的焦点是串联的绿/黄格，作用范围是它们连起来的行列/对角线。
+            Some(RuleType::CountChain) => focus.iter().fold(Mask::EMPTY, |m, &id| {
+                if rules.cells(Color::Green).binary_search(&id).is_ok() {
+                    m | line(id)
+                } else {
+                    m | diagonals(id)
+                }
+            }),
+            Some(_) if !focus.is_empty() => focus
+                .iter()
+                .fold(Mask::EMPTY, |m, &c| m | Mask::bit(c)),
+            // 兜底：范围未知时取全盘（理由更弱，但仍然成立）。
+            _ => Mask::full(size * size),
+        }
+    }
+}
+
+impl SolveObserver for Trail<'_> {
+    fn on_rule_enter(&mut self, rule: RuleType) {
+        self.rule = Some(rule);
+        self.focus.clear();
+        self.scope_id = None;
+        self.clause_reason = None;
+    }
+
+    fn on_rule_focus(&mut self, rule: RuleType, cells: &[usize]) {
+        self.rule = Some(rule);
+        self.focus.clear();
+        self.focus.extend_from_slice(cells);
+        self.scope_id = None;
+    }
+
+    fn on_cell_assignment(&mut self, cell: usize, _is_checked: bool, reason: AssignReason) {
+        let antecedent = match reason {
+            AssignReason::Initial => Antecedent::Root,
+            AssignReason::Guess => Antecedent::Decision,
+            AssignReason::Propagate => match self.clause_reason {
+                Some(clause) => Antecedent::Clause(clause),
+                None => {
+                    let id = match self.scope_id {
+                        Some(id) => id,
+                        None => {
+                            let scope = self.current_scope();
+                            self.scopes.push(scope);
+                            let id = (self.scopes.len() - 1) as u32;
+                            self.scope_id = Some(id);
+                            id
+                        }
+                    };
+                    Antecedent::Rule(id)
+                }
+            },
+        };
+        self.level[cell] = self.decision_level();
+        self.pos[cell] = self.cells.len() as u32;
+        self.antecedent[cell] = antecedent;
+        self.cells.push(cell);
+    }
+}
+
+/// 一次 CDCL 求解的全部可变状态。
+struct Cdcl<'s> {
+    trail: Trail<'s>,
+    /// 学习子句；长度 >= 2 的子句由前两个文字观察。
+    clauses: Vec<Vec<Lit>>,
+    /// `watches[l]`：观察文字 `l` 的子句（`l` 变为假时检查）。
+    watches: Vec<Vec<u32>>,
+    /// 轨迹上尚未做子句传播的起点。
+    qhead: usize,
+    activity: Vec<f64>,
+    activity_inc: f64,
+    /// `snapshots[k]`：第 k 层传播完毕、开启第 k+1 层之前的状态。
+    snapshots: Vec<SolverState>,
+}
+
+impl<'s> Cdcl<'s> {
+    fn new(solver: &'s Solver) -> Self {
+        let cell_count = solver.rules.size * solver.rules.size;
+        Self {
+            trail: Trail::new(solver),
+            clauses: Vec::new(),
+            watches: vec![Vec::new(); cell_count * 2],
+            qhead: 0,
+            activity: vec![0.0; cell_count],
+            activity_inc: 1.0,
+            snapshots: Vec::new(),
+        }
+    }
+
+    /// 规则传播与子句传播交替到不动点；冲突时返回冲突涉及的格子。
+    fn propagate(&mut self, state: &mut SolverState) -> Option<Mask> {
+        let solver = self.trail.solver;
+        loop {
+            if !solver.propagate_to_fixpoint(state, &mut self.trail) {
+                return Some(self.trail.current_scope());
+            }
+            let before = self.trail.cells.len();
+            if let Err(clause) = self.propagate_clauses(state) {
+                return Some(
+                    self.clauses[clause as usize]
+                        .iter()
+                        .fold(Mask::EMPTY, |m, &l| m | Mask::bit(lit_cell(l))),
+                );
+            }
+            if self.trail.cells.len() == before {
+                return None;
+            }
+        }
+    }
+
+    /// 双观察文字的单元传播；冲突时返回冲突子句下标。
+    fn propagate_clauses(&mut self, state: &mut SolverState) -> Result<(), u32> {
+        while self.qhead < self.trail.cells.len() {
+            let cell = self.trail.cells[self.qhead];
+            self.qhead += 1;
+            let false_lit = lit(cell, !state.is_checked_id(cell));
+
+            let mut watchers = std::mem::take(&mut self.watches[false_lit as usize]);
+            let mut i = 0;
+            let mut conflict = None;
+            while i < watchers.len() {
+                let ci = watchers[i];
+                let clause = &mut self.clauses[ci as usize];
+                if clause[0] == false_lit {
+                    clause.swap(0, 1);
+                }
+                if lit_is_true(state, clause[0]) {
+                    i += 1;
+                    continue;
+                }
+                if let Some(k) = (2..clause.len()).find(|&k| !lit_is_false(state, clause[k])) {
+                    clause.swap(1, k);
+                    self.watches[clause[1] as usize].push(ci);
+                    watchers.swap_remove(i);
+                    continue;
+                }
+                let unit = clause[0];
+                if lit_is_false(state, unit) {
+                    conflict = Some(ci);
+                    break;
+                }
+                self.trail.clause_reason = Some(ci);
+                let ok = if lit_checked(unit) {
+                    try_set_checked_id(
+                        state,
+                        lit_cell(unit),
+                        AssignReason::Propagate,
+                        &mut self.trail,
+                    )
+                } else {
+                    try_set_unchecked_id(
+                        state,
+                        lit_cell(unit),
+                        AssignReason::Propagate,
+                        &mut self.trail,
+                    )
+                };
+                self.trail.clause_reason = None;
+                if !ok {
+                    // 单元文字与状态自身的约束冲突：子句无法满足，按冲突处理。
+                    conflict = Some(ci);
+                    break;
+                }
+                i += 1;
+            }
+            self.watches[false_lit as usize].append(&mut watchers);
+            if let Some(ci) = conflict {
+                return Err(ci);
+            }
+        }
+        Ok(())
+    }
+
+    /// 1-UIP 冲突分析：返回学习子句（首个文字为断言文字）与回跳层。
+    ///
+    /// 轨迹不满足分析的前提（当前层的待归结格没有成因）时返回 None，由调用方退回时序回溯。
+    fn analyze(&mut self, state: &SolverState, conflict: Mask) -> Option<(Vec<Lit>, u32)> {
+        let current = self.trail.decision_level();
+        let mut conflict = conflict;
+        if !conflict.iter_ones().any(|c| self.trail.level[c] == current) {
+            // 规则冲突总会牵涉当前层；万一没有，退回“否定全部决策”这一平凡 nogood。
+            conflict = self.trail.decisions();
+        }
+
+        let mut seen = Mask::EMPTY;
+        let mut pending = 0usize;
+        let mut lower: Vec<usize> = Vec::new();
+        self.mark(conflict, u32::MAX, &mut seen, &mut pending, &mut lower);
+
+        let mut idx = self.trail.cells.len();
+        let uip = loop {
+            idx = idx.checked_sub(1)?;
+            let p = self.trail.cells[idx];
+            if !seen.contains(p) {
+                continue;
+            }
+            pending -= 1;
+            if pending == 0 {
+                break p;
+            }
+            let reason = match self.trail.antecedent[p] {
+                Antecedent::Rule(s) => self.trail.scopes[s as usize],
+                Antecedent::Clause(ci) => self.clauses[ci as usize]
+                    .iter()
+                    .fold(Mask::EMPTY, |m, &l| m | Mask::bit(lit_cell(l))),
+                // 当前层只有决策本身没有成因，且它最先入轨；走到这里说明轨迹已不一致。
+                Antecedent::Root | Antecedent::Decision => return None,
+            };
+            self.mark(
+                reason,
+                self.trail.pos[p],
+                &mut seen,
+                &mut pending,
+                &mut lower,
+            );
+        };
+        self.decay();
+
+        let mut learnt = vec![lit(uip, !state.is_checked_id(uip))];
+        let mut back = 0u32;
+        let mut second = 1usize;
+        for (k, &c) in lower.iter().enumerate() {
+            learnt.push(lit(c, !state.is_checked_id(c)));
+            if self.trail.level[c] > back {
+                back = self.trail.level[c];
+                second = k + 1;
+            }
+        }
+        if learnt.len() > 1 {
+            learnt.swap(1, second);
+        }
+        Some((learnt, back))
+    }
+
+    /// 把 `cells` 中早于轨迹位置 `before` 的赋值并入冲突：当前层的待归结，更低层的进入学习子句。
+    fn mark(
+        &mut self,
+        cells: Mask,
+        before: u32,
+        seen: &mut Mask,
+        pending: &mut usize,
+        lower: &mut Vec<usize>,
+    ) {
+        let current = self.trail.decision_level();
+        for c in cells.iter_ones() {
+            let level = self.trail.level[c];
+            if level == NO_LEVEL || level == 0 || self.trail.pos[c] >= before || seen.contains(c) {
+                continue;
+            }
+            seen.insert(c);
+            self.bump(c);
+            if level == current {
+                *pending += 1;
+            } else {
+                lower.push(c);
+            }
+        }
+    }
+
+    fn bump(&mut self, cell: usize) {
+        self.activity[cell] += self.activity_inc;
+        if self.activity[cell] > ACTIVITY_RESCALE {
+            for a in &mut self.activity {
+                *a /= ACTIVITY_RESCALE;
+            }
+            self.activity_inc /= ACTIVITY_RESCALE;
+        }
+    }
+
+    fn decay(&mut self) {
+        self.activity_inc /= ACTIVITY_DECAY;
+    }
+
+    /// 回跳到 `level` 层，记录学习子句并断言其首个文字。
+    ///
+    /// 断言文字无法赋值时返回 false：`level` 层本身已无解，由调用方退回时序回溯。
+    fn learn(&mut self, state: &mut SolverState, learnt: Vec<Lit>, level: u32) -> bool {
+        self.trail.backjump(level);
+        *state = self.snapshots[level as usize].clone();
+        self.qhead = self.qhead.min(self.trail.cells.len());
+
+        let ci = self.clauses.len() as u32;
+        if learnt.len() >= 2 {
+            self.watches[learnt[0] as usize].push(ci);
+            self.watches[learnt[1] as usize].push(ci);
+        }
+        let unit = learnt[0];
+        self.clauses.push(learnt);
+
+        self.trail.clause_reason = Some(ci);
+        let ok = if lit_checked(unit) {
+            try_set_checked_id(
+                state,
+                lit_cell(unit),
+                AssignReason::Propagate,
+                &mut self.trail,
+            )
+        } else {
+            try_set_unchecked_id(
+                state,
+                lit_cell(unit),
+                AssignReason::Propagate,
+                &mut self.trail,
+            )
+        };
+        self.trail.clause_reason = None;
+        ok
+    }
+
+    /// 活跃度最高的未知格；同分取 `decision_order` 中靠前者。
+    fn pick_decision(&self, state: &SolverState) -> Option<usize> {
+        let mut best: Option<usize> = None;
+        for &id in &self.trail.solver.rules.decision_order {
+            if state.is_unknown_id(id) && best.is_none_or(|b| self.activity[id] > self.activity[b])
+            {
+                best = Some(id);
+            }
+        }
+        best
+    }
+
+    /// 开启新的一层并决策 `cell`；决策值无法赋值时撤销这一层并返回 false。
+    fn decide(
+        &mut self,
+        state: &mut SolverState,
+        cell: usize,
+        checked: bool,
+        flipped: bool,
+    ) -> bool {
+        let level = self.trail.decision_level() as usize;
+        self.snapshots.truncate(level);
+        self.snapshots.push(state.clone());
+        self.trail.new_level(flipped);
+        let ok = if checked {
+            try_set_checked_id(state, cell, AssignReason::Guess, &mut self.trail)
+        } else {
+            try_set_unchecked_id(state, cell, AssignReason::Guess, &mut self.trail)
+        };
+        if !ok {
+            self.trail.backjump(level as u32);
+            *state = self.snapshots[level].clone();
+            self.qhead = self.qhead.min(self.trail.cells.len());
+        }
+        ok
+    }
+
+    /// 时序回溯：弹出已翻转的层，翻转最深的未翻转决策；没有可翻转的决策时返回 false（搜索结束）。
+    fn next_branch(&mut self, state: &mut SolverState) -> bool {
+        while let Some(&flipped) = self.trail.flipped.last() {
+            let level = self.trail.decision_level() - 1;
+            let cell = self.trail.cells[self.trail.level_starts[level as usize]];
+            let checked = state.is_checked_id(cell);
+            self.trail.backjump(level);
+            *state = self.snapshots[level as usize].clone();
+            self.qhead = self.qhead.min(self.trail.cells.len());
+            // 翻转后的取值无法赋值时，这一层两个分支都已穷尽，继续向上找。
+            if !flipped && self.decide(state, cell, !checked, true) {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+impl Solver {
+    /// 在“已有部分勾选/排除”的前提下用冲突驱动搜索求解，返回最多 `limit` 个解（limit=0 视为不限制）。
+    ///
+    /// 约定：
+    /// - `checked_mask` 表达“已确认勾选”，`unchecked_mask` 表达“已确认不勾选”；两者都未置位的格子视为“未知”；
+    /// - 同时出现在两个 mask 中的格子以 `checked_mask` 为准；
+    /// - 黑格（Color::Black）依然强制勾选，与 `checked_mask` 无关；
+    /// - 若初始赋值或传播阶段产生矛盾，直接返回空解集；
+    /// - 预算按决策次数计节点；耗尽时返回已找到的解，并在 `exhausted` 中标明停止原因。
+    ///
+    /// 解集与按时间顺序回溯的搜索相同，但顺序可能不同。
+    pub fn solve_masks_cdcl_with_budget(
+        &self,
+        checked_mask: Mask,
+        unchecked_mask: Mask,
+        limit: usize,
+        budget: &mut SolveBudget,
+    ) -> Budgeted<Vec<Mask>> {
+        let (checked_mask, unchecked_mask) = self.normalize_masks(checked_mask, unchecked_mask);
+        let mut meter = BudgetMeter::new(budget);
+        let mut cdcl = Cdcl::new(self);
+        let value = match self.initial_state_with_masks(checked_mask, unchecked_mask, &mut cdcl.trail)
+        {
+            Some(state) => self.cdcl_from(cdcl, state, limit, &mut meter),
+            None => Vec::new(),
+        };
+        meter.finish(value)
+    }
+
+    /// 基于会话状态统计解（约定同 [`Solver::solve_masks_cdcl_with_budget`]）。
+    ///
+    /// 从快照上已传播到不动点的状态出发；`solution` 为快照上的解缓存（约定同 `traced_hint`）：
+    /// 已知无解或 `limit == 1` 且已有缓存时不再搜索，搜到解时回填缓存。
+    pub(crate) fn traced_count(
+        &self,
+        traced: Option<&TracedState>,
+        solution: &mut Option<Option<Mask>>,
+        limit: usize,
+        budget: &mut SolveBudget,
+    ) -> Budgeted<Vec<Mask>> {
+        let mut meter = BudgetMeter::new(budget);
+        let value = match (traced, *solution) {
+            (None, _) | (_, Some(None)) => Vec::new(),
+            (Some(_), Some(Some(cached))) if limit == 1 => vec![cached],
+            (Some(t), _) => {
+                let mut cdcl = Cdcl::new(self);
+                cdcl.trail.assume_root(&t.state);
+                let out = self.cdcl_from(cdcl, t.state.clone(), limit, &mut meter);
+                if let Some(&first) = out.first() {
+                    *solution = Some(Some(first));
+                } else if meter.stopped().is_none() {
+                    *solution = Some(None);
+                }
+                out
+            }
+        };
+        meter.finish(value)
+    }
+
+    /// 从 `state`（第 0 层的赋值已记入 `cdcl` 的轨迹）出发做冲突驱动搜索。
+    fn cdcl_from(
+        &self,
+        mut cdcl: Cdcl,
+        mut state: SolverState,
+        limit: usize,
+        meter: &mut BudgetMeter,
+    ) -> Vec<Mask> {
+        let mut out = Vec::new();
+        loop {
+            let conflict = match cdcl.propagate(&mut state) {
+                Some(conflict) => conflict,
+                None if state.is_fully_decided() => {
+                    meter.on_solution();
+                    out.push(state.to_row_major_mask());
+                    if (limit != 0 && out.len() >= limit) || !cdcl.next_branch(&mut state) {
+                        break;
+                    }
+                    continue;
+                }
+                None => {
+                    if meter.tick() {
+                        break;
+                    }
+                    let Some(cell) = cdcl.pick_decision(&state) else {
+                        break;
+                    };
+                    // 与 `search` 一致：先试“不勾选”；某个取值无法赋值时直接走另一个分支。
+                    if !cdcl.decide(&mut state, cell, false, false)
+                        && !cdcl.decide(&mut state, cell, true, true)
+                        && !cdcl.next_branch(&mut state)
+                    {
+                        break;
+                    }
+                    continue;
+                }
+            };
+            let floor = cdcl.trail.highest_flipped();
+            if floor == cdcl.trail.decision_level() {
+                // 冲突发生在已翻转层（或第 0 层）：该层两个分支都已穷尽。
+                if !cdcl.next_branch(&mut state) {
+                    break;
+                }
+                continue;
+            }
+            let learned = match cdcl.analyze(&state, conflict) {
+                Some((learnt, level)) => cdcl.learn(&mut state, learnt, level.max(floor)),
+                None => false,
+            };
+            // 分析失败（或断言文字无法赋值）时按时间顺序翻转最深的未翻转决策（结果仍然正确，只是少了剪枝）。
+            if !learned && !cdcl.next_branch(&mut state) {
+                break;
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::colors::Color;
+    use crate::solver::testing::{random_board, random_mask, test_rng, BASE_CLUES};
+    use crate::solver::BudgetStop;
+    use crate::win::WinCondition;
+
+    fn sorted(mut masks: Vec<Mask>) -> Vec<Mask> {
+        masks.sort_by_key(|m| m.iter_ones().collect::<Vec<_>>());
+        masks
+    }
+
+    #[test]
+    fn matches_plain_search_on_random_boards() {
+        // 全部颜色随机混排 + 随机勾选/排除：解集必须与 `solve_masks_limit_with_checked_mask` 完全一致。
+        let mut rng = test_rng(7);
+        for round in 0..60 {
+            let size = 4 + round % 3;
+            let colors = random_board(&mut rng, size, &BASE_CLUES, 0.25);
+            let checked = random_mask(&mut rng, size * size, 1.0 / 12.0, Mask::EMPTY);
+            let unchecked = random_mask(&mut rng, size * size, 1.0 / 12.0, checked);
+            let win = WinCondition::InARow {
+                length: 4,
+                diagonals: round % 2 == 0,
+            };
+            let solver = Solver::new(size, colors, win);
+            let plain = solver.solve_masks_limit_with_checked_mask(checked, unchecked, 0);
+            let cdcl = solver.solve_masks_cdcl_with_budget(
+                checked,
+                unchecked,
+                0,
+                &mut SolveBudget::default(),
+            );
+            assert!(cdcl.is_finished());
+            assert_eq!(sorted(cdcl.value), sorted(plain.clone()), "round {round}");
+
+            let limited = solver.solve_masks_cdcl_with_budget(
+                checked,
+                unchecked,
+                2,
+                &mut SolveBudget::default(),
+            );
+            assert_eq!(limited.value.len(), plain.len().min(2));
+            assert!(limited.value.iter().all(|s| plain.contains(s)));
+        }
+    }
+
+    #[test]
+    fn learns_and_backjumps_on_generated_puzzle() {
+        let grid = crate::generate::generate_puzzle(123, 5, WinCondition::default()).unwrap();
+        let colors: Vec<Color> = grid
+            .into_iter()
+            .flatten()
+            .map(|v| Color::from_u8(v).unwrap())
+            .collect();
+        let solver = Solver::new(5, colors, WinCondition::default());
+        let plain = solver.solve_masks_limit(0);
+        let cdcl = solver.solve_masks_cdcl_with_budget(
+            Mask::EMPTY,
+            Mask::EMPTY,
+            0,
+            &mut SolveBudget::default(),
+        );
+        assert_eq!(sorted(cdcl.value), sorted(plain));
+    }
+
+    #[test]
+    fn respects_node_budget() {
+        let solver = Solver::new(6, vec![Color::White; 36], WinCondition::default());
+        let mut budget = SolveBudget {
+            max_nodes: 50,
+            ..SolveBudget::default()
+        };
+        let res = solver.solve_masks_cdcl_with_budget(Mask::EMPTY, Mask::EMPTY, 0, &mut budget);
+        assert_eq!(res.exhausted, Some(BudgetStop::Nodes));
+        assert!(!res.value.is_empty());
+    }
+}
@@ -0,0 +1,598 @@
+//! 无解时的冲突解释：玩家勾选中的极小冲突子集（unsat core）及牵涉的规则。
+//!
+//! 做法：
+//! - 若只靠传播就能发现矛盾，先从矛盾的规则实例出发，沿“每个传播赋值由哪条规则实例推出”
+//!   逐层回溯作用范围（同 CDCL 的成因记录），把候选收缩到真正牵涉其中的勾选；
+//! - 再逐个尝试删去候选中的勾选：删去后仍无解则丢弃，否则保留。结束时每个保留的勾选都不可缺少；
+//! - 最后只用极小子集重新传播一次，回溯出的规则实例即为解释。
+//!
+//! 排除标记与黑格视为固定前提，不参与收缩。
+//!
+//! 题目本身无解时（编辑器里的自定义题目），用同样的逐个删除法在线索（非白格）上求极小冲突集合：
+//! 把某条线索涂白后仍无解则丢弃它。
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use super::{
+    cell_assignment, rule_type_to_rule_id, rule_type_to_title, try_set_checked_id,
+    try_set_unchecked_id, AssignReason, BudgetMeter, Budgeted, HintReasonStep, RuleType,
+    SolveBudget, SolveObserver, Solver, SolverState,
+};
+use crate::colors::Color;
+use crate::i18n::{msg, Locale, Localize, Message, Param};
+use crate::masks::Mask;
+use crate::win::WinCondition;
+
+/// 无解局面的冲突解释。
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ConflictCore {
+    /// 互相冲突的玩家勾选（row-major 递增）：只保留它们（及排除标记）时无解，再去掉任意一个就有解。
+    ///
+    /// 为空表示排除标记本身就已导致无解。
+    pub checks: Vec<usize>,
+    /// 由这些勾选推出矛盾所经过的规则实例（按推导顺序，最后一条为发现矛盾的规则）；
+    /// 只靠传播发现不了矛盾（需要搜索）时为空。
+    pub rules: Vec<ConflictRule>,
+}
+
+/// 冲突链上的一条规则实例。
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ConflictRule {
+    /// 与前端 `rules.json` 的 id 对齐（同 `HintReason::rule_id`）。
+    pub rule_id: &'static str,
+    pub title: String,
+    /// `title` 的消息键与参数（见 `i18n`）。
+    pub title_key: Message,
+    /// 该实例的焦点格（用于高亮）。
+    pub cells: Vec<usize>,
+}
+
+impl Localize for ConflictRule {
+    fn localize(&mut self, locale: Locale) {
+        self.title = self.title_key.render(locale);
+    }
+}
+
+impl Localize for ConflictCore {
+    fn localize(&mut self, locale: Locale) {
+        self.rules.localize(locale);
+    }
+}
+
+/// 互相冲突的一条线索（非白格）。
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ConflictingClue {
+    pub cell: usize,
+    /// 与前端 `rules.json` 的 id 对齐（黑格为 `black`）。
+    pub rule_id: &'static str,
+}
+
+/// 题目本身无解时，求互相冲突的极小线索集合：只保留这些线索（其余涂白）时无解，再把任意一条涂白就有解。
+///
+/// - `value` 为 None 表示题目可解（或在判断出是否可解之前预算就已耗尽）；
+/// - 为空集合表示胜利条件本身在该尺寸下就无法达成；
+/// - 收缩中途预算耗尽时返回当时的候选：仍然无解，但不保证极小。
+pub fn conflicting_clues(
+    size: usize,
+    colors: &[Color],
+    win: WinCondition,
+    budget: &mut SolveBudget,
+) -> Budgeted<Option<Vec<ConflictingClue>>> {
+    let mut meter = BudgetMeter::new(budget);
+    let solvable = |colors: &[Color], meter: &mut BudgetMeter| {
+        let solver = Solver::new(size, colors.to_vec(), win);
+        solver
+            .solve_one_with_masks(Mask::EMPTY, Mask::EMPTY, meter)
+            .is_some()
+    };
+    if solvable(colors, &mut meter) || meter.stopped().is_some() {
+        return meter.finish(None);
+    }
+
+    let mut current = colors.to_vec();
+    for cell in 0..current.len() {
+        if current[cell] == Color::White {
+            continue;
+        }
+        if meter.stopped().is_some() {
+            break;
+        }
+        let clue = std::mem::replace(&mut current[cell], Color::White);
+        if solvable(&current, &mut meter) || meter.stopped().is_some() {
+            current[cell] = clue;
+        }
+    }
+
+    let clues = current
+        .iter()
+        .enumerate()
+        .filter_map(|(cell, color)| {
+            color
+                .rule_id()
+                .map(|rule_id| ConflictingClue { cell, rule_id })
+        })
+        .collect();
+    meter.finish(Some(clues))
+}
+
+/// 记录每个传播赋值由哪条规则实例推出（只记首次赋值）。
+#[derive(Default)]
+struct ConflictTrace {
+    rule: Option<RuleType>,
+    focus: Vec<usize>,
+    /// 产生过赋值的规则实例，按首次赋值的顺序。
+    instances: Vec<(Option<RuleType>, Vec<usize>)>,
+    current: Option<usize>,
+    cause: HashMap<usize, usize>,
+    /// 传播赋值的格子，按赋值顺序。
+    assigned: Vec<usize>,
+}
+
+impl ConflictTrace {
+    /// 从矛盾的规则实例出发，沿作用范围回溯：返回牵涉其中的实例（按下标标记）与途经的已赋值格。
+    ///
+    /// 实例按首次赋值的顺序编号，展开第 k 个实例时只看比它更早确定的格子（由更早的实例推出或原本已知）。
+    fn walk_back(&self, solver: &Solver, state: &SolverState) -> (Vec<bool>, Mask) {
+        let win_scope = solver.win_scope();
+        let mut used = vec![false; self.instances.len()];
+        let mut seen = Mask::EMPTY;
+        let mut pending = vec![(
+            solver.rule_scope(self.rule, &self.focus, win_scope),
+            self.instances.len(),
+        )];
+        while let Some((scope, before)) = pending.pop() {
+            for cell in scope.iter_ones() {
+                let cause = self.cause.get(&cell).copied();
+                if seen.contains(cell)
+                    || state.is_unknown_id(cell)
+                    || cause.is_some_and(|k| k >= before)
+                {
+                    continue;
+                }
+                seen.insert(cell);
+                if let Some(k) = cause {
+                    if !used[k] {
+                        used[k] = true;
+                        let (rule, focus) = &self.instances[k];
+                        pending.push((solver.rule_scope(*rule, focus, win_scope), k));
+                    }
+                }
+            }
+        }
+        (used, seen)
+    }
+}
+
+impl SolveObserver for ConflictTrace {
+    fn on_rule_enter(&mut self, rule: RuleType) {
+        self.rule = Some(rule);
+        self.focus.clear();
+        self.current = None;
+    }
+
+    fn on_rule_focus(&mut self, rule: RuleType, cells: &[usize]) {
+        self.rule = Some(rule);
+        self.focus = cells.to_vec();
+        self.current = None;
+    }
+
+    fn on_cell_assignment(&mut self, cell: usize, _is_checked: bool, reason: AssignReason) {
+        if !matches!(reason, AssignReason::Propagate) {
+            return;
+        }
+        let k = *self.current.get_or_insert_with(|| {
+            self.instances.push((self.rule, self.focus.clone()));
+            self.instances.len() - 1
+        });
+        if let std::collections::hash_map::Entry::Vacant(e) = self.cause.entry(cell) {
+            e.insert(k);
+            self.assigned.push(cell);
+        }
+    }
+}
+
+impl Solver {
+    /// 计算冲突解释（见模块文档）。
+    ///
+    /// - mask 约定同 [`Solver::solve_masks_cdcl_with_budget`]；
+    /// - `value` 为 None 表示当前局面可解（或在判断出是否可解之前预算就已耗尽）；
+    /// - 收缩中途预算耗尽时返回当时的候选：仍是无解的勾选集合，但不保证极小。
+    pub fn conflict_core(
+        &self,
+        checked_mask: Mask,
+        unchecked_mask: Mask,
+        budget: &mut SolveBudget,
+    ) -> Budgeted<Option<ConflictCore>> {
+        let (checked_mask, unchecked_mask) = self.normalize_masks(checked_mask, unchecked_mask);
+        let mut meter = BudgetMeter::new(budget);
+        let solvable = self
+            .solve_one_with_masks(checked_mask, unchecked_mask, &mut meter)
+            .is_some();
+        if solvable || meter.stopped().is_some() {
+            return meter.finish(None);
+        }
+        let core = self.conflict_core_from(checked_mask, unchecked_mask, &mut meter);
+        meter.finish(Some(core))
+    }
+
+    /// 在已知无解的前提下计算冲突解释；两个 mask 需已规范化。
+    pub(super) fn conflict_core_from(
+        &self,
+        checked_mask: Mask,
+        unchecked_mask: Mask,
+        meter: &mut BudgetMeter,
+    ) -> ConflictCore {
+        let mut core: Vec<usize> = self
+            .rules
+            .decision_order
+            .iter()
+            .copied()
+            .filter(|&id| checked_mask.contains(id))
+            .collect();
+        let to_mask = |cells: &[usize]| cells.iter().fold(Mask::EMPTY, |m, &id| m | Mask::bit(id));
+
+        // 1) 传播即可发现矛盾时，先收缩到回溯能够到达的勾选（通常已接近极小）。
+        if let Some((roots, _)) = self.trace_conflict(to_mask(&core), unchecked_mask) {
+            if roots != to_mask(&core)
+                && self
+                    .solve_one_with_masks(roots, unchecked_mask, meter)
+                    .is_none()
+                && meter.stopped().is_none()
+            {
+                core.retain(|&id| roots.contains(id));
+            }
+        }
+
+        // 2) 逐个删除：删去后仍无解的勾选不属于极小子集。
+        for cell in core.clone() {
+            if meter.stopped().is_some() {
+                break;
+            }
+            let trial: Vec<usize> = core.iter().copied().filter(|&id| id != cell).collect();
+            let solvable = self
+                .solve_one_with_masks(to_mask(&trial), unchecked_mask, meter)
+                .is_some();
+            if !solvable && meter.stopped().is_none() {
+                core = trial;
+            }
+        }
+
+        let checks = to_mask(&core);
+        let rules = self
+            .trace_conflict(checks, unchecked_mask)
+            .map_or_else(Vec::new, |(_, rules)| rules);
+        ConflictCore {
+            checks: checks.iter_ones().collect(),
+            rules,
+        }
+    }
+
+    /// 只用传播检查 `checks`（及排除标记）是否矛盾；矛盾时返回回溯到的勾选与途经的规则实例。
+    fn trace_conflict(
+        &self,
+        checks: Mask,
+        unchecked_mask: Mask,
+    ) -> Option<(Mask, Vec<ConflictRule>)> {
+        let mut trace = ConflictTrace::default();
+        let Some(mut state) = self.initial_state_with_masks(checks, unchecked_mask, &mut trace)
+        else {
+            // 排除标记与黑格直接冲突，与勾选无关。
+            return Some((Mask::EMPTY, Vec::new()));
+        };
+        if self.propagate_to_fixpoint(&mut state, &mut trace) {
+            return None;
+        }
+
+        let (used, seen) = trace.walk_back(self, &state);
+        let roots = seen
+            .iter_ones()
+            .filter(|id| checks.contains(*id) && !trace.cause.contains_key(id))
+            .fold(Mask::EMPTY, |m, id| m | Mask::bit(id));
+
+        let conflict = (trace.rule, trace.focus.clone());
+        let mut rules: Vec<ConflictRule> = Vec::new();
+        let chain = trace
+            .instances
+            .iter()
+            .zip(&used)
+            .filter(|(_, &u)| u)
+            .map(|(instance, _)| instance)
+            .chain(std::iter::once(&conflict));
+        for (rule, focus) in chain {
+            let Some(rule) = *rule else {
+                continue;
+            };
+            let title = rule_type_to_title(rule);
+            let next = ConflictRule {
+                rule_id: rule_type_to_rule_id(rule),
+                title: title.zh(),
+                title_key: title,
+                cells: focus.clone(),
+            };
+            if rules.last() != Some(&next) {
+                rules.push(next);
+            }
+        }
+        Some((roots, rules))
+    }
+}
+
+/// 反证的具体过程（见 [`Solver::refutation`]）。
+pub(super) struct Refutation {
+    /// 假设 → 逐条规则推出的赋值 → 无法满足的规则。
+    pub steps: Vec<HintReasonStep>,
+    /// 推导中涉及的全部格子（用于次级高亮）。
+    pub cells: Vec<usize>,
+    /// 最终无法满足的规则。
+    pub rule_id: Option<&'static str>,
+}
+
+impl Solver {
+    /// 在 `state` 上假设 `cell` 取 `assume_checked` 并传播；传播矛盾时回溯出导致矛盾的那部分推导。
+    ///
+    /// 只保留矛盾真正依赖的规则实例与赋值，按推出顺序排列，玩家可以在盘面上逐步重放。
+    /// 这次传播在 `meter` 上记一个节点，预算已耗尽时返回 None。
+    pub(super) fn refutation(
+        &self,
+        state: &SolverState,
+        cell: usize,
+        assume_checked: bool,
+        meter: &mut BudgetMeter,
+    ) -> Option<Refutation> {
+        let size = self.rules.size;
+        if meter.tick() {
+            return None;
+        }
+
+        let mut trace = ConflictTrace::default();
+        let mut fork = state.clone();
+        let ok = if assume_checked {
+            try_set_checked_id(&mut fork, cell, AssignReason::Guess, &mut trace)
+        } else {
+            try_set_unchecked_id(&mut fork, cell, AssignReason::Guess, &mut trace)
+        };
+        if ok && self.propagate_to_fixpoint(&mut fork, &mut trace) {
+            return None;
+        }
+
+        let mut steps = vec![HintReasonStep::new(
+            msg!(
+                "refute.assume",
+                assignment = cell_assignment(size, cell, assume_checked)
+            ),
+            None,
+            vec![cell],
+        )];
+        let mut cells = vec![cell];
+        if !ok {
+            return Some(Refutation {
+                steps,
+                cells,
+                rule_id: None,
+            });
+        }
+
+        let (used, seen) = trace.walk_back(self, &fork);
+        for (k, (rule, focus)) in trace.instances.iter().enumerate() {
+            if !used[k] {
+                continue;
+            }
+            let forced: Vec<usize> = trace
+                .assigned
+                .iter()
+                .copied()
+                .filter(|c| trace.cause[c] == k && seen.contains(*c))
+                .collect();
+            let conclusions = forced
+                .iter()
+                .map(|&c| cell_assignment(size, c, fork.is_checked_id(c)))
+                .collect();
+            let mut step_cells = forced.clone();
+            step_cells.extend(focus.iter().filter(|c| !forced.contains(c)));
+            for &c in &step_cells {
+                if !cells.contains(&c) {
+                    cells.push(c);
+                }
+            }
+            steps.push(HintReasonStep::new(
+                msg!(
+                    "hint.derive",
+                    rule = rule.map_or_else(|| msg!("rule.generic"), rule_type_to_title),
+                    conclusions = Param::list("sep.comma", conclusions)
+                ),
+                rule.map(rule_type_to_rule_id),
+                step_cells,
+            ));
+        }
+
+        let rule_id = trace.rule.map(rule_type_to_rule_id);
+        let title = match trace.rule {
+            Some(RuleType::FiveInRow) => {
+                msg!("refute.no_line", line = self.rules.win.title(size))
+            }
+            Some(rule) => msg!("refute.violated", rule = rule_type_to_title(rule)),
+            None => msg!("refute.stuck"),
+        };
+        for &c in &trace.focus {
+            if !cells.contains(&c) {
+                cells.push(c);
+            }
+        }
+        steps.push(HintReasonStep::new(title, rule_id, trace.focus.clone()));
+        Some(Refutation {
+            steps,
+            cells,
+            rule_id,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::colors::Color;
+    use crate::solver::testing::{random_board, random_mask, test_rng, BASE_CLUES};
+    use crate::win::WinCondition;
+
+    /// 暴力验证极小性：子集无解，去掉任意一个后有解。
+    fn assert_minimal(solver: &Solver, core: &ConflictCore, unchecked: Mask) {
+        let checks = core
+            .checks
+            .iter()
+            .fold(Mask::EMPTY, |m, &id| m | Mask::bit(id));
+        assert!(solver
+            .solve_masks_limit_with_checked_mask(checks, unchecked, 1)
+            .is_empty());
+        for &id in &core.checks {
+            let mut relaxed = checks;
+            relaxed.remove(id);
+            assert!(
+                !solver
+                    .solve_masks_limit_with_checked_mask(relaxed, unchecked, 1)
+                    .is_empty(),
+                "check {id} is not needed"
+            );
+        }
+    }
+
+    #[test]
+    fn finds_three_checks_that_conflict_via_blue() {
+        // 中心蓝格最多两邻勾选：勾了三个邻居即矛盾；另外两处无关的勾选不应出现在子集里。
+        let mut colors = vec![Color::White; 25];
+        colors[12] = Color::Blue;
+        let solver = Solver::new(5, colors, WinCondition::default());
+        let checked = Mask::bit(6) | Mask::bit(7) | Mask::bit(8) | Mask::bit(0) | Mask::bit(24);
+        let got = solver.conflict_core(checked, Mask::EMPTY, &mut SolveBudget::default());
+        let core = got.value.expect("unsolvable");
+        assert_eq!(core.checks, vec![6, 7, 8]);
+        assert_eq!(core.rules.last().map(|r| r.rule_id), Some("blue"));
+        assert_minimal(&solver, &core, Mask::EMPTY);
+    }
+
+    #[test]
+    fn clue_core_is_minimal() {
+        // 两个相邻的蓝格被一圈黑格包围：黑格勾选数超过蓝格上限；无关的红格不应出现在结果里。
+        let mut colors = vec![Color::White; 25];
+        colors[12] = Color::Blue;
+        for id in [6, 7, 8] {
+            colors[id] = Color::Black;
+        }
+        colors[24] = Color::Red;
+        colors[0] = Color::Green;
+        let got = conflicting_clues(
+            5,
+            &colors,
+            WinCondition::default(),
+            &mut SolveBudget::default(),
+        );
+        assert!(got.is_finished());
+        let clues = got.value.expect("unsolvable");
+        assert_eq!(
+            clues,
+            [6, 7, 8, 12]
+                .map(|cell| ConflictingClue {
+                    cell,
+                    rule_id: colors[cell].rule_id().unwrap(),
+                })
+                .to_vec()
+        );
+        // 把任意一条涂白都可解。
+        for clue in &clues {
+            let mut relaxed: Vec<Color> = vec![Color::White; 25];
+            for other in clues.iter().filter(|c| c.cell != clue.cell) {
+                relaxed[other.cell] = colors[other.cell];
+            }
+            let solver = Solver::new(5, relaxed, WinCondition::default());
+            assert!(!solver
+                .solve_masks_limit_with_checked_mask(Mask::EMPTY, Mask::EMPTY, 1)
+                .is_empty());
+        }
+    }
+
+    #[test]
+    fn solvable_puzzle_has_no_clue_core() {
+        let grid = crate::generate::generate_puzzle(5, 5, WinCondition::default()).unwrap();
+        let colors: Vec<Color> = grid
+            .into_iter()
+            .flatten()
+            .map(|v| Color::from_u8(v).unwrap())
+            .collect();
+        let got = conflicting_clues(
+            5,
+            &colors,
+            WinCondition::default(),
+            &mut SolveBudget::default(),
+        );
+        assert_eq!(got.value, None);
+    }
+
+    #[test]
+    fn refutation_keeps_only_the_relevant_chain() {
+        // 假设 (5,4) 不勾选：蓝格推出 (4,2)(4,3)(4,4) 不勾选，红格 (5,3) 的八邻便全不勾选。
+        let mut colors = vec![Color::White; 25];
+        colors[12] = Color::Blue;
+        colors[6] = Color::Black;
+        colors[7] = Color::Black;
+        colors[22] = Color::Red;
+        let solver = Solver::new(5, colors, WinCondition::default());
+        let state = solver
+            .initial_state_with_masks(Mask::EMPTY, Mask::bit(21), &mut ())
+            .unwrap();
+        let mut budget = SolveBudget::default();
+        let mut meter = BudgetMeter::new(&mut budget);
+        let r = solver
+            .refutation(&state, 23, false, &mut meter)
+            .expect("contradiction");
+        assert_eq!(r.steps[0].cells, vec![23]);
+        let blue = r
+            .steps
+            .iter()
+            .find(|s| s.rule_id == Some("blue"))
+            .expect("blue step");
+        assert!([16, 17, 18].iter().all(|c| blue.cells.contains(c)));
+        assert!(blue.title.contains("(4,2) 不勾选"));
+        // 与矛盾无关的蓝格结论（如 (2,4)）不出现在推导里。
+        assert!(!blue.title.contains("(2,4)"));
+        let last = r.steps.last().unwrap();
+        assert_eq!(last.rule_id, Some("red"));
+        assert_eq!(last.title_key.key, "refute.violated");
+        assert_eq!(r.rule_id, Some("red"));
+
+        assert!(solver.refutation(&state, 23, true, &mut meter).is_none());
+    }
+
+    #[test]
+    fn solvable_state_has_no_core() {
+        let solver = Solver::new(5, vec![Color::White; 25], WinCondition::default());
+        let got = solver.conflict_core(Mask::bit(3), Mask::EMPTY, &mut SolveBudget::default());
+        assert!(got.is_finished());
+        assert_eq!(got.value, None);
+    }
+
+    #[test]
+    fn cores_are_minimal_on_random_dead_states() {
+        let clues: Vec<Color> = BASE_CLUES.into_iter().filter(|&c| c != Color::Black).collect();
+        let mut rng = test_rng(11);
+        let mut found = 0;
+        for _ in 0..60 {
+            let colors = random_board(&mut rng, 5, &clues, 5.0 / 12.0);
+            let checked = random_mask(&mut rng, 25, 0.25, Mask::EMPTY);
+            let unchecked = random_mask(&mut rng, 25, 0.1, checked);
+            let solver = Solver::new(5, colors, WinCondition::default());
+            let got = solver.conflict_core(checked, unchecked, &mut SolveBudget::default());
+            assert!(got.is_finished());
+            let Some(core) = got.value else {
+                continue;
+            };
+            found += 1;
+            assert!(core.checks.iter().all(|&id| checked.contains(id)));
+            assert_minimal(&solver, &core, unchecked);
+        }
+        assert!(found > 5, "too few dead states: {found}");
+    }
+}
@@ -0,0 +1,295 @@
+//! 绿/黄格的计数相等链。
+//!
+//! 绿格要求“所在行计数 = 所在列计数”，黄格要求“两条对角线计数相等”。把行、列、对角线的
+//! 勾选数看作变量，每个绿/黄格就是一条等式；共享行列的多个绿格会把等式串成链
+//! （如 第 1 行 = 第 3 列 = 第 4 行）。这里用并查集把变量合并成等价类，再在整个类上做区间传播：
+//! 类的可行区间为各成员 `[min, max]` 的交集，成员的上界卡在区间下界时其未知格全部勾选，
+//! 下界卡在区间上界时全部不勾选。
+//!
+//! 只含两个变量的类与单个绿/黄格的传播（见 `rules` 模块）等价，因此只保留三个及以上变量的类。
+
+use super::{
+    cell_id, fill_cells_unknowns_as, fill_col_unknowns_as, fill_row_unknowns_as, RuleSet, RuleType,
+    SolveObserver, Solver, SolverState,
+};
+use crate::i18n::{msg, Message};
+
+/// 一个计数变量：某一行/列/对角线上的勾选数。
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum CountVar {
+    Row(usize),
+    Col(usize),
+    /// `\` 方向对角线（编号同 `RuleSet::diag_down_cells`）。
+    DiagDown(usize),
+    /// `/` 方向对角线（编号同 `RuleSet::diag_up_cells`）。
+    DiagUp(usize),
+}
+
+impl CountVar {
+    fn min_max(self, state: &SolverState) -> (u8, u8) {
+        match self {
+            Self::Row(r) => state.row_min_max(r),
+            Self::Col(c) => state.col_min_max(c),
+            Self::DiagDown(d) => state.diag_down_min_max(d),
+            Self::DiagUp(d) => state.diag_up_min_max(d),
+        }
+    }
+
+    fn fill(
+        self,
+        rules: &RuleSet,
+        state: &mut SolverState,
+        is_checked: bool,
+        obs: &mut impl SolveObserver,
+    ) -> bool {
+        match self {
+            Self::Row(r) => fill_row_unknowns_as(state, r, is_checked, obs),
+            Self::Col(c) => fill_col_unknowns_as(state, c, is_checked, obs),
+            Self::DiagDown(d) => {
+                fill_cells_unknowns_as(state, &rules.diag_down_cells[d], is_checked, obs)
+            }
+            Self::DiagUp(d) => {
+                fill_cells_unknowns_as(state, &rules.diag_up_cells[d], is_checked, obs)
+            }
+        }
+    }
+
+    /// 该变量覆盖的格子。
+    pub(crate) fn cells(self, rules: &RuleSet) -> Vec<usize> {
+        match self {
+            Self::Row(r) => (0..rules.size).map(|c| cell_id(r, c, rules.size)).collect(),
+            Self::Col(c) => (0..rules.size).map(|r| cell_id(r, c, rules.size)).collect(),
+            Self::DiagDown(d) => rules.diag_down_cells[d].clone(),
+            Self::DiagUp(d) => rules.diag_up_cells[d].clone(),
+        }
+    }
+
+    /// 用于提示文案的名称，如“第 2 行”。
+    pub(crate) fn title(self, size: usize) -> Message {
+        match self {
+            Self::Row(r) => msg!("count.row", row = r + 1),
+            Self::Col(c) => msg!("count.col", col = c + 1),
+            // 用对角线在首行（或首/末列）上的起点标识。
+            Self::DiagDown(d) => {
+                let (r, c) = if d < size {
+                    (0, size - 1 - d)
+                } else {
+                    (d - (size - 1), 0)
+                };
+                msg!("count.diag_down", row = r + 1, col = c + 1)
+            }
+            Self::DiagUp(d) => {
+                let (r, c) = if d < size {
+                    (0, d)
+                } else {
+                    (d - (size - 1), size - 1)
+                };
+                msg!("count.diag_up", row = r + 1, col = c + 1)
+            }
+        }
+    }
+}
+
+/// 一个计数等价类：`vars` 的勾选数必须全部相等；`links` 为把它们连起来的绿/黄格（row-major 递增）。
+#[derive(Clone, Debug)]
+pub(crate) struct CountClass {
+    pub vars: Vec<CountVar>,
+    pub links: Vec<usize>,
+}
+
+/// 按绿/黄格合并计数变量，返回包含三个及以上变量的等价类。
+pub(super) fn build_count_classes(
+    size: usize,
+    green_cells: &[usize],
+    yellow_cells: &[usize],
+) -> Vec<CountClass> {
+    // 变量编号：行 [0,n)、列 [n,2n)、`\` 对角线 [2n,2n+D)、`/` 对角线 [2n+D,2n+2D)。
+    let diag_count = size * 2 - 1;
+    let var_of = |k: usize| {
+        if k < size {
+            CountVar::Row(k)
+        } else if k < 2 * size {
+            CountVar::Col(k - size)
+        } else if k < 2 * size + diag_count {
+            CountVar::DiagDown(k - 2 * size)
+        } else {
+            CountVar::DiagUp(k - 2 * size - diag_count)
+        }
+    };
+
+    let mut parent: Vec<usize> = (0..2 * size + 2 * diag_count).collect();
+    fn find(parent: &mut [usize], mut x: usize) -> usize {
+        while parent[x] != x {
+            parent[x] = parent[parent[x]];
+            x = parent[x];
+        }
+        x
+    }
+
+    let mut links: Vec<(usize, usize, usize)> = Vec::new();
+    for &id in green_cells {
+        let (row, col) = (id / size, id % size);
+        links.push((id, row, size + col));
+    }
+    for &id in yellow_cells {
+        let (row, col) = (id / size, id % size);
+        let down = row + (size - 1) - col;
+        let up = row + col;
+        links.push((id, 2 * size + down, 2 * size + diag_count + up));
+    }
+    for &(_, a, b) in &links {
+        let (ra, rb) = (find(&mut parent, a), find(&mut parent, b));
+        if ra != rb {
+            parent[ra] = rb;
+        }
+    }
+
+    let mut classes: Vec<(usize, CountClass)> = Vec::new();
+    for k in 0..parent.len() {
+        let root = find(&mut parent, k);
+        match classes.iter_mut().find(|(r, _)| *r == root) {
+            Some((_, class)) => class.vars.push(var_of(k)),
+            None => classes.push((
+                root,
+                CountClass {
+                    vars: vec![var_of(k)],
+                    links: Vec::new(),
+                },
+            )),
+        }
+    }
+    for &(id, a, _) in &links {
+        let root = find(&mut parent, a);
+        if let Some((_, class)) = classes.iter_mut().find(|(r, _)| *r == root) {
+            class.links.push(id);
+        }
+    }
+
+    classes
+        .into_iter()
+        .map(|(_, mut class)| {
+            class.links.sort_unstable();
+            class
+        })
+        .filter(|class| class.vars.len() >= 3)
+        .collect()
+}
+
+impl Solver {
+    /// 在每个计数等价类上做区间传播（见模块文档）。
+    pub(super) fn propagate_count_chains(
+        &self,
+        state: &mut SolverState,
+        obs: &mut impl SolveObserver,
+    ) -> bool {
+        for class in &self.rules.count_classes {
+            // 教学：计数链的推导范围 = 把各条线连起来的绿/黄格
+            obs.on_rule_focus(RuleType::CountChain, &class.links);
+
+            let bounds: Vec<(u8, u8)> = class.vars.iter().map(|v| v.min_max(state)).collect();
+            let lo = bounds.iter().map(|b| b.0).max().unwrap_or(0);
+            let hi = bounds.iter().map(|b| b.1).min().unwrap_or(0);
+            if lo > hi {
+                return false;
+            }
+
+            for (&var, &(min, max)) in class.vars.iter().zip(&bounds) {
+                if max == lo && min < max && !var.fill(&self.rules, state, true, obs) {
+                    return false;
+                }
+                if min == hi && min < max && !var.fill(&self.rules, state, false, obs) {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::colors::Color;
+    use crate::masks::Mask;
+    use crate::solver::testing::{random_board, random_mask, test_rng};
+    use crate::solver::{HintAction, SolveBudget};
+    use crate::win::WinCondition;
+
+    /// 4x4：(0,2)、(3,2) 为绿格，串成 第 1 行 = 第 3 列 = 第 4 行。
+    fn chained_greens() -> Solver {
+        let mut colors = vec![Color::White; 16];
+        colors[2] = Color::Green;
+        colors[14] = Color::Green;
+        Solver::new(
+            4,
+            colors,
+            WinCondition::InARow {
+                length: 4,
+                diagonals: false,
+            },
+        )
+    }
+
+    #[test]
+    fn merges_lines_sharing_a_green_column() {
+        let solver = chained_greens();
+        let classes = &solver.rules.count_classes;
+        assert_eq!(classes.len(), 1);
+        assert_eq!(
+            classes[0].vars,
+            vec![CountVar::Row(0), CountVar::Row(3), CountVar::Col(2)]
+        );
+        assert_eq!(classes[0].links, vec![2, 14]);
+    }
+
+    #[test]
+    fn chain_forces_what_single_cells_cannot() {
+        // 第 1 行已有 2 个勾选（>=2），第 4 行已排除 2 格（<=2）：整条链只能等于 2。
+        let solver = chained_greens();
+        let mut state = SolverState::new(4);
+        for cell in [0, 1] {
+            state.set_checked_id(cell).unwrap();
+        }
+        for cell in [12, 13] {
+            state.set_unchecked_id(cell).unwrap();
+        }
+
+        let mut single = state.clone();
+        assert!(solver.propagate_stage(RuleType::Cell(Color::Green), &mut single, &mut ()));
+        assert!(single.is_unknown_id(14));
+
+        assert!(solver.propagate_count_chains(&mut state, &mut ()));
+        assert!(state.is_checked_id(14) && state.is_checked_id(15));
+        assert!(state.is_unchecked_id(2) && state.is_unchecked_id(3));
+
+        let checked = Mask::bit(0) | Mask::bit(1);
+        let unchecked = Mask::bit(12) | Mask::bit(13);
+        let hint = solver.hint_next(checked, unchecked);
+        let mv = hint.mv.expect("forced move");
+        assert!(mv.forced && matches!(mv.action, HintAction::Check));
+        let reason = hint.reason.expect("reason");
+        assert_eq!(reason.rule_id, Some("green"));
+        assert!(reason
+            .steps
+            .iter()
+            .any(|s| s.title_key.key == "hint.count_chain.chain"));
+    }
+
+    #[test]
+    fn chains_keep_solution_counts() {
+        // 随机绿/黄/白布局 + 随机排除：带链式传播的枚举必须与（不依赖传播的）精确计数一致。
+        let mut rng = test_rng(3);
+        for _ in 0..30 {
+            let colors = random_board(&mut rng, 5, &[Color::Green, Color::Yellow], 1.0 / 3.0);
+            let unchecked = random_mask(&mut rng, 25, 0.2, Mask::EMPTY);
+            let solver = Solver::new(5, colors, WinCondition::default());
+            let enumerated = solver
+                .solve_masks_limit_with_checked_mask(Mask::EMPTY, unchecked, 0)
+                .len() as u128;
+            let exact = solver
+                .count_solutions_exact(Mask::EMPTY, unchecked, 0, &mut SolveBudget::default())
+                .value;
+            assert_eq!(Some(enumerated), exact);
+        }
+    }
+}
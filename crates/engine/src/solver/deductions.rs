@@ -0,0 +1,348 @@
+//! 批量推导：一次性给出当前局面下所有“已经能确定”的格子（用于辅助模式自动标记、“揭示逻辑下一步”）。
+//!
+//! 按推理深度分三档，每档包含前一档的结论：
+//! - `Propagate`：只做约束传播；
+//! - `Probe`：再对每个未知格做单步反证（假设一个取值，传播出矛盾则取另一值），反复直到不动点；
+//! - `Backbone`：再用定向求解补齐骨干（所有解中取值都相同的格子，见 `backbone` 模块）。
+//!
+//! 每个格子记录首次被确定时的深度与一句简短理由。
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    rule_type_to_rule_id, rule_type_to_title, try_set_checked_id, try_set_unchecked_id,
+    AssignReason, BudgetMeter, Budgeted, HintExplainObserver, SolveBudget, Solver, SolverState,
+    TracedState,
+};
+use crate::i18n::{msg, Locale, Localize, Message};
+use crate::masks::Mask;
+
+/// 推理深度（由浅到深）。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeductionDepth {
+    /// 约束传播。
+    Propagate,
+    /// 传播 + 单步反证（failed-literal probing）到不动点。
+    Probe,
+    /// 完整骨干（需要搜索）。
+    Backbone,
+}
+
+/// 一个已能确定的格子。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeducedCell {
+    pub cell: usize,
+    pub checked: bool,
+    /// 首次确定该格所需的深度。
+    pub depth: DeductionDepth,
+    /// 相关规则（与前端 `rules.json` 的 id 对齐）；反证与骨干结论为 None。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rule_id: Option<&'static str>,
+    pub reason: String,
+    /// `reason` 的消息键与参数（见 `i18n`）。
+    pub reason_key: Message,
+}
+
+impl DeducedCell {
+    fn new(
+        cell: usize,
+        checked: bool,
+        depth: DeductionDepth,
+        rule_id: Option<&'static str>,
+        reason: Message,
+    ) -> Self {
+        Self {
+            cell,
+            checked,
+            depth,
+            rule_id,
+            reason: reason.zh(),
+            reason_key: reason,
+        }
+    }
+}
+
+impl Localize for DeducedCell {
+    fn localize(&mut self, locale: Locale) {
+        self.reason = self.reason_key.render(locale);
+    }
+}
+
+impl Solver {
+    /// 在 `depth` 档推理深度下，列出当前局面所有已能确定、但玩家尚未标记的格子。
+    ///
+    /// - mask 约定同 [`Solver::solve_masks_cdcl_with_budget`]；
+    /// - 结果按深度分组，组内按推出的先后排列；
+    /// - `value` 为 None 表示已证明无解（`Propagate` 档只发现传播矛盾）；
+    /// - 预算耗尽时返回已确定的部分，并在 `exhausted` 中标明停止原因。
+    pub fn deductions(
+        &self,
+        checked_mask: Mask,
+        unchecked_mask: Mask,
+        depth: DeductionDepth,
+        budget: &mut SolveBudget,
+    ) -> Budgeted<Option<Vec<DeducedCell>>> {
+        let (checked_mask, unchecked_mask) = self.normalize_masks(checked_mask, unchecked_mask);
+        let mut meter = BudgetMeter::new(budget);
+        let mut obs = HintExplainObserver::default();
+        let Some(mut state) = self.initial_state_with_masks(checked_mask, unchecked_mask, &mut obs)
+        else {
+            return meter.finish(None);
+        };
+        if !self.propagate_to_fixpoint(&mut state, &mut obs) {
+            return meter.finish(None);
+        }
+        let givens = self
+            .rules
+            .given_cells
+            .iter()
+            .fold(checked_mask | unchecked_mask, |m, &id| m | Mask::bit(id));
+        let traced = TracedState {
+            state,
+            obs,
+            explain: None,
+        };
+        let value = self.deductions_from(givens, &traced, depth, &mut None, &mut meter);
+        meter.finish(value)
+    }
+
+    /// 基于会话状态的批量推导；`givens` 为玩家已确认的格子（含黑格），
+    /// `solution` 为快照上的解缓存（约定同 `traced_hint`）。
+    pub(crate) fn traced_deductions(
+        &self,
+        givens: Mask,
+        traced: Option<&TracedState>,
+        solution: &mut Option<Option<Mask>>,
+        depth: DeductionDepth,
+        budget: &mut SolveBudget,
+    ) -> Budgeted<Option<Vec<DeducedCell>>> {
+        let mut meter = BudgetMeter::new(budget);
+        let value = match (traced, *solution) {
+            (None, _) | (_, Some(None)) => None,
+            (Some(t), _) => self.deductions_from(givens, t, depth, solution, &mut meter),
+        };
+        meter.finish(value)
+    }
+
+    fn deductions_from(
+        &self,
+        givens: Mask,
+        traced: &TracedState,
+        depth: DeductionDepth,
+        reference: &mut Option<Option<Mask>>,
+        meter: &mut BudgetMeter,
+    ) -> Option<Vec<DeducedCell>> {
+        let size = self.rules.size;
+        let cell_count = size * size;
+        let coord = |id: usize| msg!("cell.coord", row = id / size + 1, col = id % size + 1);
+        let mut known = givens;
+        let mut out = Vec::new();
+
+        // 1) 传播：按首次赋值的先后排列，理由取触发传播的规则。
+        let mut state = traced.state.clone();
+        let mut propagated: Vec<usize> = newly_known(&state, known, cell_count).collect();
+        propagated.sort_by_key(|id| traced.obs.assigned_at.get(id).copied());
+        for cell in propagated {
+            let rule = traced
+                .obs
+                .propagate_info(cell)
+                .and_then(|(rule, _, _)| rule);
+            out.push(DeducedCell::new(
+                cell,
+                state.is_checked_id(cell),
+                DeductionDepth::Propagate,
+                rule.map(rule_type_to_rule_id),
+                match rule {
+                    Some(rule) => msg!("deduce.rule", rule = rule_type_to_title(rule)),
+                    None => msg!("deduce.propagate"),
+                },
+            ));
+            known.insert(cell);
+        }
+        if depth == DeductionDepth::Propagate {
+            return Some(out);
+        }
+
+        // 2) 单步反证：每个未知格两个取值各传播一次，只剩一个可行取值时采纳它的传播结果，直到不动点。
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for &cell in &self.rules.decision_order {
+                if !state.is_unknown_id(cell) {
+                    continue;
+                }
+                if meter.tick() {
+                    return Some(out);
+                }
+                let [if_checked, if_unchecked] = [true, false].map(|checked| {
+                    let mut fork = state.clone();
+                    let ok = if checked {
+                        try_set_checked_id(&mut fork, cell, AssignReason::Guess, &mut ())
+                    } else {
+                        try_set_unchecked_id(&mut fork, cell, AssignReason::Guess, &mut ())
+                    };
+                    (ok && self.propagate_to_fixpoint(&mut fork, &mut ())).then_some(fork)
+                });
+                let (checked, next) = match (if_checked, if_unchecked) {
+                    (None, None) => return None,
+                    (Some(_), Some(_)) => continue,
+                    (Some(fork), None) => (true, fork),
+                    (None, Some(fork)) => (false, fork),
+                };
+                state = next;
+                changed = true;
+                let reason = if checked {
+                    msg!("deduce.probe.uncheck", cell = coord(cell))
+                } else {
+                    msg!("deduce.probe.check", cell = coord(cell))
+                };
+                out.push(DeducedCell::new(
+                    cell,
+                    checked,
+                    DeductionDepth::Probe,
+                    None,
+                    reason,
+                ));
+                known.insert(cell);
+                let follow: Vec<usize> = newly_known(&state, known, cell_count).collect();
+                for id in follow {
+                    out.push(DeducedCell::new(
+                        id,
+                        state.is_checked_id(id),
+                        DeductionDepth::Probe,
+                        None,
+                        msg!("deduce.probe.follow", cell = coord(cell)),
+                    ));
+                    known.insert(id);
+                }
+            }
+        }
+        if depth == DeductionDepth::Probe {
+            return Some(out);
+        }
+
+        // 3) 骨干：单步反证仍不能确定、但所有解中取值都相同的格子。
+        let backbone = self.backbone_from(state, reference, meter)?;
+        for cell in (backbone.checked | backbone.unchecked).iter_ones() {
+            if known.contains(cell) {
+                continue;
+            }
+            out.push(DeducedCell::new(
+                cell,
+                backbone.checked.contains(cell),
+                DeductionDepth::Backbone,
+                None,
+                msg!("deduce.backbone"),
+            ));
+        }
+        Some(out)
+    }
+}
+
+/// `state` 中已赋值、但不在 `known` 里的格子（row-major）。
+fn newly_known(
+    state: &SolverState,
+    known: Mask,
+    cell_count: usize,
+) -> impl Iterator<Item = usize> + '_ {
+    (0..cell_count).filter(move |&id| !known.contains(id) && !state.is_unknown_id(id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::colors::Color;
+    use crate::win::WinCondition;
+
+    fn run(
+        solver: &Solver,
+        checked: Mask,
+        unchecked: Mask,
+        depth: DeductionDepth,
+    ) -> Vec<DeducedCell> {
+        let res = solver.deductions(checked, unchecked, depth, &mut SolveBudget::default());
+        assert!(res.is_finished());
+        res.value.expect("solvable")
+    }
+
+    #[test]
+    fn propagation_lists_rule_consequences() {
+        let mut colors = vec![Color::White; 25];
+        colors[12] = Color::Blue;
+        colors[6] = Color::Black;
+        colors[7] = Color::Black;
+        let solver = Solver::new(5, colors, WinCondition::default());
+        let cells = run(&solver, Mask::EMPTY, Mask::EMPTY, DeductionDepth::Propagate);
+        for id in [8, 11, 13, 16, 17, 18] {
+            let d = cells
+                .iter()
+                .find(|d| d.cell == id)
+                .expect("blue consequence");
+            assert!(!d.checked);
+            assert_eq!(d.rule_id, Some("blue"));
+            assert!(d.reason.contains("蓝格"));
+        }
+        // 黑格属于前提，不重复列出。
+        assert!(cells.iter().all(|d| d.cell != 6 && d.cell != 7));
+    }
+
+    #[test]
+    fn deeper_levels_extend_shallower_ones() {
+        let grid = crate::generate::generate_puzzle(7, 5, WinCondition::default()).unwrap();
+        let colors: Vec<Color> = grid
+            .into_iter()
+            .flatten()
+            .map(|v| Color::from_u8(v).unwrap())
+            .collect();
+        let solver = Solver::new(5, colors, WinCondition::default());
+        let solution = solver.solve_masks_limit_with_checked_mask(Mask::EMPTY, Mask::EMPTY, 1)[0];
+
+        let levels = [
+            DeductionDepth::Propagate,
+            DeductionDepth::Probe,
+            DeductionDepth::Backbone,
+        ]
+        .map(|depth| run(&solver, Mask::EMPTY, Mask::EMPTY, depth));
+        for pair in levels.windows(2) {
+            // 这道题每加深一档都能多确定几格。
+            assert!(pair[0].len() < pair[1].len());
+            assert_eq!(pair[0][..], pair[1][..pair[0].len()]);
+        }
+        for d in &levels[2] {
+            assert_eq!(d.checked, solution.contains(d.cell), "cell {}", d.cell);
+        }
+
+        let backbone = solver
+            .backbone(Mask::EMPTY, Mask::EMPTY, &mut SolveBudget::default())
+            .value
+            .unwrap();
+        let deduced = levels[2]
+            .iter()
+            .fold(Mask::EMPTY, |m, d| m | Mask::bit(d.cell));
+        let black = solver
+            .rules
+            .given_cells
+            .iter()
+            .fold(Mask::EMPTY, |m, &id| m | Mask::bit(id));
+        assert_eq!(deduced, (backbone.checked | backbone.unchecked) & !black);
+    }
+
+    #[test]
+    fn probing_detects_dead_states() {
+        let solver = Solver::new(5, vec![Color::White; 25], WinCondition::default());
+        // 每行、每列各排除一格且覆盖中心：横向、纵向、斜向都不可能连成五格。
+        let unchecked = [0, 8, 12, 19, 21]
+            .into_iter()
+            .fold(Mask::EMPTY, |m, id| m | Mask::bit(id));
+        let res = solver.deductions(
+            Mask::EMPTY,
+            unchecked,
+            DeductionDepth::Backbone,
+            &mut SolveBudget::default(),
+        );
+        assert!(res.is_finished());
+        assert_eq!(res.value, None);
+    }
+}
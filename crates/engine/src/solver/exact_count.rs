@@ -0,0 +1,530 @@
+//! 精确解计数：按 row-major 逐格推进的轮廓 DP（frontier DP），不枚举解。
+//!
+//! 思路：
+//! - 每条颜色规则（`CellRule::tally`）都只依赖某个格子集合中“勾选数”的简单函数（至少 1 个 / 不超过 2 个 / 恰好 N 个 / 奇偶 / 两组相等），
+//!   因此只需为每条规则维护一个小的累加器，在其最后一个相关格子处结算；
+//! - 胜利条件用“每条直线上当前连续勾选的长度”跟踪，一旦达成就清空全部连线跟踪器；
+//! - DP 状态只包含“已开始但尚未结算”的累加器，已结算/无法再达成的部分一律归零，
+//!   因此勾选方式不同但对未来等价的前缀会合并到同一个状态上。
+//!
+//! 状态数主要取决于同时“悬而未决”的规则数量和连线跟踪器，与解的数量无关；
+//! 但在大而空旷的棋盘上（如 8x8 全白）连线跟踪器的组合仍会爆炸，因此调用方需给出状态上限。
+//!
+//! 保留全部层及层间转移（[`SampleTable`]）时，还可以从末层按计数加权逐层回溯，
+//! 得到在全部解上严格均匀的随机解。
+
+use std::collections::HashMap;
+use std::hash::{BuildHasherDefault, Hasher};
+
+use rand::Rng;
+
+use super::{registry, BudgetMeter, BudgetStop, Budgeted, SolveBudget, Solver};
+use crate::masks::Mask;
+
+/// FNV-1a：状态 key 很短，比默认的 SipHash 快得多（不做安全用途）。
+#[derive(Default)]
+struct FnvHasher(u64);
+
+impl Hasher for FnvHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        let mut h = if self.0 == 0 { 1469598103934665603u64 } else { self.0 };
+        for &b in bytes {
+            h ^= b as u64;
+            h = h.wrapping_mul(1099511628211);
+        }
+        self.0 = h;
+    }
+}
+
+type Layer = HashMap<Vec<i8>, u128, BuildHasherDefault<FnvHasher>>;
+
+/// 规则累加器的结算方式。
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Tally {
+    /// 红格：至少 1 个勾选（累加器封顶为 1）。
+    AtLeastOne,
+    /// 蓝格：不超过 2 个勾选（超过即剪枝）。
+    AtMostTwo,
+    /// 紫格：勾选数为奇数（累加器为奇偶位）。
+    Odd,
+    /// 橙格：勾选数为偶数。
+    Even,
+    /// 青格：bit1 表示自身勾选，bit0 表示四邻中至少 1 个勾选。
+    CheckedNeedsNeighbor,
+    /// 绿格/黄格：两组格子勾选数相等（+1/-1 累加差值）。
+    Balanced,
+    /// 数字格：恰好 N 个勾选（超过、或剩余格子补不足即剪枝）。
+    Exactly(u8),
+}
+
+impl Tally {
+    /// 所有相关格子都已决定后，累加器是否满足规则。
+    fn settle(self, acc: i8) -> bool {
+        match self {
+            Tally::AtLeastOne | Tally::Odd => acc == 1,
+            Tally::AtMostTwo => acc <= 2,
+            Tally::Even | Tally::Balanced => acc == 0,
+            Tally::CheckedNeedsNeighbor => acc != 0b10,
+            Tally::Exactly(n) => acc == n as i8,
+        }
+    }
+}
+
+/// 某个格子对某个累加器的贡献。
+#[derive(Clone, Copy, Debug)]
+struct Touch {
+    slot: usize,
+    tally: Tally,
+    weight: i8,
+    /// 该规则在此格之后（row-major）还剩多少个相关格子；为 0 时在此格结算。
+    remaining: u8,
+}
+
+/// 某个格子在一条连线直线上的位置。
+#[derive(Clone, Copy, Debug)]
+struct LineStep {
+    slot: usize,
+    /// 该直线在此格之后还剩多少个格子。
+    remaining: u8,
+}
+
+/// 预处理后的计数模型：逐格的贡献表与状态布局。
+struct CountModel {
+    touches: Vec<Vec<Touch>>,
+    line_steps: Vec<Vec<LineStep>>,
+    line_slots: std::ops::Range<usize>,
+    win_len: i8,
+    /// 状态向量最后一位：是否已达成胜利条件。
+    achieved_slot: usize,
+}
+
+impl CountModel {
+    /// 构造模型；若存在“无相关格子且不可能满足”的规则（如 1x1 上的红格），返回 None。
+    fn new(solver: &Solver) -> Option<Self> {
+        let rules = &solver.rules;
+        let size = rules.size;
+        let cell_count = size * size;
+
+        // (tally, [(cell, weight)])
+        let mut tallies: Vec<(Tally, Vec<(usize, i8)>)> = Vec::new();
+        for rule in registry() {
+            for &id in rules.cells(rule.color()) {
+                tallies.extend(rule.tally(rules, id));
+            }
+        }
+
+        let mut touches = vec![Vec::new(); cell_count];
+        for (slot, (tally, mut scope)) in tallies.into_iter().enumerate() {
+            if scope.is_empty() {
+                if !tally.settle(0) {
+                    return None;
+                }
+                continue;
+            }
+            scope.sort_unstable_by_key(|&(cell, _)| cell);
+            let n = scope.len();
+            for (k, (cell, weight)) in scope.into_iter().enumerate() {
+                touches[cell].push(Touch {
+                    slot,
+                    tally,
+                    weight,
+                    remaining: (n - 1 - k) as u8,
+                });
+            }
+        }
+        let tally_slots = touches.iter().flatten().map(|t| t.slot + 1).max().unwrap_or(0);
+
+        let lines = rules.win.lines(size);
+        let line_start = tally_slots;
+        let mut line_steps = vec![Vec::new(); cell_count];
+        for (k, line) in lines.iter().enumerate() {
+            for (p, &cell) in line.iter().enumerate() {
+                line_steps[cell].push(LineStep {
+                    slot: line_start + k,
+                    remaining: (line.len() - 1 - p) as u8,
+                });
+            }
+        }
+        let line_end = line_start + lines.len();
+
+        Some(Self {
+            touches,
+            line_steps,
+            line_slots: line_start..line_end,
+            win_len: rules.win.length(size) as i8,
+            achieved_slot: line_end,
+        })
+    }
+
+    /// 在状态 `key` 上决定格子 `cell`；返回 false 表示该分支已不可能满足。
+    fn apply(&self, key: &mut [i8], cell: usize, checked: bool) -> bool {
+        for t in &self.touches[cell] {
+            let acc = &mut key[t.slot];
+            if checked {
+                match t.tally {
+                    Tally::AtLeastOne => *acc = 1,
+                    Tally::AtMostTwo => {
+                        *acc += 1;
+                        if *acc > 2 {
+                            return false;
+                        }
+                    }
+                    Tally::Odd | Tally::Even => *acc ^= 1,
+                    Tally::CheckedNeedsNeighbor => *acc |= t.weight,
+                    Tally::Balanced => *acc += t.weight,
+                    Tally::Exactly(n) => {
+                        *acc += 1;
+                        if *acc > n as i8 {
+                            return false;
+                        }
+                    }
+                }
+            }
+            match t.tally {
+                Tally::Balanced if acc.unsigned_abs() > t.remaining => return false,
+                Tally::Exactly(n) if (*acc as u8) + t.remaining < n => return false,
+                _ => {}
+            }
+            if t.remaining == 0 {
+                if !t.tally.settle(*acc) {
+                    return false;
+                }
+                *acc = 0;
+            }
+        }
+
+        if key[self.achieved_slot] == 0 {
+            let mut achieved = false;
+            for step in &self.line_steps[cell] {
+                let run = &mut key[step.slot];
+                *run = if checked { *run + 1 } else { 0 };
+                if *run >= self.win_len {
+                    achieved = true;
+                } else if *run + (step.remaining as i8) < self.win_len {
+                    // 当前连续段已无法延伸到目标长度：与“从 0 重新开始”等价。
+                    *run = 0;
+                }
+            }
+            if achieved {
+                key[self.line_slots.clone()].fill(0);
+                key[self.achieved_slot] = 1;
+            }
+        }
+
+        true
+    }
+}
+
+impl Solver {
+    /// 精确统计解的数量（不枚举解，见模块文档）。
+    ///
+    /// - `checked_mask` / `unchecked_mask` 的约定与 [`Solver::solve_masks_cdcl_with_budget`] 相同；
+    /// - `max_states`：DP 任意一层的状态数上限（0 表示不限制）；超过时放弃（`BudgetStop::States`）；
+    /// - `budget` 按 DP 状态转移计数；耗尽时放弃，`value` 为 None；
+    /// - 结果超过 `u128` 时饱和为 `u128::MAX`。
+    pub fn count_solutions_exact(
+        &self,
+        checked_mask: Mask,
+        unchecked_mask: Mask,
+        max_states: usize,
+        budget: &mut SolveBudget,
+    ) -> Budgeted<Option<u128>> {
+        let (checked_mask, unchecked_mask) = self.normalize_masks(checked_mask, unchecked_mask);
+        let mut meter = BudgetMeter::new(budget);
+        let Some(model) = CountModel::new(self) else {
+            return meter.finish(Some(0));
+        };
+        if model.line_slots.is_empty() {
+            return meter.finish(Some(0));
+        }
+
+        let size = self.rules.size;
+        let mut forced_checked = checked_mask;
+        for &id in &self.rules.given_cells {
+            forced_checked.insert(id);
+        }
+
+        let mut layer = Layer::default();
+        layer.insert(vec![0; model.achieved_slot + 1], 1);
+        for cell in 0..size * size {
+            let forced = (forced_checked.contains(cell), unchecked_mask.contains(cell));
+            let choices: &[bool] = match forced {
+                (true, true) => return meter.finish(Some(0)),
+                (true, false) => &[true],
+                (false, true) => &[false],
+                (false, false) => &[false, true],
+            };
+
+            let mut next = Layer::with_capacity_and_hasher(layer.len() * 2, Default::default());
+            for (key, count) in &layer {
+                if meter.tick() {
+                    return meter.finish(None);
+                }
+                for &checked in choices {
+                    let mut key = key.clone();
+                    if model.apply(&mut key, cell, checked) {
+                        let slot = next.entry(key).or_insert(0);
+                        *slot = slot.saturating_add(*count);
+                    }
+                }
+            }
+            if next.is_empty() {
+                return meter.finish(Some(0));
+            }
+            if max_states != 0 && next.len() > max_states {
+                meter.stop(BudgetStop::States);
+                return meter.finish(None);
+            }
+            layer = next;
+        }
+
+        meter.finish(Some(
+            layer
+                .into_iter()
+                .filter(|(key, _)| key[model.achieved_slot] != 0)
+                .fold(0u128, |acc, (_, count)| acc.saturating_add(count)),
+        ))
+    }
+}
+
+/// 保留全部层的前向 DP，用于均匀抽样。
+pub(super) struct SampleTable {
+    /// `counts[i]`：决定第 i 格之前各状态的前缀计数（下标即状态编号）。
+    counts: Vec<Vec<u128>>,
+    /// `preds[i][j]`：决定第 i 格后第 j 个状态的全部来路（上一层状态编号, 是否勾选）。
+    preds: Vec<Vec<Vec<(u32, bool)>>>,
+    /// 末层中已达成胜利条件的状态编号。
+    accepting: Vec<u32>,
+    /// 解的总数（饱和到 `u128::MAX`）。
+    pub total: u128,
+}
+
+/// 按权重随机取一项；权重和必须为正。
+fn pick<T: Copy>(rng: &mut impl Rng, items: impl Iterator<Item = (T, u128)> + Clone) -> T {
+    let total = items.clone().fold(0u128, |acc, (_, w)| acc.saturating_add(w));
+    let mut r = rng.gen_range(0..total);
+    let mut last = None;
+    for (item, w) in items {
+        if r < w {
+            return item;
+        }
+        r -= w;
+        last = Some(item);
+    }
+    last.expect("权重和为正")
+}
+
+impl SampleTable {
+    fn empty() -> Self {
+        Self {
+            counts: Vec::new(),
+            preds: Vec::new(),
+            accepting: Vec::new(),
+            total: 0,
+        }
+    }
+
+    /// 均匀随机取一个解：末层按计数选状态，再逐层按前驱计数回溯。要求 `total > 0`。
+    pub(super) fn sample(&self, rng: &mut impl Rng) -> Mask {
+        let last = &self.counts[self.preds.len()];
+        let mut state = pick(rng, self.accepting.iter().map(|&j| (j, last[j as usize])));
+        let mut mask = Mask::EMPTY;
+        for cell in (0..self.preds.len()).rev() {
+            let prev = &self.counts[cell];
+            let (from, checked) = pick(
+                rng,
+                self.preds[cell][state as usize]
+                    .iter()
+                    .map(|&(p, checked)| ((p, checked), prev[p as usize])),
+            );
+            if checked {
+                mask.insert(cell);
+            }
+            state = from;
+        }
+        mask
+    }
+}
+
+impl Solver {
+    /// 与 [`Solver::count_solutions_exact`] 相同的前向 DP，但保留全部层与转移供抽样。
+    ///
+    /// 预算耗尽或状态数超过 `max_states` 时返回 None（停止原因记在 `meter` 上）。
+    pub(super) fn sample_table(
+        &self,
+        checked_mask: Mask,
+        unchecked_mask: Mask,
+        max_states: usize,
+        meter: &mut BudgetMeter,
+    ) -> Option<SampleTable> {
+        let (checked_mask, unchecked_mask) = self.normalize_masks(checked_mask, unchecked_mask);
+        let Some(model) = CountModel::new(self) else {
+            return Some(SampleTable::empty());
+        };
+        if model.line_slots.is_empty() {
+            return Some(SampleTable::empty());
+        }
+
+        let size = self.rules.size;
+        let mut forced_checked = checked_mask;
+        for &id in &self.rules.given_cells {
+            forced_checked.insert(id);
+        }
+
+        let mut keys = vec![vec![0i8; model.achieved_slot + 1]];
+        let mut table = SampleTable {
+            counts: vec![vec![1]],
+            preds: Vec::with_capacity(size * size),
+            accepting: Vec::new(),
+            total: 0,
+        };
+        for cell in 0..size * size {
+            let choices: &[bool] = match (forced_checked.contains(cell), unchecked_mask.contains(cell)) {
+                (true, true) => return Some(SampleTable::empty()),
+                (true, false) => &[true],
+                (false, true) => &[false],
+                (false, false) => &[false, true],
+            };
+
+            let counts = table.counts.last().expect("至少有初始层");
+            let mut index: HashMap<Vec<i8>, u32, BuildHasherDefault<FnvHasher>> =
+                HashMap::with_capacity_and_hasher(keys.len() * 2, Default::default());
+            let mut next_keys: Vec<Vec<i8>> = Vec::new();
+            let mut next_counts: Vec<u128> = Vec::new();
+            let mut preds: Vec<Vec<(u32, bool)>> = Vec::new();
+            for (from, key) in keys.iter().enumerate() {
+                if meter.tick() {
+                    return None;
+                }
+                for &checked in choices {
+                    let mut key = key.clone();
+                    if !model.apply(&mut key, cell, checked) {
+                        continue;
+                    }
+                    let j = *index.entry(key.clone()).or_insert_with(|| {
+                        next_keys.push(key);
+                        next_counts.push(0);
+                        preds.push(Vec::new());
+                        (next_keys.len() - 1) as u32
+                    }) as usize;
+                    next_counts[j] = next_counts[j].saturating_add(counts[from]);
+                    preds[j].push((from as u32, checked));
+                }
+            }
+            if next_keys.is_empty() {
+                return Some(SampleTable::empty());
+            }
+            if max_states != 0 && next_keys.len() > max_states {
+                meter.stop(BudgetStop::States);
+                return None;
+            }
+            keys = next_keys;
+            table.counts.push(next_counts);
+            table.preds.push(preds);
+        }
+
+        let last = table.counts.last().expect("至少有初始层");
+        for (j, key) in keys.iter().enumerate() {
+            if key[model.achieved_slot] != 0 {
+                table.accepting.push(j as u32);
+                table.total = table.total.saturating_add(last[j]);
+            }
+        }
+        Some(table)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::colors::Color;
+    use crate::masks::{BoardMasks, Neighborhood};
+    use crate::solver::testing::{random_board, test_rng, BASE_CLUES};
+    use crate::win::WinCondition;
+
+    fn exact(solver: &Solver, checked: Mask, unchecked: Mask) -> u128 {
+        solver
+            .count_solutions_exact(checked, unchecked, 0, &mut SolveBudget::default())
+            .value
+            .unwrap()
+    }
+
+    #[test]
+    fn exact_count_matches_enumeration() {
+        for seed in 0..40u64 {
+            let colors = random_board(&mut test_rng(seed), 5, &BASE_CLUES, 1.0 / 9.0);
+            let solver = Solver::new(5, colors, WinCondition::default());
+            let enumerated = solver.solve_masks_limit(0).len() as u128;
+            assert_eq!(
+                exact(&solver, Mask::EMPTY, Mask::EMPTY),
+                enumerated,
+                "seed={seed}"
+            );
+        }
+    }
+
+    #[test]
+    fn exact_count_matches_enumeration_with_numbered_clues() {
+        // 数字取自一个含整行连线的目标解：至少有这一个解。
+        let target = [10, 11, 12, 13, 14, 0, 6, 18, 24]
+            .into_iter()
+            .fold(Mask::EMPTY, |m, c| m | Mask::bit(c));
+        let masks = BoardMasks::new(5);
+        let colors: Vec<Color> = (0..25)
+            .map(|i| match i % 3 {
+                0 => Color::Number((target & masks.neighbors(Neighborhood::King, i)).count_ones() as u8),
+                _ => Color::White,
+            })
+            .collect();
+        let solver = Solver::new(5, colors, WinCondition::default());
+        let enumerated = solver.solve_masks_limit(0);
+        assert!(enumerated.contains(&target));
+        assert_eq!(
+            exact(&solver, Mask::EMPTY, Mask::EMPTY),
+            enumerated.len() as u128
+        );
+    }
+
+    #[test]
+    fn exact_count_respects_masks_and_win_conditions() {
+        let win = WinCondition::InARow {
+            length: 3,
+            diagonals: true,
+        };
+        let colors = random_board(&mut test_rng(7), 4, &BASE_CLUES, 1.0 / 9.0);
+        let solver = Solver::new(4, colors, win);
+        let checked = Mask::bit(5);
+        let unchecked = Mask::bit(0) | Mask::bit(10);
+        assert_eq!(
+            exact(&solver, checked, unchecked),
+            solver.solve_masks_limit_with_checked_mask(checked, unchecked, 0).len() as u128
+        );
+
+        // 5x5 全白：解即“至少含一条五连线”的勾选方式，直接按 2^25 种勾选暴力核对。
+        let solver = Solver::new(5, vec![Color::White; 25], WinCondition::default());
+        let lines: Vec<u32> = WinCondition::default()
+            .segment_masks(5)
+            .iter()
+            .map(|m| m.iter_ones().fold(0u32, |acc, i| acc | (1 << i)))
+            .collect();
+        let expected = (0u32..1 << 25)
+            .filter(|&m| lines.iter().any(|&line| line & !m == 0))
+            .count() as u128;
+        assert_eq!(exact(&solver, Mask::EMPTY, Mask::EMPTY), expected);
+    }
+
+    #[test]
+    fn exact_count_handles_large_loose_boards() {
+        // 9x9 全白、只允许勾选前两行：每行 5 个窗口，解数可直接算出。
+        let solver = Solver::new(9, vec![Color::White; 81], WinCondition::default());
+        let unchecked = Mask::full(81) & !Mask::full(18);
+        // 单行 9 格中不含连续 5 个勾选的方式数：a(n) = a(n-1)+...+a(n-5)，a(0..5)=1,2,4,8,16 → a(9)=464
+        let no_run_per_row = 464u128;
+        let expected = (1u128 << 18) - no_run_per_row * no_run_per_row;
+        assert_eq!(exact(&solver, Mask::EMPTY, unchecked), expected);
+    }
+}
@@ -0,0 +1,326 @@
+//! 分级提示：同一条提示按“剧透程度”拆成若干层，供 UI 逐级展开。
+//!
+//! 1. 只点明区域与规则；
+//! 2. 点明规则实例及其关注格；
+//! 3. 点明目标格（不说勾选还是排除）；
+//! 4. 完整答案与推导链（即 `HintResult` 本身）。
+//!
+//! 各层都由一次 `hint_next` 的结果（`HintReason`）派生，响应里带上全部层级，UI 升级时无需重新搜索。
+
+use serde::Serialize;
+
+use super::{
+    registry, BudgetStop, HintMove, HintReason, HintReasonKind, HintReasonStep, HintResult,
+    HintStatus, SolveBudget, Solver, Technique,
+};
+use crate::i18n::{msg, Locale, Localize, Message};
+use crate::masks::Mask;
+
+/// 最详细的一层（完整答案）。
+pub const MAX_HINT_LEVEL: u8 = 4;
+
+/// 分级提示中的一层。
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HintTier {
+    /// 1..=`MAX_HINT_LEVEL`。
+    pub level: u8,
+    pub message: String,
+    /// `message` 的消息键与参数（见 `i18n`）。
+    pub message_key: Message,
+    /// 本层点明的规则（与前端 `rules.json` 的 id 对齐）。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rule_id: Option<&'static str>,
+    /// 推出结论所用的技巧（第 2 层起，与 `HintReason::technique` 相同）。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub technique: Option<Technique>,
+    /// 本层需要高亮的格子。
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cells: Vec<usize>,
+    /// 具体操作（仅最后一层）。
+    #[serde(rename = "move", skip_serializing_if = "Option::is_none")]
+    pub mv: Option<HintMove>,
+    /// 详细推导链（仅最后一层）。
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub steps: Vec<HintReasonStep>,
+}
+
+/// 分级提示：`tiers` 由浅到深排列，`level` 为本次请求（截断到可用层数后）的层级。
+#[derive(Debug, Clone, Serialize)]
+pub struct GradedHint {
+    pub status: HintStatus,
+    pub level: u8,
+    /// 请求层级的提示文字（即 `tiers[level - 1].message`）。
+    pub message: String,
+    #[serde(rename = "messageKey")]
+    pub message_key: Message,
+    pub tiers: Vec<HintTier>,
+    /// 预算耗尽的原因；None 表示在预算内完成。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exhausted: Option<BudgetStop>,
+}
+
+impl HintTier {
+    fn new(level: u8, message: Message, rule_id: Option<&'static str>, cells: Vec<usize>) -> Self {
+        Self {
+            level,
+            message: message.zh(),
+            message_key: message,
+            rule_id,
+            technique: None,
+            cells,
+            mv: None,
+            steps: vec![],
+        }
+    }
+}
+
+impl Localize for HintTier {
+    fn localize(&mut self, locale: Locale) {
+        self.message = self.message_key.render(locale);
+        self.steps.localize(locale);
+    }
+}
+
+impl Localize for GradedHint {
+    fn localize(&mut self, locale: Locale) {
+        self.message = self.message_key.render(locale);
+        self.tiers.localize(locale);
+    }
+}
+
+/// 规则 id 对应的简称（用于前几层的提示文字）。
+fn rule_name(rule_id: Option<&str>) -> Message {
+    match rule_id {
+        Some("bingo") => msg!("rule.short.bingo"),
+        Some(id) => registry()
+            .iter()
+            .find(|rule| rule.id() == id)
+            .map_or_else(|| msg!("rule.generic"), |rule| rule.short_name()),
+        None => msg!("rule.generic"),
+    }
+}
+
+/// 把棋盘按行列三等分，返回 `cell` 所在的区域名称与区域内的格子。
+fn region_of(size: usize, cell: usize) -> (Message, Vec<usize>) {
+    const NAMES: [[&str; 3]; 3] = [
+        ["region.top_left", "region.top", "region.top_right"],
+        ["region.left", "region.center", "region.right"],
+        ["region.bottom_left", "region.bottom", "region.bottom_right"],
+    ];
+    let band = |i: usize| (i * 3 / size).min(2);
+    let (r, c) = (band(cell / size), band(cell % size));
+    let cells = (0..size * size)
+        .filter(|&id| band(id / size) == r && band(id % size) == c)
+        .collect();
+    (Message::new(NAMES[r][c]), cells)
+}
+
+impl GradedHint {
+    /// 把完整提示拆成分级提示；`level` 截断到 1..=可用层数。
+    pub fn from_result(size: usize, result: HintResult, level: u8) -> Self {
+        let tiers = match (&result.reason, &result.mv) {
+            (Some(reason), mv) => Self::tiers(size, &result, reason, mv.as_ref()),
+            // 预算耗尽或没有可给出的一步：只有一层。
+            (None, _) => vec![HintTier {
+                mv: result.mv.clone(),
+                ..HintTier::new(1, result.message_key.clone(), None, vec![])
+            }],
+        };
+        let level = level.clamp(1, tiers.len() as u8);
+        let chosen = &tiers[level as usize - 1];
+        Self {
+            status: result.status,
+            level,
+            message: chosen.message.clone(),
+            message_key: chosen.message_key.clone(),
+            tiers,
+            exhausted: result.exhausted,
+        }
+    }
+
+    fn tiers(
+        size: usize,
+        result: &HintResult,
+        reason: &HintReason,
+        mv: Option<&HintMove>,
+    ) -> Vec<HintTier> {
+        let name = rule_name(reason.rule_id);
+        let anchor = mv
+            .map(|m| m.cell)
+            .or_else(|| reason.affected_cells.first().copied());
+        let (region, region_cells) = match anchor {
+            Some(cell) => region_of(size, cell),
+            None => (msg!("region.board"), vec![]),
+        };
+
+        let (nudge, focus, focus_cells) = match reason.kind {
+            HintReasonKind::Propagate => (
+                msg!(
+                    "graded.propagate.nudge",
+                    region = region,
+                    rule = name.clone()
+                ),
+                msg!("graded.propagate.focus", rule = name),
+                reason.secondary_cells.clone(),
+            ),
+            HintReasonKind::Contradiction => (
+                msg!(
+                    "graded.contradiction.nudge",
+                    region = region,
+                    rule = name.clone()
+                ),
+                msg!("graded.contradiction.focus", rule = name),
+                reason.secondary_cells.clone(),
+            ),
+            HintReasonKind::Suggest => (
+                msg!("graded.suggest.nudge", region = region),
+                msg!("graded.suggest.focus"),
+                reason.secondary_cells.clone(),
+            ),
+            HintReasonKind::Repair => {
+                let checks = reason
+                    .conflict
+                    .as_ref()
+                    .map_or_else(|| reason.affected_cells.clone(), |c| c.checks.clone());
+                (
+                    msg!("graded.repair.nudge", rule = name),
+                    msg!("graded.repair.focus", count = checks.len()),
+                    checks,
+                )
+            }
+        };
+
+        let mut tiers = vec![
+            HintTier::new(1, nudge, reason.rule_id, region_cells),
+            HintTier::new(2, focus, reason.rule_id, focus_cells),
+        ];
+        if let Some(m) = mv {
+            let (row, col) = (m.cell / size + 1, m.cell % size + 1);
+            let message = match reason.kind {
+                HintReasonKind::Repair => msg!("graded.target.repair", row = row, col = col),
+                HintReasonKind::Suggest => msg!("graded.target.suggest", row = row, col = col),
+                _ => msg!("graded.target.forced", row = row, col = col),
+            };
+            tiers.push(HintTier::new(3, message, reason.rule_id, vec![m.cell]));
+        }
+        tiers.push(HintTier {
+            mv: mv.cloned(),
+            steps: reason.steps.clone(),
+            ..HintTier::new(
+                tiers.len() as u8 + 1,
+                result.message_key.clone(),
+                reason.rule_id,
+                reason.affected_cells.clone(),
+            )
+        });
+        for tier in tiers.iter_mut().skip(1) {
+            tier.technique = reason.technique;
+        }
+        tiers
+    }
+}
+
+impl Solver {
+    /// 分级提示（见模块文档）：参数与 [`Solver::hint_next_with_budget`] 相同，`level` 取 1..=4。
+    pub fn hint_with_level(
+        &self,
+        checked_mask: Mask,
+        unchecked_mask: Mask,
+        level: u8,
+        budget: &mut SolveBudget,
+    ) -> GradedHint {
+        let result = self.hint_next_with_budget(checked_mask, unchecked_mask, budget);
+        GradedHint::from_result(self.rules.size, result, level)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::colors::Color;
+    use crate::win::WinCondition;
+
+    /// 中心蓝格 + 两个黑邻居：传播即可推出结论。
+    fn forced_solver() -> Solver {
+        let mut colors = vec![Color::White; 25];
+        colors[12] = Color::Blue;
+        colors[6] = Color::Black;
+        colors[7] = Color::Black;
+        Solver::new(5, colors, WinCondition::default())
+    }
+
+    #[test]
+    fn tiers_escalate_without_spoiling() {
+        let solver = forced_solver();
+        let full = solver.hint_next(Mask::EMPTY, Mask::EMPTY);
+        let target = full.mv.as_ref().expect("forced move").cell;
+        let graded =
+            solver.hint_with_level(Mask::EMPTY, Mask::EMPTY, 1, &mut SolveBudget::default());
+        assert_eq!(graded.level, 1);
+        assert_eq!(graded.tiers.len(), MAX_HINT_LEVEL as usize);
+        assert_eq!(graded.message, graded.tiers[0].message);
+
+        for (k, tier) in graded.tiers.iter().enumerate() {
+            assert_eq!(tier.level as usize, k + 1);
+            assert_eq!(tier.rule_id, Some("blue"));
+        }
+        // 前两层不点明目标格（关注格里混有目标格，但不单独标出），前三层不给出操作。
+        assert!(!graded.tiers[0].message.contains("必须"));
+        assert!(graded.tiers[..2].iter().all(|t| !t.message.contains('(')));
+        assert!(!graded.tiers[1].cells.is_empty());
+        assert!(graded.tiers[..3]
+            .iter()
+            .all(|t| t.mv.is_none() && t.steps.is_empty()));
+        assert_eq!(graded.tiers[2].cells, vec![target]);
+        assert!(graded.tiers[0].cells.contains(&target));
+        // 第 1 层只点明规则，从第 2 层起才说出技巧名。
+        assert_eq!(graded.tiers[0].technique, None);
+        assert!(graded.tiers[1..]
+            .iter()
+            .all(|t| t.technique == Some(Technique::BlueSaturation)));
+
+        let last = &graded.tiers[3];
+        assert_eq!(last.message, full.message);
+        assert_eq!(last.mv.as_ref().map(|m| m.cell), Some(target));
+        assert!(!last.steps.is_empty());
+    }
+
+    #[test]
+    fn tiers_render_in_requested_locale() {
+        let solver = forced_solver();
+        let mut graded =
+            solver.hint_with_level(Mask::EMPTY, Mask::EMPTY, 4, &mut SolveBudget::default());
+        let zh: Vec<String> = graded.tiers.iter().map(|t| t.message.clone()).collect();
+        graded.localize(Locale::En);
+        let is_cjk = |c: char| ('\u{4e00}'..='\u{9fff}').contains(&c);
+        for (tier, zh) in graded.tiers.iter().zip(&zh) {
+            assert_ne!(&tier.message, zh);
+            assert!(!tier.message.contains(is_cjk), "{}", tier.message);
+            assert!(tier.steps.iter().all(|s| !s.title.contains(is_cjk)));
+        }
+        assert_eq!(graded.message, graded.tiers[3].message);
+        assert_eq!(graded.message_key.key, "hint.forced.uncheck");
+        graded.localize(Locale::ZhCn);
+        assert_eq!(graded.tiers[0].message, zh[0]);
+    }
+
+    #[test]
+    fn level_is_clamped_to_available_tiers() {
+        let solver = forced_solver();
+        let graded =
+            solver.hint_with_level(Mask::EMPTY, Mask::EMPTY, 9, &mut SolveBudget::default());
+        assert_eq!(graded.level, MAX_HINT_LEVEL);
+        assert!(graded.tiers[3].mv.is_some());
+
+        let cancel = std::sync::atomic::AtomicBool::new(true);
+        let mut budget = SolveBudget {
+            cancel: Some(&cancel),
+            ..SolveBudget::default()
+        };
+        let exhausted = solver.hint_with_level(Mask::EMPTY, Mask::EMPTY, 3, &mut budget);
+        assert_eq!(exhausted.exhausted, Some(BudgetStop::Cancelled));
+        assert_eq!((exhausted.level, exhausted.tiers.len()), (1, 1));
+        assert_eq!(exhausted.message, exhausted.tiers[0].message);
+    }
+}
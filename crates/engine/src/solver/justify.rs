@@ -0,0 +1,229 @@
+//! 传播结论的论证图（justification DAG）：每个传播结论连到推出它的规则实例，
+//! 以及该实例作用范围内比它更早确定的格子（前件）。
+//!
+//! 图不在传播时构建：`HintExplainObserver` 只记下每个格子首次赋值的先后序号与传播来源，
+//! 需要“详细解释”时再按规则实例的作用范围（同 CDCL 的成因记录）还原前件。
+//! 从目标格出发回溯，只保留真正牵涉其中的结论，得到从前提到目标的推导链。
+
+use std::collections::VecDeque;
+
+use super::{
+    cell_assignment, rule_type_to_rule_id, rule_type_to_title, HintExplainObserver, HintReasonStep,
+    RuleType, Solver,
+};
+use crate::i18n::{msg, Param};
+use crate::masks::Mask;
+
+/// 推导链最多展示的中间结论数（Bingo 等大范围规则的前件可能很多）。
+const MAX_CHAIN_DEDUCTIONS: usize = 12;
+
+/// 论证图中的一个传播结论。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct Deduction {
+    pub cell: usize,
+    pub checked: bool,
+    pub rule: Option<RuleType>,
+    pub focus: Vec<usize>,
+    /// 前件：作用范围内比该结论更早确定的格子。
+    pub antecedents: Vec<usize>,
+}
+
+/// 从某个结论回溯得到的论证子图。
+#[derive(Debug, Clone, Default)]
+pub(super) struct Justification {
+    /// 牵涉其中的传播结论（含目标本身），按推出的先后排列。
+    pub deductions: Vec<Deduction>,
+    /// 途经的前提格（黑格、玩家的勾选/排除）。
+    pub givens: Mask,
+    /// 超出 `MAX_CHAIN_DEDUCTIONS` 而省略的结论数。
+    pub omitted: usize,
+}
+
+impl Solver {
+    /// `cell` 的传播结论在论证图中的一个结点；`cell` 不是传播结论时返回 None。
+    fn deduction(
+        &self,
+        obs: &HintExplainObserver,
+        cell: usize,
+        win_scope: Mask,
+    ) -> Option<Deduction> {
+        let (rule, focus, checked) = obs.propagate_info(cell)?;
+        let at = obs.assigned_at.get(&cell).copied()?;
+        let antecedents = self
+            .rule_scope(rule, focus, win_scope)
+            .iter_ones()
+            .filter(|id| obs.assigned_at.get(id).is_some_and(|&t| t < at))
+            .collect();
+        Some(Deduction {
+            cell,
+            checked,
+            rule,
+            focus: focus.clone(),
+            antecedents,
+        })
+    }
+
+    /// 从 `cell` 的传播结论回溯论证子图（广度优先，离目标越近越优先保留）。
+    pub(super) fn justification(&self, obs: &HintExplainObserver, cell: usize) -> Justification {
+        let win_scope = self.win_scope();
+        let mut out = Justification::default();
+        let mut seen = Mask::bit(cell);
+        let mut queue = VecDeque::from([cell]);
+        while let Some(id) = queue.pop_front() {
+            let Some(d) = self.deduction(obs, id, win_scope) else {
+                out.givens.insert(id);
+                continue;
+            };
+            if out.deductions.len() >= MAX_CHAIN_DEDUCTIONS {
+                out.omitted += 1;
+                continue;
+            }
+            for &a in &d.antecedents {
+                if !seen.contains(a) {
+                    seen.insert(a);
+                    queue.push_back(a);
+                }
+            }
+            out.deductions.push(d);
+        }
+        out.deductions.sort_by_key(|d| obs.assigned_at[&d.cell]);
+        out
+    }
+
+    /// 目标格之前的推导链（前提 + 中间结论），用于 `HintReason.steps` 的详细解释。
+    ///
+    /// 同一规则实例推出的多个结论合并为一步；目标格本身的结论由调用方另行解释。
+    pub(super) fn justification_steps(
+        &self,
+        obs: &HintExplainObserver,
+        cell: usize,
+    ) -> Vec<HintReasonStep> {
+        let size = self.rules.size;
+        let just = self.justification(obs, cell);
+        let mut steps = Vec::new();
+        if !just.givens.is_empty() {
+            steps.push(HintReasonStep::new(
+                msg!("justify.givens", count = just.givens.count_ones()),
+                None,
+                just.givens.iter_ones().collect(),
+            ));
+        }
+        if just.omitted > 0 {
+            steps.push(HintReasonStep::new(
+                msg!("justify.omitted", count = just.omitted),
+                None,
+                vec![],
+            ));
+        }
+
+        let chain: Vec<&Deduction> = just.deductions.iter().filter(|d| d.cell != cell).collect();
+        let mut i = 0;
+        while i < chain.len() {
+            let head = chain[i];
+            let mut j = i + 1;
+            while j < chain.len() && chain[j].rule == head.rule && chain[j].focus == head.focus {
+                j += 1;
+            }
+            let group = &chain[i..j];
+            let conclusions = group
+                .iter()
+                .map(|d| cell_assignment(size, d.cell, d.checked))
+                .collect();
+            let mut cells: Vec<usize> = group.iter().map(|d| d.cell).collect();
+            for &c in &head.focus {
+                if !cells.contains(&c) {
+                    cells.push(c);
+                }
+            }
+            steps.push(HintReasonStep::new(
+                msg!(
+                    "hint.derive",
+                    rule = head
+                        .rule
+                        .map_or_else(|| msg!("rule.generic"), rule_type_to_title),
+                    conclusions = Param::list("sep.comma", conclusions)
+                ),
+                head.rule.map(rule_type_to_rule_id),
+                cells,
+            ));
+            i = j;
+        }
+        steps
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::colors::Color;
+    use crate::solver::{BudgetMeter, SolveBudget};
+    use crate::win::WinCondition;
+
+    /// 中心蓝格 + 两个黑邻居：蓝格推出其余邻居不勾选；
+    /// 右下红格的邻居里只剩 (5,4) 未定，由红格推出必须勾选——它依赖蓝格的结论。
+    fn chained() -> (Solver, HintExplainObserver) {
+        let mut colors = vec![Color::White; 25];
+        colors[12] = Color::Blue;
+        colors[6] = Color::Black;
+        colors[7] = Color::Black;
+        colors[22] = Color::Red;
+        let solver = Solver::new(5, colors, WinCondition::default());
+        let mut budget = SolveBudget::default();
+        let mut meter = BudgetMeter::new(&mut budget);
+        let (_, _, obs) = solver
+            .solve_one_with_masks_with_hint_trace(Mask::EMPTY, Mask::bit(21), &mut meter)
+            .expect("solvable");
+        (solver, obs)
+    }
+
+    #[test]
+    fn chain_reaches_back_to_givens() {
+        let (solver, obs) = chained();
+        let just = solver.justification(&obs, 23);
+        let target = just.deductions.last().expect("target deduction");
+        assert_eq!(
+            (target.cell, target.checked, target.rule),
+            (23, true, Some(RuleType::Cell(Color::Red)))
+        );
+        for cell in [16, 17, 18] {
+            let d = just
+                .deductions
+                .iter()
+                .find(|d| d.cell == cell)
+                .expect("blue deduction in chain");
+            assert_eq!(
+                (d.checked, d.rule),
+                (false, Some(RuleType::Cell(Color::Blue)))
+            );
+        }
+        assert!(just.givens.contains(21));
+        assert!(just.givens.contains(6) && just.givens.contains(7));
+        assert_eq!(just.omitted, 0);
+        // 论证图按推出顺序排列：前件总在结论之前。
+        for (k, d) in just.deductions.iter().enumerate() {
+            for a in &d.antecedents {
+                if let Some(p) = just.deductions.iter().position(|x| x.cell == *a) {
+                    assert!(p < k);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn hint_steps_show_the_chain() {
+        let (solver, obs) = chained();
+        let hint = solver.propagate_hint(&obs, 23, true);
+        let steps = hint.reason.expect("reason").steps;
+        assert_eq!(steps[0].title_key.key, "justify.givens");
+        let blue = steps
+            .iter()
+            .position(|s| s.rule_id == Some("blue"))
+            .expect("blue step");
+        let red = steps
+            .iter()
+            .position(|s| s.rule_id == Some("red"))
+            .expect("red step");
+        assert!(blue < red);
+        assert_eq!(steps.last().unwrap().title_key.key, "hint.conclude.check");
+    }
+}
@@ -0,0 +1,174 @@
+//! 逐格边际概率：每个格子在全部解中被勾选的比例（用于热力图）。
+//!
+//! 解不多时直接枚举，结果精确；解超过 `samples` 个时改用轮廓 DP（见 `exact_count`）
+//! 得到总数，并从 DP 中均匀抽取 `samples` 个解估计比例。抽样使用固定种子，同一输入结果稳定。
+
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+use super::{BudgetMeter, Budgeted, SolveBudget, Solver};
+use crate::masks::Mask;
+
+/// 抽样使用的固定种子。
+const SAMPLE_SEED: u64 = 0x6b61_6972_656d;
+
+/// 逐格边际概率。
+#[derive(Debug, Clone, PartialEq)]
+pub struct Marginals {
+    /// row-major，每格被勾选的比例（0..=1）；无解时全为 0。
+    pub cells: Vec<f64>,
+    /// 解的总数（超过 u128 时饱和）。
+    pub count: u128,
+    /// 估计所用的样本数；0 表示 `cells` 为精确值。
+    pub samples: u32,
+}
+
+impl Marginals {
+    fn from_solutions(cell_count: usize, solutions: &[Mask], samples: u32) -> Self {
+        let mut hits = vec![0u32; cell_count];
+        for s in solutions {
+            for id in s.iter_ones() {
+                hits[id] += 1;
+            }
+        }
+        let n = solutions.len().max(1) as f64;
+        Self {
+            cells: hits.into_iter().map(|h| h as f64 / n).collect(),
+            count: solutions.len() as u128,
+            samples,
+        }
+    }
+}
+
+impl Solver {
+    /// 计算逐格边际概率（见模块文档）。
+    ///
+    /// - mask 约定同 [`Solver::solve_masks_cdcl_with_budget`]；
+    /// - `samples`：解数不超过它时精确枚举，否则抽取这么多个样本估计；0 表示总是精确枚举；
+    /// - `max_states`：抽样所用 DP 的单层状态数上限，约定同 [`Solver::count_solutions_exact`]；
+    /// - 预算耗尽或状态数超限时 `value` 为 None。
+    pub fn solution_marginals(
+        &self,
+        checked_mask: Mask,
+        unchecked_mask: Mask,
+        samples: u32,
+        max_states: usize,
+        budget: &mut SolveBudget,
+    ) -> Budgeted<Option<Marginals>> {
+        let (checked_mask, unchecked_mask) = self.normalize_masks(checked_mask, unchecked_mask);
+        let cell_count = self.rules.size * self.rules.size;
+        let mut meter = BudgetMeter::new(budget);
+
+        let mut obs = ();
+        let Some(mut state) = self.initial_state_with_masks(checked_mask, unchecked_mask, &mut obs)
+        else {
+            return meter.finish(Some(Marginals::from_solutions(cell_count, &[], 0)));
+        };
+        if !self.propagate_to_fixpoint(&mut state, &mut obs) {
+            return meter.finish(Some(Marginals::from_solutions(cell_count, &[], 0)));
+        }
+
+        let limit = if samples == 0 {
+            0
+        } else {
+            samples as usize + 1
+        };
+        let mut out = Vec::new();
+        self.search(state, limit, &mut out, 0, &mut meter);
+        if meter.stopped().is_some() {
+            return meter.finish(None);
+        }
+        if limit == 0 || out.len() < limit {
+            return meter.finish(Some(Marginals::from_solutions(cell_count, &out, 0)));
+        }
+
+        let Some(table) = self.sample_table(checked_mask, unchecked_mask, max_states, &mut meter)
+        else {
+            return meter.finish(None);
+        };
+        if table.total == 0 {
+            debug_assert!(false, "DP 与搜索的解集一致");
+            return meter.finish(Some(Marginals::from_solutions(cell_count, &[], 0)));
+        }
+        let mut rng = ChaCha8Rng::seed_from_u64(SAMPLE_SEED);
+        let drawn: Vec<Mask> = (0..samples).map(|_| table.sample(&mut rng)).collect();
+        let mut marginals = Marginals::from_solutions(cell_count, &drawn, samples);
+        marginals.count = table.total;
+        meter.finish(Some(marginals))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::colors::Color;
+    use crate::solver::BudgetStop;
+    use crate::win::WinCondition;
+
+    #[test]
+    fn exact_marginals_match_enumeration() {
+        let grid = crate::generate::generate_puzzle(3, 5, WinCondition::default()).unwrap();
+        let colors: Vec<Color> = grid
+            .into_iter()
+            .flatten()
+            .map(|v| Color::from_u8(v).unwrap())
+            .collect();
+        let solver = Solver::new(5, colors, WinCondition::default());
+        let solutions = solver.solve_masks_limit_with_checked_mask(Mask::EMPTY, Mask::EMPTY, 0);
+        let got =
+            solver.solution_marginals(Mask::EMPTY, Mask::EMPTY, 0, 0, &mut SolveBudget::default());
+        assert!(got.is_finished());
+        let marginals = got.value.unwrap();
+        assert_eq!(marginals.samples, 0);
+        assert_eq!(marginals, Marginals::from_solutions(25, &solutions, 0));
+        assert_eq!(marginals.count, solutions.len() as u128);
+    }
+
+    #[test]
+    fn sampled_marginals_estimate_loose_board() {
+        let solver = Solver::new(5, vec![Color::White; 25], WinCondition::default());
+        let unchecked = (5..15).fold(Mask::EMPTY, |m, id| m | Mask::bit(id));
+        let solutions = solver.solve_masks_limit_with_checked_mask(Mask::EMPTY, unchecked, 0);
+        let exact = Marginals::from_solutions(25, &solutions, 0);
+
+        let got =
+            solver.solution_marginals(Mask::EMPTY, unchecked, 2000, 0, &mut SolveBudget::default());
+        let sampled = got.value.unwrap();
+        assert_eq!(sampled.samples, 2000);
+        assert_eq!(sampled.count, solutions.len() as u128);
+        for (id, (&est, &truth)) in sampled.cells.iter().zip(&exact.cells).enumerate() {
+            assert!((est - truth).abs() < 0.05, "cell {id}: {est} vs {truth}");
+        }
+        assert_eq!(sampled.cells[5], 0.0);
+        assert_eq!(sampled.cells[14], 0.0);
+    }
+
+    #[test]
+    fn samples_are_solutions() {
+        let solver = Solver::new(5, vec![Color::White; 25], WinCondition::default());
+        let unchecked = Mask::bit(6) | Mask::bit(12);
+        let (_, unchecked) = solver.normalize_masks(Mask::EMPTY, unchecked);
+        let mut budget = SolveBudget::default();
+        let mut meter = BudgetMeter::new(&mut budget);
+        let table = solver
+            .sample_table(Mask::EMPTY, unchecked, 0, &mut meter)
+            .expect("unlimited");
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        for _ in 0..50 {
+            let s = table.sample(&mut rng);
+            assert_eq!(
+                solver.solve_masks_limit_with_checked_mask(s, Mask::full(25) & !s, 0),
+                vec![s]
+            );
+        }
+    }
+
+    #[test]
+    fn state_limit_gives_up() {
+        let solver = Solver::new(6, vec![Color::White; 36], WinCondition::default());
+        let got =
+            solver.solution_marginals(Mask::EMPTY, Mask::EMPTY, 10, 4, &mut SolveBudget::default());
+        assert_eq!(got.value, None);
+        assert_eq!(got.exhausted, Some(BudgetStop::States));
+    }
+}
@@ -1,10 +1,39 @@
-use crate::colors::Color;
-use crate::masks::{Mask, CELL_COUNT, GRID_SIZE};
+use crate::colors::{Color, CountColor, COLOR_COUNT};
+use crate::i18n::{msg, Locale, Localize, Message, Param};
+use crate::masks::{Mask, Neighborhood, MAX_GRID_SIZE};
+use crate::win::WinCondition;
 use serde::Serialize;
 
+mod backbone;
+mod budget;
+mod cdcl;
+mod conflict;
+mod count_chain;
+mod deductions;
+mod exact_count;
+mod graded;
+mod justify;
+mod marginals;
+mod parity;
+mod repair;
+mod rules;
 mod state;
-
+mod technique;
+
+pub use backbone::Backbone;
+pub use budget::{BudgetStop, Budgeted, SolveBudget, SolveProgress};
+pub use conflict::{conflicting_clues, ConflictCore, ConflictRule, ConflictingClue};
+pub use deductions::{DeducedCell, DeductionDepth};
+pub use graded::{GradedHint, HintTier, MAX_HINT_LEVEL};
+pub use marginals::Marginals;
+pub use repair::{Repair, MAX_REPAIR_MOVES};
+pub use technique::{technique_catalog, Technique, TechniqueCount, TechniqueInfo, TechniqueTier};
+use budget::BudgetMeter;
+use count_chain::{CountClass, CountVar};
 use state::SolverState;
+pub(crate) use rules::{clue_colors, registry, rule_for, Filled};
+use rules::{CellRule, Deduce};
+pub(crate) use technique::{hardest_technique, technique_histogram};
 
 #[inline]
 fn cell_id(row: usize, col: usize, size: usize) -> usize {
@@ -12,93 +41,63 @@ fn cell_id(row: usize, col: usize, size: usize) -> usize {
 }
 
 #[derive(Clone, Debug)]
-struct RuleSet {
+pub(crate) struct RuleSet {
     size: usize,
 
-    black_cells: Vec<usize>,
-    blue_cells: Vec<usize>,
-    red_cells: Vec<usize>,
-    green_cells: Vec<usize>,
-    yellow_cells: Vec<usize>,
-    purple_cells: Vec<usize>,
-    orange_cells: Vec<usize>,
-    cyan_cells: Vec<usize>,
+    /// 各颜色的线索格，按颜色编码索引（见 [`RuleSet::cells`]）。
+    cells: Vec<Vec<usize>>,
+    /// 本身必须勾选的格子（黑格等，见 `CellRule::fixes_checked`）。
+    given_cells: Vec<usize>,
+    /// 每一轮传播依次执行的阶段（见 `rules::propagation_stages`）。
+    stages: Vec<RuleType>,
 
     decision_order: Vec<usize>,
 
-    neighbors8: Vec<Vec<usize>>,
-    neighbors4: Vec<Vec<usize>>,
+    /// 各邻域下每格的邻居，按 `Neighborhood::index` 索引（见 [`RuleSet::neighbors`]）。
+    neighborhoods: Vec<Vec<Vec<usize>>>,
 
     diag_down_cells: Vec<Vec<usize>>,
     diag_up_cells: Vec<Vec<usize>>,
+
+    win: WinCondition,
+    /// 胜利条件下的全部候选连线（见 `WinCondition::segments`）。
+    win_segments: Vec<Vec<usize>>,
+
+    /// 绿/黄格串起来的计数等价类（只含三个及以上变量的类，见 `count_chain`）。
+    count_classes: Vec<CountClass>,
 }
 
 impl RuleSet {
-    fn new(size: usize, colors: Vec<Color>) -> Self {
-        assert!((1..=32).contains(&size), "size 必须在 1..=32，得到：{size}");
+    fn new(size: usize, colors: Vec<Color>, win: WinCondition) -> Self {
+        assert!(
+            (1..=MAX_GRID_SIZE).contains(&size),
+            "size 必须在 1..={MAX_GRID_SIZE}，得到：{size}"
+        );
         assert_eq!(colors.len(), size * size, "colors 长度必须为 size*size");
 
         let cell_count = size * size;
         let diag_count = size * 2 - 1;
 
-        let mut black_cells = Vec::new();
-        let mut blue_cells = Vec::new();
-        let mut red_cells = Vec::new();
-        let mut green_cells = Vec::new();
-        let mut yellow_cells = Vec::new();
-        let mut purple_cells = Vec::new();
-        let mut orange_cells = Vec::new();
-        let mut cyan_cells = Vec::new();
-
-        for id in 0..cell_count {
-            match colors[id] {
-                Color::Black => black_cells.push(id),
-                Color::Blue => blue_cells.push(id),
-                Color::Red => red_cells.push(id),
-                Color::Green => green_cells.push(id),
-                Color::Yellow => yellow_cells.push(id),
-                Color::Purple => purple_cells.push(id),
-                Color::Orange => orange_cells.push(id),
-                Color::Cyan => cyan_cells.push(id),
-                _ => {}
+        let mut cells = vec![Vec::new(); COLOR_COUNT];
+        let mut given_cells = Vec::new();
+        for (id, &color) in colors.iter().enumerate() {
+            let Some(rule) = rule_for(color) else {
+                continue;
+            };
+            cells[color.to_u8() as usize].push(id);
+            if rule.fixes_checked() {
+                given_cells.push(id);
             }
         }
 
-        let mut neighbors8 = vec![Vec::new(); cell_count];
-        let mut neighbors4 = vec![Vec::new(); cell_count];
-
-        for row in 0..size {
-            for col in 0..size {
-                let id = cell_id(row, col, size);
-
-                for dr in -1i32..=1 {
-                    for dc in -1i32..=1 {
-                        if dr == 0 && dc == 0 {
-                            continue;
-                        }
-                        let nr = row as i32 + dr;
-                        let nc = col as i32 + dc;
-                        if nr < 0 || nc < 0 || nr >= size as i32 || nc >= size as i32 {
-                            continue;
-                        }
-                        neighbors8[id].push(cell_id(nr as usize, nc as usize, size));
-                    }
-                }
-
-                if row > 0 {
-                    neighbors4[id].push(cell_id(row - 1, col, size));
-                }
-                if row + 1 < size {
-                    neighbors4[id].push(cell_id(row + 1, col, size));
-                }
-                if col > 0 {
-                    neighbors4[id].push(cell_id(row, col - 1, size));
-                }
-                if col + 1 < size {
-                    neighbors4[id].push(cell_id(row, col + 1, size));
-                }
-            }
-        }
+        let neighborhoods: Vec<Vec<Vec<usize>>> = Neighborhood::ALL
+            .iter()
+            .map(|&hood| {
+                (0..cell_count)
+                    .map(|id| hood.cells(id / size, id % size, size).collect())
+                    .collect()
+            })
+            .collect();
 
         let mut diag_down_cells = vec![Vec::new(); diag_count];
         let mut diag_up_cells = vec![Vec::new(); diag_count];
@@ -111,40 +110,72 @@ impl RuleSet {
         }
 
         let mut decision_order: Vec<usize> = (0..cell_count)
-            .filter(|&id| colors[id] != Color::Black)
+            .filter(|id| given_cells.binary_search(id).is_err())
             .collect();
         decision_order.sort_by_key(|&id| {
-            let neighbor_degree = neighbors8[id].len() as i32;
-            let color_weight = match colors[id] {
-                Color::Blue => 100,
-                Color::Green => 90,
-                Color::Yellow => 80,
-                Color::Cyan => 70,
-                Color::Red => 60,
-                Color::Purple => 50,
-                Color::Orange => 50,
-                _ => 10,
-            };
+            let neighbor_degree = neighborhoods[Neighborhood::King.index()][id].len() as i32;
+            let color_weight = rule_for(colors[id]).map_or(10, |rule| rule.decision_weight());
             -(color_weight + neighbor_degree)
         });
+        let count_classes = count_chain::build_count_classes(
+            size,
+            &cells[Color::Green.to_u8() as usize],
+            &cells[Color::Yellow.to_u8() as usize],
+        );
+
+        // 棋盘上没有的颜色不必占一个传播阶段。
+        let stages = rules::propagation_stages()
+            .into_iter()
+            .filter(|stage| match stage {
+                RuleType::Cell(color) => !cells[color.to_u8() as usize].is_empty(),
+                _ => true,
+            })
+            .collect();
 
         Self {
             size,
-            black_cells,
-            blue_cells,
-            red_cells,
-            green_cells,
-            yellow_cells,
-            purple_cells,
-            orange_cells,
-            cyan_cells,
+            cells,
+            given_cells,
+            stages,
             decision_order,
-            neighbors8,
-            neighbors4,
+            neighborhoods,
             diag_down_cells,
             diag_up_cells,
+            win,
+            win_segments: win.segments(size),
+            count_classes,
         }
     }
+
+    /// 某种颜色的全部线索格（row-major）。
+    fn cells(&self, color: Color) -> &[usize] {
+        &self.cells[color.to_u8() as usize]
+    }
+
+    /// `cell` 在邻域 `hood` 下的邻居。
+    fn neighbors(&self, hood: Neighborhood, cell: usize) -> &[usize] {
+        &self.neighborhoods[hood.index()][cell]
+    }
+
+    /// 全部紫/橙格（含变体邻域）：`(格子, 是否要求奇数, 邻域)`，按邻域分组、组内先紫后橙。
+    fn parity_clues(&self) -> impl Iterator<Item = (usize, bool, Neighborhood)> + '_ {
+        Neighborhood::ALL.into_iter().flat_map(move |hood| {
+            [(CountColor::Purple, true), (CountColor::Orange, false)]
+                .into_iter()
+                .flat_map(move |(base, odd)| {
+                    self.cells(Color::counted(base, hood))
+                        .iter()
+                        .map(move |&id| (id, odd, hood))
+                })
+        })
+    }
+
+    /// `cell` 处的紫/橙格（若是）：是否要求奇数与所看的邻域。
+    fn parity_clue(&self, cell: usize) -> Option<(bool, Neighborhood)> {
+        self.parity_clues()
+            .find(|&(id, _, _)| id == cell)
+            .map(|(_, odd, hood)| (odd, hood))
+    }
 }
 
 #[derive(Clone, Debug, Default)]
@@ -176,7 +207,6 @@ pub(crate) struct SolveStats {
 #[derive(Clone, Debug, Default)]
 pub(crate) struct HumanDifficultyAnalysis {
     pub solved: bool,
-    pub exhausted_budget: bool,
 
     pub variable_cells: u32,
     pub initial_unknown_after_logic: u32,
@@ -194,6 +224,8 @@ pub(crate) struct HumanDifficultyAnalysis {
     pub max_logic_burst_size: u32,
     pub logic_rule_trigger_counts: std::collections::HashMap<RuleType, u64>,
     pub logic_first_trigger_counts: std::collections::HashMap<RuleType, u64>,
+    /// 各技巧推出的格子数（主路径传播）；每次单步反证另计一次 `SingleAssumptionContradiction`。
+    pub technique_counts: std::collections::HashMap<Technique, u64>,
 
     /// 反证（假设某格取值）走到矛盾所消耗的传播开销。
     pub contradiction_propagate_rounds: u64,
@@ -230,13 +262,12 @@ enum AssignReason {
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub(crate) enum RuleType {
-    Green,
-    Yellow,
-    Red,
-    Blue,
-    Purple,
-    Orange,
-    Cyan,
+    /// 单个线索格的颜色规则（见 `rules` 模块的注册表）。
+    Cell(Color),
+    /// 紫/橙格奇偶方程联立（GF(2) 消元，见 `parity` 模块）。
+    Parity,
+    /// 绿/黄格串起来的计数相等链（见 `count_chain` 模块）。
+    CountChain,
     FiveInRow,
 }
 
@@ -251,9 +282,14 @@ trait SolveObserver {
     fn on_rule_trigger(&mut self, _rule: RuleType, _is_first: bool) {}
     fn on_rule_enter(&mut self, _rule: RuleType) {}
     fn on_rule_focus(&mut self, _rule: RuleType, _cells: &[usize]) {}
+    /// 规则在 `on_rule_focus` 之后细分所用的技巧；不调用时按 [`Technique::of_rule`] 归类。
+    fn on_technique(&mut self, _technique: Technique) {}
     fn on_rule_exit(&mut self, _rule: RuleType) {}
     fn on_cell_assignment(&mut self, _cell: usize, _is_checked: bool, _reason: AssignReason) {}
-    fn on_backtrack(&mut self, _from_depth: u32, _to_depth: u32) {}
+    /// 搜索在每个节点（以及第二个分支之前）询问是否中止；返回 true 时整棵搜索树立即收束。
+    fn should_abort(&mut self) -> bool {
+        false
+    }
 }
 
 impl SolveObserver for () {}
@@ -264,6 +300,8 @@ struct PropagationObserver {
     assignments_propagated: u64,
     rule_trigger_counts: std::collections::HashMap<RuleType, u64>,
     first_trigger_counts: std::collections::HashMap<RuleType, u64>,
+    current_technique: Option<Technique>,
+    technique_counts: std::collections::HashMap<Technique, u64>,
 }
 
 impl PropagationObserver {
@@ -277,6 +315,9 @@ impl PropagationObserver {
         for (&rule, &count) in &self.first_trigger_counts {
             *analysis.logic_first_trigger_counts.entry(rule).or_insert(0) += count;
         }
+        for (&technique, &count) in &self.technique_counts {
+            *analysis.technique_counts.entry(technique).or_insert(0) += count;
+        }
     }
 
     fn merge_into_contradiction(&self, analysis: &mut HumanDifficultyAnalysis) {
@@ -305,6 +346,24 @@ impl SolveObserver for PropagationObserver {
             *self.first_trigger_counts.entry(rule).or_insert(0) += 1;
         }
     }
+
+    fn on_rule_focus(&mut self, rule: RuleType, _cells: &[usize]) {
+        self.current_technique = Technique::of_rule(rule);
+    }
+
+    fn on_technique(&mut self, technique: Technique) {
+        self.current_technique = Some(technique);
+    }
+
+    fn on_rule_exit(&mut self, _rule: RuleType) {
+        self.current_technique = None;
+    }
+
+    fn on_cell_assignment(&mut self, _cell: usize, _is_checked: bool, reason: AssignReason) {
+        if let (AssignReason::Propagate, Some(technique)) = (reason, self.current_technique) {
+            *self.technique_counts.entry(technique).or_insert(0) += 1;
+        }
+    }
 }
 
 impl SolveObserver for SolveStats {
@@ -358,14 +417,6 @@ impl SolveObserver for SolveStats {
             *self.first_trigger_counts.entry(rule).or_insert(0) += 1;
         }
     }
-
-    fn on_backtrack(&mut self, from_depth: u32, to_depth: u32) {
-        let distance = from_depth.saturating_sub(to_depth);
-        self.backtrack_distances.push(distance);
-        // 更新平均回溯距离
-        let total_distances: u32 = self.backtrack_distances.iter().sum();
-        self.avg_backtrack_distance = total_distances as f64 / self.backtrack_distances.len() as f64;
-    }
 }
 
 fn try_set_checked<O: SolveObserver>(
@@ -443,7 +494,7 @@ fn try_set_unchecked_id<O: SolveObserver>(
 /// 说明：
 /// - 黑格（Color::Black）强制勾选，不作为变量参与决策；
 /// - 其余格子为变量：可勾选或不勾选；
-/// - 解的判定：满足所有颜色规则且至少形成一条“五连线”（连续 5 个勾选，四个方向）。
+/// - 解的判定：满足所有颜色规则且满足胜利条件（`WinCondition`，默认“五连线”：连续 5 个勾选，四个方向）。
 pub struct Solver {
     rules: RuleSet,
 }
@@ -452,7 +503,10 @@ pub struct Solver {
 #[serde(rename_all = "snake_case")]
 pub enum HintAction {
     Check,
+    /// 撤销玩家已有的勾选。
     Uncheck,
+    /// 把未知格标记为排除（推出“必须不勾选”）。
+    Exclude,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -472,6 +526,8 @@ pub enum HintStatus {
     Forced,
     /// 从某个可行解中抽取的一步（不保证唯一/必然）。
     Suggested,
+    /// 预算耗尽（或被取消），未能判断当前状态是否可解。
+    Exhausted,
 }
 
 #[derive(Debug, Clone, Copy, Serialize)]
@@ -503,50 +559,106 @@ pub struct HintReason {
     /// 详细推导链（用于“简洁/详细”切换）。
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub steps: Vec<HintReasonStep>,
+    /// 推出结论所用的技巧（仅 `Propagate` / `Contradiction`）。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub technique: Option<Technique>,
+    /// 无解时的冲突解释：互相冲突的极小勾选子集及牵涉的规则（仅 `Repair`）。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conflict: Option<ConflictCore>,
 }
 
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct HintReasonStep {
     pub title: String,
+    /// `title` 的消息键与参数（见 `i18n`）。
+    pub title_key: Message,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub rule_id: Option<&'static str>,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub cells: Vec<usize>,
 }
 
+impl HintReasonStep {
+    fn new(title: Message, rule_id: Option<&'static str>, cells: Vec<usize>) -> Self {
+        Self {
+            title: title.zh(),
+            title_key: title,
+            rule_id,
+            cells,
+        }
+    }
+}
+
+impl Localize for HintReasonStep {
+    fn localize(&mut self, locale: Locale) {
+        self.title = self.title_key.render(locale);
+    }
+}
+
+impl Localize for HintReason {
+    fn localize(&mut self, locale: Locale) {
+        self.steps.localize(locale);
+        self.conflict.localize(locale);
+    }
+}
+
 fn rule_type_to_rule_id(rule: RuleType) -> &'static str {
     match rule {
-        RuleType::Green => "green",
-        RuleType::Yellow => "yellow",
-        RuleType::Red => "red",
-        RuleType::Blue => "blue",
-        RuleType::Purple => "purple",
-        RuleType::Orange => "orange",
-        RuleType::Cyan => "cyan",
+        RuleType::Cell(color) => color.rule_id().expect("传播阶段只含已登记的颜色"),
+        // 联立的约束可能同时含紫/橙格，提示中按参与组合的格子颜色另行细分。
+        RuleType::Parity => "purple",
+        RuleType::CountChain => "green",
         RuleType::FiveInRow => "bingo",
     }
 }
 
-fn rule_type_to_title(rule: RuleType) -> &'static str {
+fn rule_type_to_title(rule: RuleType) -> Message {
     match rule {
-        RuleType::Green => "绿格（行列计数相等）",
-        RuleType::Yellow => "黄格（对角线计数相等）",
-        RuleType::Red => "红格（八邻至少一格勾选）",
-        RuleType::Blue => "蓝格（八邻最多两格勾选）",
-        RuleType::Purple => "紫格（八邻勾选数为奇数）",
-        RuleType::Orange => "橙格（八邻勾选数为偶数）",
-        RuleType::Cyan => "青格（四邻约束）",
-        RuleType::FiveInRow => "五连线（Bingo）",
+        RuleType::Cell(color) => {
+            rule_for(color).map_or_else(|| msg!("rule.generic"), |r| r.title())
+        }
+        RuleType::Parity => msg!("rule.parity"),
+        RuleType::CountChain => msg!("rule.count_chain"),
+        RuleType::FiveInRow => msg!("rule.five_in_row"),
+    }
+}
+
+/// 推导链里的一个结论，如“(2,3) 不勾选”。
+fn cell_assignment(size: usize, cell: usize, checked: bool) -> Message {
+    let (row, col) = (cell / size + 1, cell % size + 1);
+    if checked {
+        msg!("cell.checked", row = row, col = col)
+    } else {
+        msg!("cell.unchecked", row = row, col = col)
+    }
+}
+
+/// 在判断出“当前是否可解”之前预算就已耗尽时的提示。
+fn exhausted_hint(stop: BudgetStop) -> HintResult {
+    let message = match stop {
+        BudgetStop::Cancelled => msg!("hint.exhausted.cancelled"),
+        BudgetStop::Time => msg!("hint.exhausted.time"),
+        BudgetStop::Nodes | BudgetStop::States => msg!("hint.exhausted.budget"),
+    };
+    HintResult {
+        exhausted: Some(stop),
+        ..HintResult::new(HintStatus::Exhausted, message, None, None)
     }
 }
 
-#[derive(Default)]
+#[derive(Clone, Default)]
 struct HintExplainObserver {
     current_rule: Option<RuleType>,
     current_focus: Vec<usize>,
+    current_technique: Option<Technique>,
     // 仅记录传播阶段的“首次赋值原因”，用于解释强制结论。
     propagate_assignments: std::collections::HashMap<usize, (Option<RuleType>, Vec<usize>, bool)>,
+    /// 每个格子首次赋值（任意来源）的先后序号：规则实例只依赖比结论更早确定的格子，
+    /// 据此可在事后还原论证图（见 `justify` 模块）。
+    assigned_at: std::collections::HashMap<usize, u32>,
+    /// 传播结论所用的技巧（与 `propagate_assignments` 同步，只记录第一次）。
+    propagate_techniques: std::collections::HashMap<usize, Technique>,
 }
 
 impl HintExplainObserver {
@@ -555,25 +667,38 @@ impl HintExplainObserver {
             .get(&cell)
             .map(|(r, focus, is_checked)| (*r, focus, *is_checked))
     }
+
+    fn technique_of(&self, cell: usize) -> Option<Technique> {
+        self.propagate_techniques.get(&cell).copied()
+    }
 }
 
 impl SolveObserver for HintExplainObserver {
     fn on_rule_enter(&mut self, rule: RuleType) {
         self.current_rule = Some(rule);
         self.current_focus.clear();
+        self.current_technique = None;
     }
 
     fn on_rule_focus(&mut self, rule: RuleType, cells: &[usize]) {
         self.current_rule = Some(rule);
         self.current_focus = cells.to_vec();
+        self.current_technique = Technique::of_rule(rule);
+    }
+
+    fn on_technique(&mut self, technique: Technique) {
+        self.current_technique = Some(technique);
     }
 
     fn on_rule_exit(&mut self, _rule: RuleType) {
         self.current_rule = None;
         self.current_focus.clear();
+        self.current_technique = None;
     }
 
     fn on_cell_assignment(&mut self, cell: usize, is_checked: bool, reason: AssignReason) {
+        let next = self.assigned_at.len() as u32;
+        self.assigned_at.entry(cell).or_insert(next);
         if !matches!(reason, AssignReason::Propagate) {
             return;
         }
@@ -581,421 +706,620 @@ impl SolveObserver for HintExplainObserver {
         self.propagate_assignments
             .entry(cell)
             .or_insert((self.current_rule, self.current_focus.clone(), is_checked));
+        if let Some(technique) = self.current_technique {
+            self.propagate_techniques.entry(cell).or_insert(technique);
+        }
     }
 }
 
+/// 会话（增量求解）用的状态：传播到不动点后的赋值，以及传播解释记录。
+#[derive(Clone)]
+pub(crate) struct TracedState {
+    state: SolverState,
+    obs: HintExplainObserver,
+    /// 按从头构造的顺序重放传播得到的解释记录：首次提示时建立，之后的提示直接复用。
+    ///
+    /// 增量传播的不动点与从头构造相同，但格子的“首次赋值原因”取决于赋值顺序，
+    /// 提示的理由以这份记录为准，才能与无状态接口一致。
+    explain: Option<HintExplainObserver>,
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct HintResult {
     pub status: HintStatus,
     pub message: String,
+    /// `message` 的消息键与参数（见 `i18n`）。
+    #[serde(rename = "messageKey")]
+    pub message_key: Message,
     #[serde(rename = "move")]
     pub mv: Option<HintMove>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub reason: Option<HintReason>,
+    /// 预算耗尽的原因；None 表示在预算内完成。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exhausted: Option<BudgetStop>,
+}
+
+impl HintResult {
+    fn new(
+        status: HintStatus,
+        message: Message,
+        mv: Option<HintMove>,
+        reason: Option<HintReason>,
+    ) -> Self {
+        Self {
+            status,
+            message: message.zh(),
+            message_key: message,
+            mv,
+            reason,
+            exhausted: None,
+        }
+    }
+}
+
+impl Localize for HintResult {
+    fn localize(&mut self, locale: Locale) {
+        self.message = self.message_key.render(locale);
+        self.reason.localize(locale);
+    }
 }
 
 impl Solver {
-    pub fn new(size: usize, colors: Vec<Color>) -> Self {
+    pub fn new(size: usize, colors: Vec<Color>, win: WinCondition) -> Self {
         Self {
-            rules: RuleSet::new(size, colors),
+            rules: RuleSet::new(size, colors, win),
         }
     }
 
+    /// 不限预算的 [`Solver::hint_next_with_budget`]。
+    #[cfg(test)]
+    pub fn hint_next(&self, checked_mask: Mask, unchecked_mask: Mask) -> HintResult {
+        self.hint_next_with_budget(checked_mask, unchecked_mask, &mut SolveBudget::default())
+    }
+
     /// 提示下一步（面向 UI 的“分层提示”）：
     ///
-    /// - 将 `checked_mask` 视为“已确认勾选”的集合，其余格子仍视为未知；
+    /// - 将 `checked_mask` 视为“已确认勾选”的集合，`unchecked_mask` 视为“已确认不勾选”（玩家的排除标记），其余格子仍视为未知；
     /// - 优先返回传播得到的强制结论，其次返回反证（传播矛盾）得到的强制结论；
     /// - 若没有强制结论，则从任意一个可行解中抽取一步作为“建议”。
     ///
     /// 备注：
-    /// - 若 `checked_mask` 导致无解，会尝试给出“撤销某个勾选”的修复建议（非强制）。
-    pub fn hint_next(&self, checked_mask: Mask) -> HintResult {
-        let cell_count = self.rules.size * self.rules.size;
-        let valid_mask = if cell_count == 64 { u64::MAX } else { (1u64 << cell_count) - 1 };
-        let checked_mask = checked_mask & valid_mask;
-
-        let Some((state, solution, hint_obs)) = self.solve_one_with_checked_mask_with_hint_trace(checked_mask) else {
-            // 无解：尝试找一个“撤销某个勾选后可行”的建议（用于把用户从死路拉回来）。
-            for &cell in &self.rules.decision_order {
-                let bit = 1u64 << cell;
-                if (checked_mask & bit) == 0 {
-                    continue;
-                }
-
-                let relaxed = checked_mask & !bit;
-                if self.solve_one_with_checked_mask(relaxed).is_some() {
-                    return HintResult {
-                        status: HintStatus::Suggested,
-                        message: format!(
-                            "当前勾选无法补全成解。建议先取消勾选：({},{})",
-                            (cell / self.rules.size) + 1,
-                            (cell % self.rules.size) + 1
-                        ),
-                        mv: Some(HintMove {
-                            cell,
-                            action: HintAction::Uncheck,
-                            forced: false,
-                        }),
-                        reason: Some(HintReason {
-                            kind: HintReasonKind::Repair,
-                            rule_id: None,
-                            affected_cells: vec![cell],
-                            secondary_cells: vec![],
-                            steps: vec![
-                                HintReasonStep {
-                                    title: "当前勾选已无解：建议先回到“可解状态”再继续推理。".to_string(),
-                                    rule_id: None,
-                                    cells: vec![],
-                                },
-                                HintReasonStep {
-                                    title: format!(
-                                        "尝试取消勾选：({},{})（可恢复到可解状态）",
-                                        (cell / self.rules.size) + 1,
-                                        (cell % self.rules.size) + 1
-                                    ),
-                                    rule_id: None,
-                                    cells: vec![cell],
-                                },
-                            ],
-                        }),
-                    };
-                }
-            }
-
-            return HintResult {
-                status: HintStatus::NoSolution,
-                message: "当前勾选无法补全成解：请尝试撤销部分勾选后再求提示。".to_string(),
-                mv: None,
-                reason: None,
-            };
+    /// - 同时出现在两个 mask 中的格子以 `checked_mask` 为准；
+    /// - 若当前状态导致无解，会在保留排除标记的前提下给出“撤销最少的勾选”的修复建议（非强制，见 `minimal_repair`）；
+    /// - 搜索与修复尝试共享同一份 `budget`：在判断出“当前是否可解”之前耗尽时返回 `HintStatus::Exhausted`，
+    ///   修复尝试中途耗尽时返回不带修复建议的 `NoSolution`，两者都会带上 `exhausted`。
+    pub fn hint_next_with_budget(
+        &self,
+        checked_mask: Mask,
+        unchecked_mask: Mask,
+        budget: &mut SolveBudget,
+    ) -> HintResult {
+        let (checked_mask, unchecked_mask) = self.normalize_masks(checked_mask, unchecked_mask);
+        let mut meter = BudgetMeter::new(budget);
+        let traced =
+            self.solve_one_with_masks_with_hint_trace(checked_mask, unchecked_mask, &mut meter);
+        if let Some(stop) = meter.stopped() {
+            return exhausted_hint(stop);
+        }
+        self.hint_from_trace(
+            checked_mask,
+            unchecked_mask,
+            traced.as_ref().map(|(state, solution, obs)| (state, *solution, obs)),
+            &[],
+            &mut meter,
+        )
+    }
+
+    /// `hint_next` 的主体。
+    ///
+    /// `traced` 为“传播到不动点后的状态 + 任意一个解 + 传播解释记录”，当前赋值无解时为 None；
+    /// 两个 mask 需已规范化（见 `normalize_masks`）；`history` 为玩家的操作历史（用于修复建议，可为空）。
+    fn hint_from_trace(
+        &self,
+        checked_mask: Mask,
+        unchecked_mask: Mask,
+        traced: Option<(&SolverState, Mask, &HintExplainObserver)>,
+        history: &[usize],
+        meter: &mut BudgetMeter,
+    ) -> HintResult {
+        let Some((state, solution, hint_obs)) = traced else {
+            return self.repair_hint(checked_mask, unchecked_mask, history, meter);
         };
 
         // 1) 传播阶段已经推出的强制“必须勾选”优先返回（最直观）。
         for &cell in &self.rules.decision_order {
-            let bit = 1u64 << cell;
-            if (checked_mask & bit) == 0 && state.is_checked_id(cell) {
-                let mut rule_id = None;
-                let mut secondary = vec![];
-                let mut steps = vec![];
-                if let Some((rule, focus, _is_checked)) = hint_obs.propagate_info(cell) {
-                    if let Some(rule) = rule {
-                        rule_id = Some(rule_type_to_rule_id(rule));
-                        secondary = focus.clone();
-                        steps.push(HintReasonStep {
-                            title: format!("推导：{} 触发了传播。", rule_type_to_title(rule)),
-                            rule_id,
-                            cells: secondary.clone(),
-                        });
-                    }
-                }
-                steps.push(HintReasonStep {
-                    title: format!(
-                        "结论：({},{}) 必须勾选。",
-                        (cell / self.rules.size) + 1,
-                        (cell % self.rules.size) + 1
-                    ),
-                    rule_id: None,
-                    cells: vec![cell],
-                });
-                return HintResult {
-                    status: HintStatus::Forced,
-                    message: format!(
-                        "根据当前信息可推出：({},{}) 必须勾选。",
-                        (cell / self.rules.size) + 1,
-                        (cell % self.rules.size) + 1
-                    ),
-                    mv: Some(HintMove {
-                        cell,
-                        action: HintAction::Check,
-                        forced: true,
-                    }),
-                    reason: Some(HintReason {
-                        kind: HintReasonKind::Propagate,
-                        rule_id,
-                        affected_cells: vec![cell],
-                        secondary_cells: secondary,
-                        steps,
-                    }),
-                };
+            if !checked_mask.contains(cell) && state.is_checked_id(cell) {
+                return self.propagate_hint(hint_obs, cell, true);
             }
         }
 
-        // 2) 传播推出的“必须不勾选”也属于强制，但对 UI 来说不如“勾哪一格”直观。
+        // 2) 传播推出的“必须不勾选”：只返回玩家尚未排除的格子，避免提示停留在已完成的结论上。
         //
-        // 注意：当前 UI 仅记录“已勾选”(checked_mask)，没有“已确定不勾选”的单独状态。
-        // 因此如果目标格本来就未勾选，返回“必须不勾选”会变成无效操作，导致用户反复点提示却卡在同一句话。
-        // 这里仅返回对当前状态“可执行/可改变”的 uncheck：目标格必须是当前已勾选。
+        // 备注：玩家已勾选的格子若被推出“不勾选”，传播阶段就已矛盾（走上面的修复分支），
+        // 因此这里的目标格总是“未知”格，对应 UI 上的“标记为排除”。
         for &cell in &self.rules.decision_order {
-            if !state.is_unchecked_id(cell) {
-                continue;
-            }
-            let bit = 1u64 << cell;
-            if (checked_mask & bit) == 0 {
-                continue;
+            if !unchecked_mask.contains(cell) && state.is_unchecked_id(cell) {
+                return self.propagate_hint(hint_obs, cell, false);
             }
+        }
 
-            let mut rule_id = None;
-            let mut secondary = vec![];
-            let mut steps = vec![];
-            if let Some((rule, focus, _is_checked)) = hint_obs.propagate_info(cell) {
-                if let Some(rule) = rule {
-                    rule_id = Some(rule_type_to_rule_id(rule));
-                    secondary = focus.clone();
-                    steps.push(HintReasonStep {
-                        title: format!("推导：{} 触发了传播。", rule_type_to_title(rule)),
-                        rule_id,
-                        cells: secondary.clone(),
-                    });
-                }
-            }
-            steps.push(HintReasonStep {
-                title: format!(
-                    "结论：({},{}) 必须不勾选。",
-                    (cell / self.rules.size) + 1,
-                    (cell % self.rules.size) + 1
-                ),
-                rule_id: None,
-                cells: vec![cell],
-            });
-            return HintResult {
-                status: HintStatus::Forced,
-                message: format!(
-                    "根据当前信息可推出：({},{}) 必须不勾选。",
-                    (cell / self.rules.size) + 1,
-                    (cell % self.rules.size) + 1
-                ),
-                mv: Some(HintMove {
+        // 3) 反证（传播矛盾）推出的强制一步：依然是“安全提示”。
+        //
+        // 反证只会在未知格上找到结论，因此两个方向都一定会改变当前状态。
+        // 每次假设传播记一个节点；耗尽时不再退回下面的“建议”，以免把没找完的反证当成“没有强制步”。
+        let forced = self.find_forced_by_contradiction(state, meter);
+        if let Some(stop) = meter.stopped() {
+            return exhausted_hint(stop);
+        }
+        if let Some((cell, forced_checked, _obs, _scarcity)) = forced {
+            let (row, col) = (cell / self.rules.size + 1, cell % self.rules.size + 1);
+            // 重放反证：假设相反取值，只保留导致矛盾的那部分推导。
+            let refutation = self.refutation(state, cell, !forced_checked, meter);
+            if let Some(stop) = meter.stopped() {
+                return exhausted_hint(stop);
+            }
+            let mut steps = match &refutation {
+                Some(r) => r.steps.clone(),
+                None => vec![HintReasonStep::new(
+                    msg!("hint.contradiction.assume"),
+                    None,
+                    vec![cell],
+                )],
+            };
+            let (conclusion, message) = if forced_checked {
+                (
+                    msg!("hint.contradiction.conclude.check", row = row, col = col),
+                    msg!("hint.contradiction.check", row = row, col = col),
+                )
+            } else {
+                (
+                    msg!("hint.contradiction.conclude.uncheck", row = row, col = col),
+                    msg!("hint.contradiction.uncheck", row = row, col = col),
+                )
+            };
+            steps.push(HintReasonStep::new(conclusion, None, vec![cell]));
+            return HintResult::new(
+                HintStatus::Forced,
+                message,
+                Some(HintMove {
                     cell,
-                    action: HintAction::Uncheck,
+                    action: if forced_checked {
+                        HintAction::Check
+                    } else {
+                        HintAction::Exclude
+                    },
                     forced: true,
                 }),
-                reason: Some(HintReason {
-                    kind: HintReasonKind::Propagate,
-                    rule_id,
+                Some(HintReason {
+                    kind: HintReasonKind::Contradiction,
+                    rule_id: refutation.as_ref().and_then(|r| r.rule_id),
                     affected_cells: vec![cell],
-                    secondary_cells: secondary,
+                    secondary_cells: refutation
+                        .map(|r| r.cells.into_iter().filter(|&c| c != cell).collect())
+                        .unwrap_or_default(),
                     steps,
+                    technique: Some(Technique::SingleAssumptionContradiction),
+                    conflict: None,
                 }),
-            };
-        }
-
-        // 3) 反证（传播矛盾）推出的强制一步：依然是“安全提示”。
-        let mut budget = 10_000u32;
-        if let Some((cell, forced_checked, _obs, _scarcity)) =
-            self.find_forced_by_contradiction(&state, &mut budget)
-        {
-            let bit = 1u64 << cell;
-
-            // 同上：只返回“会改变当前状态”的一步，避免提示卡死。
-            if forced_checked {
-                if (checked_mask & bit) == 0 {
-                    return HintResult {
-                        status: HintStatus::Forced,
-                        message: format!(
-                            "通过反证可推出：({},{}) 必须{}。",
-                            (cell / self.rules.size) + 1,
-                            (cell % self.rules.size) + 1,
-                            "勾选"
-                        ),
-                        mv: Some(HintMove {
-                            cell,
-                            action: HintAction::Check,
-                            forced: true,
-                        }),
-                        reason: Some(HintReason {
-                            kind: HintReasonKind::Contradiction,
-                            rule_id: None,
-                            affected_cells: vec![cell],
-                            secondary_cells: vec![],
-                            steps: vec![HintReasonStep {
-                                title: "反证：如果选择相反操作，会在传播中走到矛盾/无解。".to_string(),
-                                rule_id: None,
-                                cells: vec![cell],
-                            }],
-                        }),
-                    };
-                }
-            } else if (checked_mask & bit) != 0 {
-                return HintResult {
-                    status: HintStatus::Forced,
-                    message: format!(
-                        "通过反证可推出：({},{}) 必须{}。",
-                        (cell / self.rules.size) + 1,
-                        (cell % self.rules.size) + 1,
-                        "不勾选"
-                    ),
-                    mv: Some(HintMove {
-                        cell,
-                        action: HintAction::Uncheck,
-                        forced: true,
-                    }),
-                    reason: Some(HintReason {
-                        kind: HintReasonKind::Contradiction,
-                        rule_id: None,
-                        affected_cells: vec![cell],
-                        secondary_cells: vec![],
-                        steps: vec![HintReasonStep {
-                            title: "反证：如果选择相反操作，会在传播中走到矛盾/无解。".to_string(),
-                            rule_id: None,
-                            cells: vec![cell],
-                        }],
-                    }),
-                };
-            }
+            );
         }
 
         // 4) 没有强制结论：从一个可行解中抽取一步（建议）。
         for &cell in &self.rules.decision_order {
-            let bit = 1u64 << cell;
-            if (checked_mask & bit) == 0 && (solution & bit) != 0 {
+            if !checked_mask.contains(cell) && solution.contains(cell) {
                 let line = self.best_bingo_line_for_cell(solution, cell);
-                return HintResult {
-                    status: HintStatus::Suggested,
-                    message: format!(
-                        "给出一个可能有帮助的下一步：尝试勾选 ({},{})。",
-                        (cell / self.rules.size) + 1,
-                        (cell % self.rules.size) + 1
-                    ),
-                    mv: Some(HintMove {
+                let (row, col) = (cell / self.rules.size + 1, cell % self.rules.size + 1);
+                return HintResult::new(
+                    HintStatus::Suggested,
+                    msg!("hint.suggest", row = row, col = col),
+                    Some(HintMove {
                         cell,
                         action: HintAction::Check,
                         forced: false,
                     }),
-                    reason: Some(HintReason {
+                    Some(HintReason {
                         kind: HintReasonKind::Suggest,
                         rule_id: Some("bingo"),
                         affected_cells: vec![cell],
                         secondary_cells: line.clone(),
                         steps: vec![
-                            HintReasonStep {
-                                title: "目标：达成至少一条五连线（Bingo）。".to_string(),
-                                rule_id: Some("bingo"),
-                                cells: line,
-                            },
-                            HintReasonStep {
-                                title: format!(
-                                    "建议：先勾选 ({},{}) 作为推进方向（不保证唯一）。",
-                                    (cell / self.rules.size) + 1,
-                                    (cell % self.rules.size) + 1
+                            HintReasonStep::new(
+                                msg!(
+                                    "hint.suggest.goal",
+                                    line = self.rules.win.title(self.rules.size)
                                 ),
-                                rule_id: None,
-                                cells: vec![cell],
-                            },
+                                Some("bingo"),
+                                line,
+                            ),
+                            HintReasonStep::new(
+                                msg!("hint.suggest.step", row = row, col = col),
+                                None,
+                                vec![cell],
+                            ),
                         ],
+                        technique: None,
+                        conflict: None,
                     }),
-                };
+                );
             }
         }
 
+        HintResult::new(HintStatus::Suggested, msg!("hint.no_step"), None, None)
+    }
+
+    /// 当前勾选无解时的提示：优先给出改动最少的修复（见 `repair` 模块，只撤销勾选），
+    /// 同样少时优先撤销 `history` 中最近的操作；找不到时给出互相冲突的极小勾选子集。
+    fn repair_hint(
+        &self,
+        checked_mask: Mask,
+        unchecked_mask: Mask,
+        history: &[usize],
+        meter: &mut BudgetMeter,
+    ) -> HintResult {
+        let size = self.rules.size;
+        let repair = self.minimal_repair_from(checked_mask, unchecked_mask, history, false, meter);
+        if let Some(first) = repair.moves.first() {
+            let cells: Vec<Message> = repair
+                .moves
+                .iter()
+                .map(|m| {
+                    msg!(
+                        "cell.coord",
+                        row = m.cell / size + 1,
+                        col = m.cell % size + 1
+                    )
+                })
+                .collect();
+            let message = if cells.len() == 1 {
+                msg!("hint.repair.one", cell = cells[0].clone())
+            } else {
+                msg!(
+                    "hint.repair.many",
+                    count = cells.len(),
+                    cells = Param::list("sep.enum", cells)
+                )
+            };
+            return HintResult::new(
+                HintStatus::Suggested,
+                message,
+                Some(first.clone()),
+                Some(repair.reason),
+            );
+        }
+
+        let checks = repair.reason.conflict.as_ref().map_or(0, |c| c.checks.len());
+        let message = if meter.stopped().is_some() {
+            msg!("hint.repair.exhausted")
+        } else if checks == 0 {
+            msg!("hint.repair.exclusions")
+        } else {
+            msg!(
+                "hint.repair.too_many",
+                count = checks,
+                max = MAX_REPAIR_MOVES
+            )
+        };
         HintResult {
-            status: HintStatus::Suggested,
-            message: "当前勾选已能补全成解，但暂无可直接给出的“一步提示”。".to_string(),
-            mv: None,
-            reason: None,
+            exhausted: meter.stopped(),
+            ..HintResult::new(HintStatus::NoSolution, message, None, Some(repair.reason))
+        }
+    }
+
+    /// 传播阶段推出的强制结论（勾选/不勾选）对应的提示。
+    fn propagate_hint(&self, hint_obs: &HintExplainObserver, cell: usize, is_checked: bool) -> HintResult {
+        let size = self.rules.size;
+        let (row, col) = (cell / size + 1, cell % size + 1);
+        let mut rule_id = None;
+        let mut secondary = vec![];
+        // 详细解释：先列出从前提到目标之前的推导链，再解释目标格本身。
+        let mut steps = self.justification_steps(hint_obs, cell);
+        match hint_obs.propagate_info(cell) {
+            Some((Some(RuleType::Parity), sources, _is_checked)) => {
+                // 奇偶联立：逐条列出参与组合的紫/橙格，再说明相加后只剩目标格。
+                secondary = sources.clone();
+                for &source in sources {
+                    let (r, c) = (source / size + 1, source % size + 1);
+                    let (odd, hood) = self
+                        .rules
+                        .parity_clue(source)
+                        .expect("奇偶联立的来源必是紫/橙格");
+                    let (id, title) = if odd {
+                        let title = msg!("hint.parity.odd", row = r, col = c, hood = hood.noun());
+                        ("purple", title)
+                    } else {
+                        let title = msg!("hint.parity.even", row = r, col = c, hood = hood.noun());
+                        ("orange", title)
+                    };
+                    rule_id.get_or_insert(id);
+                    let mut cells = vec![source];
+                    cells.extend_from_slice(self.rules.neighbors(hood, source));
+                    steps.push(HintReasonStep::new(title, Some(id), cells));
+                }
+                steps.push(HintReasonStep::new(
+                    msg!(
+                        "hint.parity.combine",
+                        count = sources.len(),
+                        row = row,
+                        col = col
+                    ),
+                    rule_id,
+                    sources.clone(),
+                ));
+            }
+            Some((Some(RuleType::CountChain), links, _is_checked)) => {
+                // 计数链：逐个列出绿/黄格给出的等式，再说明它们串成的等价类。
+                secondary = links.clone();
+                for &link in links {
+                    let (r, c) = (link / size, link % size);
+                    let (id, a, b) = if self.rules.cells(Color::Green).contains(&link) {
+                        ("green", CountVar::Row(r), CountVar::Col(c))
+                    } else {
+                        let down = CountVar::DiagDown(r + (size - 1) - c);
+                        ("yellow", down, CountVar::DiagUp(r + c))
+                    };
+                    rule_id.get_or_insert(id);
+                    let mut cells = a.cells(&self.rules);
+                    cells.extend(b.cells(&self.rules).into_iter().filter(|c| *c != link));
+                    steps.push(HintReasonStep::new(
+                        msg!(
+                            "hint.count_chain.equation",
+                            row = r + 1,
+                            col = c + 1,
+                            a = a.title(size),
+                            b = b.title(size)
+                        ),
+                        Some(id),
+                        cells,
+                    ));
+                }
+                if let Some(class) = self.rules.count_classes.iter().find(|c| &c.links == links) {
+                    let chain = class.vars.iter().map(|v| v.title(size)).collect();
+                    steps.push(HintReasonStep::new(
+                        msg!(
+                            "hint.count_chain.chain",
+                            vars = Param::list("sep.equals", chain)
+                        ),
+                        rule_id,
+                        links.clone(),
+                    ));
+                }
+            }
+            Some((Some(RuleType::FiveInRow), candidates, _is_checked)) => {
+                rule_id = Some("bingo");
+                secondary = candidates.clone();
+                steps.push(HintReasonStep::new(
+                    msg!(
+                        "hint.bingo.forced",
+                        line = self.rules.win.title(size),
+                        row = row,
+                        col = col
+                    ),
+                    rule_id,
+                    secondary.clone(),
+                ));
+            }
+            Some((Some(rule), focus, _is_checked)) => {
+                rule_id = Some(rule_type_to_rule_id(rule));
+                secondary = focus.clone();
+                steps.push(HintReasonStep::new(
+                    msg!("hint.propagate.rule", rule = rule_type_to_title(rule)),
+                    rule_id,
+                    secondary.clone(),
+                ));
+            }
+            _ => {}
         }
+        let (conclusion, message) = if is_checked {
+            (
+                msg!("hint.conclude.check", row = row, col = col),
+                msg!("hint.forced.check", row = row, col = col),
+            )
+        } else {
+            (
+                msg!("hint.conclude.uncheck", row = row, col = col),
+                msg!("hint.forced.uncheck", row = row, col = col),
+            )
+        };
+        steps.push(HintReasonStep::new(conclusion, None, vec![cell]));
+        HintResult::new(
+            HintStatus::Forced,
+            message,
+            Some(HintMove {
+                cell,
+                action: if is_checked {
+                    HintAction::Check
+                } else {
+                    HintAction::Exclude
+                },
+                forced: true,
+            }),
+            Some(HintReason {
+                kind: HintReasonKind::Propagate,
+                rule_id,
+                affected_cells: vec![cell],
+                secondary_cells: secondary,
+                steps,
+                technique: hint_obs.technique_of(cell),
+                conflict: None,
+            }),
+        )
+    }
+
+    /// 截断到棋盘范围，并让 `checked_mask` 优先于 `unchecked_mask`。
+    fn normalize_masks(&self, checked_mask: Mask, unchecked_mask: Mask) -> (Mask, Mask) {
+        let valid_mask = Mask::full(self.rules.size * self.rules.size);
+        let checked_mask = checked_mask & valid_mask;
+        (checked_mask, unchecked_mask & valid_mask & !checked_mask)
     }
 
-    fn solve_one_with_checked_mask(&self, checked_mask: Mask) -> Option<(SolverState, Mask)> {
+    /// 构造初始状态：黑格强制勾选，`checked_mask` 为已确认勾选，`unchecked_mask` 为已确认不勾选。
+    ///
+    /// 若初始赋值本身矛盾（如把黑格标记为不勾选），返回 None。
+    fn initial_state_with_masks<O: SolveObserver>(
+        &self,
+        checked_mask: Mask,
+        unchecked_mask: Mask,
+        obs: &mut O,
+    ) -> Option<SolverState> {
         let mut state = SolverState::new(self.rules.size);
 
-        for &id in &self.rules.black_cells {
-            if state.set_checked_id(id).is_err() {
+        for &id in &self.rules.given_cells {
+            if !try_set_checked_id(&mut state, id, AssignReason::Initial, obs) {
                 return None;
             }
         }
 
-        // 将 checked_mask 视为“已确认勾选”，其余保持未知。
         for &cell in &self.rules.decision_order {
-            let bit = 1u64 << cell;
-            if (checked_mask & bit) != 0 {
-                if state.set_checked_id(cell).is_err() {
-                    return None;
-                }
+            if checked_mask.contains(cell)
+                && !try_set_checked_id(&mut state, cell, AssignReason::Initial, obs)
+            {
+                return None;
             }
         }
 
-        // 先传播到不再变化，尽早发现矛盾，并为“强制提示”准备信息。
+        for cell in unchecked_mask.iter_ones() {
+            if !try_set_unchecked_id(&mut state, cell, AssignReason::Initial, obs) {
+                return None;
+            }
+        }
+
+        Some(state)
+    }
+
+    fn solve_one_with_masks(
+        &self,
+        checked_mask: Mask,
+        unchecked_mask: Mask,
+        meter: &mut BudgetMeter,
+    ) -> Option<(SolverState, Mask)> {
         let mut obs = ();
+        let mut state = self.initial_state_with_masks(checked_mask, unchecked_mask, &mut obs)?;
+
+        // 先传播到不再变化，尽早发现矛盾，并为“强制提示”准备信息。
         if !self.propagate_to_fixpoint(&mut state, &mut obs) {
             return None;
         }
 
         let mut out = Vec::new();
-        self.search(state.clone(), 1, &mut out, 0, &mut obs);
+        self.search(state.clone(), 1, &mut out, 0, meter);
         out.first().copied().map(|mask| (state, mask))
     }
 
-    fn solve_one_with_checked_mask_with_hint_trace(
+    fn solve_one_with_masks_with_hint_trace(
         &self,
         checked_mask: Mask,
+        unchecked_mask: Mask,
+        meter: &mut BudgetMeter,
     ) -> Option<(SolverState, Mask, HintExplainObserver)> {
-        let mut state = SolverState::new(self.rules.size);
         let mut obs = HintExplainObserver::default();
-
-        for &id in &self.rules.black_cells {
-            if !try_set_checked_id(&mut state, id, AssignReason::Initial, &mut obs) {
-                return None;
-            }
-        }
-
-        // 将 checked_mask 视为“已确认勾选”，其余保持未知。
-        for &cell in &self.rules.decision_order {
-            let bit = 1u64 << cell;
-            if (checked_mask & bit) != 0 {
-                if !try_set_checked_id(&mut state, cell, AssignReason::Initial, &mut obs) {
-                    return None;
-                }
-            }
-        }
+        let mut state = self.initial_state_with_masks(checked_mask, unchecked_mask, &mut obs)?;
 
         if !self.propagate_to_fixpoint(&mut state, &mut obs) {
             return None;
         }
 
         let mut out = Vec::new();
-        let mut search_obs = ();
-        self.search(state.clone(), 1, &mut out, 0, &mut search_obs);
+        self.search(state.clone(), 1, &mut out, 0, meter);
         let solution = out.first().copied()?;
         Some((state, solution, obs))
     }
 
-    fn best_bingo_line_for_cell(&self, solution: Mask, cell: usize) -> Vec<usize> {
-        let size = self.rules.size;
-        if size != GRID_SIZE {
-            // 当前产品约束是 5x5；若未来扩展 size，可在这里做更通用的线段枚举。
-            return vec![cell];
+    /// 会话的起点：只含黑格并传播到不动点；黑格本身导致矛盾时返回 None。
+    pub(crate) fn traced_initial(&self) -> Option<TracedState> {
+        let mut obs = HintExplainObserver::default();
+        let mut state = self.initial_state_with_masks(Mask::EMPTY, Mask::EMPTY, &mut obs)?;
+        if !self.propagate_to_fixpoint(&mut state, &mut obs) {
+            return None;
         }
+        Some(TracedState {
+            state,
+            obs,
+            explain: None,
+        })
+    }
 
-        let row = cell / size;
-        let col = cell % size;
+    /// 在 `base` 上追加一个玩家赋值，并只从该格出发增量传播；矛盾时返回 None。
+    ///
+    /// 传播到不动点的结果与从头构造相同，只是省去了重建与重复传播。
+    pub(crate) fn traced_assign(
+        &self,
+        base: &TracedState,
+        cell: usize,
+        checked: bool,
+    ) -> Option<TracedState> {
+        let mut next = base.clone();
+        next.explain = None;
+        let ok = if checked {
+            try_set_checked_id(&mut next.state, cell, AssignReason::Initial, &mut next.obs)
+        } else {
+            try_set_unchecked_id(&mut next.state, cell, AssignReason::Initial, &mut next.obs)
+        };
+        if !ok || !self.propagate_to_fixpoint(&mut next.state, &mut next.obs) {
+            return None;
+        }
+        Some(next)
+    }
 
-        let mut candidates: Vec<Vec<usize>> = Vec::new();
+    /// 基于会话状态在预算内给出提示。
+    ///
+    /// `solution` 为快照上的解缓存（None 为尚未计算）：需要时在同一份预算内搜索一个解，
+    /// 只有在预算内完成时才写回缓存；`history` 为会话中的操作顺序（用于修复建议）。
+    /// 理由取自快照上缓存的重放记录（见 [`TracedState`]），每个快照只重放一次。
+    pub(crate) fn traced_hint(
+        &self,
+        checked_mask: Mask,
+        unchecked_mask: Mask,
+        traced: Option<&mut TracedState>,
+        solution: &mut Option<Option<Mask>>,
+        history: &[usize],
+        budget: &mut SolveBudget,
+    ) -> HintResult {
+        let mut meter = BudgetMeter::new(budget);
+        let found = match (traced.as_deref(), *solution) {
+            (_, Some(cached)) => cached,
+            (None, None) => None,
+            (Some(t), None) => {
+                let mut out = Vec::new();
+                self.search(t.state.clone(), 1, &mut out, 0, &mut meter);
+                if let Some(stop) = meter.stopped() {
+                    return exhausted_hint(stop);
+                }
+                let found = out.first().copied();
+                *solution = Some(found);
+                found
+            }
+        };
+        let explained = traced.zip(found).map(|(t, solution)| {
+            let obs = t
+                .explain
+                .get_or_insert_with(|| self.replay_explain(checked_mask, unchecked_mask));
+            (&t.state, solution, &*obs)
+        });
+        self.hint_from_trace(checked_mask, unchecked_mask, explained, history, &mut meter)
+    }
 
-        // 行
-        candidates.push((0..size).map(|c| cell_id(row, c, size)).collect());
-        // 列
-        candidates.push((0..size).map(|r| cell_id(r, col, size)).collect());
-        // 主对角线
-        if row == col {
-            candidates.push((0..size).map(|k| cell_id(k, k, size)).collect());
-        }
-        // 副对角线
-        if row + col == size - 1 {
-            candidates.push((0..size).map(|k| cell_id(k, size - 1 - k, size)).collect());
+    /// 按从头构造的顺序（见 `initial_state_with_masks`）重放传播，只取解释记录。
+    fn replay_explain(&self, checked_mask: Mask, unchecked_mask: Mask) -> HintExplainObserver {
+        let mut obs = HintExplainObserver::default();
+        if let Some(mut state) = self.initial_state_with_masks(checked_mask, unchecked_mask, &mut obs)
+        {
+            self.propagate_to_fixpoint(&mut state, &mut obs);
         }
+        obs
+    }
 
+    fn best_bingo_line_for_cell(&self, solution: Mask, cell: usize) -> Vec<usize> {
         let mut best = vec![cell];
         let mut best_score = -1i32;
-        for line in candidates {
-            let mut score = 0i32;
-            for &id in &line {
-                if (solution & (1u64 << id)) != 0 {
-                    score += 1;
-                }
+        for segment in &self.rules.win_segments {
+            if !segment.contains(&cell) {
+                continue;
             }
+            let score = segment.iter().filter(|&&id| solution.contains(id)).count() as i32;
             if score > best_score {
                 best_score = score;
-                best = line;
+                best = segment.clone();
             }
         }
         best
@@ -1004,7 +1328,7 @@ impl Solver {
     /// 求解并返回最多 `limit` 个解（limit=0 视为不限制）。
     pub fn solve_masks_limit(&self, limit: usize) -> Vec<Mask> {
         let mut state = SolverState::new(self.rules.size);
-        for &id in &self.rules.black_cells {
+        for &id in &self.rules.given_cells {
             // 黑格固定勾选，如果冲突交给传播阶段判定即可。
             let _ = state.set_checked_id(id);
         }
@@ -1021,7 +1345,7 @@ impl Solver {
         stats: &mut SolveStats,
     ) -> Vec<Mask> {
         let mut state = SolverState::new(self.rules.size);
-        for &id in &self.rules.black_cells {
+        for &id in &self.rules.given_cells {
             if !try_set_checked_id(&mut state, id, AssignReason::Initial, stats) {
                 return Vec::new();
             }
@@ -1032,85 +1356,94 @@ impl Solver {
         out
     }
 
-    /// 在“已有部分勾选”的前提下求解，并返回最多 `limit` 个解（limit=0 视为不限制）。
-    ///
-    /// 约定：
-    /// - `checked_mask` 仅表达“已确认勾选”的集合；未置位的格子仍视为“未知”，而不是“不勾选”；
-    /// - 黑格（Color::Black）依然强制勾选，与 `checked_mask` 无关；
-    /// - 若初始赋值或传播阶段产生矛盾，直接返回空解集。
-    pub fn solve_masks_limit_with_checked_mask(&self, checked_mask: Mask, limit: usize) -> Vec<Mask> {
-        let cell_count = self.rules.size * self.rules.size;
-        let valid_mask = if cell_count == 64 { u64::MAX } else { (1u64 << cell_count) - 1 };
-        let checked_mask = checked_mask & valid_mask;
-
-        let mut state = SolverState::new(self.rules.size);
-        for &id in &self.rules.black_cells {
-            // 黑格固定勾选；如果冲突交给传播阶段判定即可
-            let _ = state.set_checked_id(id);
-        }
-
-        // 将 checked_mask 视为“已确认勾选”，其余保持未知
-        for &cell in &self.rules.decision_order {
-            let bit = 1u64 << cell;
-            if (checked_mask & bit) != 0 {
-                if state.set_checked_id(cell).is_err() {
-                    return Vec::new();
-                }
-            }
-        }
+    /// 按时间顺序回溯的参照搜索：约定同 [`Solver::solve_masks_cdcl_with_budget`]，预算按搜索节点计。
+    #[cfg(test)]
+    pub fn solve_masks_limit_with_budget(
+        &self,
+        checked_mask: Mask,
+        unchecked_mask: Mask,
+        limit: usize,
+        budget: &mut SolveBudget,
+    ) -> Budgeted<Vec<Mask>> {
+        let (checked_mask, unchecked_mask) = self.normalize_masks(checked_mask, unchecked_mask);
+        let mut meter = BudgetMeter::new(budget);
 
         let mut obs = ();
+        let Some(mut state) = self.initial_state_with_masks(checked_mask, unchecked_mask, &mut obs)
+        else {
+            return meter.finish(Vec::new());
+        };
         if !self.propagate_to_fixpoint(&mut state, &mut obs) {
-            return Vec::new();
+            return meter.finish(Vec::new());
         }
 
         let mut out = Vec::new();
-        self.search(state, limit, &mut out, 0, &mut obs);
-        out
+        self.search(state, limit, &mut out, 0, &mut meter);
+        meter.finish(out)
     }
 
-    pub(crate) fn analyze_human_difficulty(&self) -> HumanDifficultyAnalysis {
-        const BUDGET: u32 = 50_000;
+    /// 不限预算的 `solve_masks_limit_with_budget`。
+    #[cfg(test)]
+    pub fn solve_masks_limit_with_checked_mask(
+        &self,
+        checked_mask: Mask,
+        unchecked_mask: Mask,
+        limit: usize,
+    ) -> Vec<Mask> {
+        self.solve_masks_limit_with_budget(
+            checked_mask,
+            unchecked_mask,
+            limit,
+            &mut SolveBudget::default(),
+        )
+        .value
+    }
 
+    /// 人类逻辑难度分析；每次传播（含反证、试探）记一个节点，耗尽时 `value` 为已分析部分的统计。
+    pub(crate) fn analyze_human_difficulty(
+        &self,
+        budget: &mut SolveBudget,
+    ) -> Budgeted<HumanDifficultyAnalysis> {
+        let mut meter = BudgetMeter::new(budget);
         let mut state = SolverState::new(self.rules.size);
-        for &id in &self.rules.black_cells {
+        for &id in &self.rules.given_cells {
             if state.set_checked_id(id).is_err() {
-                return HumanDifficultyAnalysis {
+                return meter.finish(HumanDifficultyAnalysis {
                     solved: false,
-                    exhausted_budget: false,
                     variable_cells: self.rules.decision_order.len() as u32,
                     ..HumanDifficultyAnalysis::default()
-                };
+                });
             }
         }
 
-        let mut analysis = HumanDifficultyAnalysis::default();
-        analysis.variable_cells = self.rules.decision_order.len() as u32;
+        let mut analysis = HumanDifficultyAnalysis {
+            variable_cells: self.rules.decision_order.len() as u32,
+            ..HumanDifficultyAnalysis::default()
+        };
 
-        let mut budget = BUDGET;
         if self
-            .propagate_logic_with_budget(&mut state, &mut budget, &mut analysis)
+            .propagate_logic_with_budget(&mut state, &mut meter, &mut analysis)
             .is_none()
         {
             analysis.solved = false;
-            analysis.exhausted_budget = budget == 0;
-            let (total, possible, guaranteed) = bingo_segment_stats(&state);
+            let (total, possible, guaranteed) = bingo_segment_stats(&state, &self.rules.win_segments);
             analysis.bingo_segments_total = total;
             analysis.bingo_segments_possible = possible;
             analysis.bingo_segments_guaranteed = guaranteed;
-            return analysis;
+            return meter.finish(analysis);
         }
         analysis.initial_unknown_after_logic = self.count_unknown_decision_cells(&state);
 
         // 只做“人类常用”的逻辑阶段：传播 + 反证推出强制。
-        loop {
-            let Some((cell, forced_checked, contradiction_obs, (unknown_cells, candidate_assumptions))) =
-                self.find_forced_by_contradiction(&state, &mut budget)
-            else {
-                break;
-            };
+        while let Some((cell, forced_checked, contradiction_obs, (unknown_cells, candidate_assumptions))) =
+            self.find_forced_by_contradiction(&state, &mut meter)
+        {
 
             analysis.forced_by_contradiction += 1;
+            *analysis
+                .technique_counts
+                .entry(Technique::SingleAssumptionContradiction)
+                .or_insert(0) += 1;
             contradiction_obs.merge_into_contradiction(&mut analysis);
 
             // “断档”稀缺度：可用入口越少，人类越容易卡住。
@@ -1139,7 +1472,7 @@ impl Solver {
             }
 
             if self
-                .propagate_logic_with_budget(&mut state, &mut budget, &mut analysis)
+                .propagate_logic_with_budget(&mut state, &mut meter, &mut analysis)
                 .is_none()
             {
                 break;
@@ -1151,7 +1484,7 @@ impl Solver {
         // - 真·自由题（怎么填都推不动，但也不太会卡）
         // - 断档题（只有极少数入口能开启下一段推导）
         if !state.is_fully_decided() {
-            let (total, candidates, max_burst) = self.probe_progress_candidates(&state, &mut budget);
+            let (total, candidates, max_burst) = self.probe_progress_candidates(&state, &mut meter);
             analysis.probe_total_assumptions = total;
             analysis.probe_candidate_assumptions = candidates;
             analysis.probe_max_burst_size = max_burst;
@@ -1161,14 +1494,13 @@ impl Solver {
         }
 
         analysis.solved = state.is_fully_decided();
-        analysis.exhausted_budget = budget == 0;
 
-        let (total, possible, guaranteed) = bingo_segment_stats(&state);
+        let (total, possible, guaranteed) = bingo_segment_stats(&state, &self.rules.win_segments);
         analysis.bingo_segments_total = total;
         analysis.bingo_segments_possible = possible;
         analysis.bingo_segments_guaranteed = guaranteed;
 
-        analysis
+        meter.finish(analysis)
     }
 
     fn count_unknown_decision_cells(&self, state: &SolverState) -> u32 {
@@ -1182,13 +1514,12 @@ impl Solver {
     fn propagate_logic_with_budget(
         &self,
         state: &mut SolverState,
-        budget: &mut u32,
+        meter: &mut BudgetMeter,
         analysis: &mut HumanDifficultyAnalysis,
     ) -> Option<()> {
-        if *budget == 0 {
+        if meter.tick() {
             return None;
         }
-        *budget -= 1;
 
         let mut obs = PropagationObserver::default();
         if !self.propagate_to_fixpoint(state, &mut obs) {
@@ -1207,7 +1538,7 @@ impl Solver {
     fn find_forced_by_contradiction(
         &self,
         state: &SolverState,
-        budget: &mut u32,
+        meter: &mut BudgetMeter,
     ) -> Option<(usize, bool, PropagationObserver, (u32, u32))> {
         // 统计“断档”稀缺度：在当前状态下，做一次“单步反证”共有多少可用入口？
         // - total_assumptions = 未知格子数 * 2
@@ -1221,17 +1552,20 @@ impl Solver {
             if !state.is_unknown_id(cell) {
                 continue;
             }
+            if meter.stopped().is_some() {
+                break;
+            }
             unknown_cells = unknown_cells.saturating_add(1);
 
             // 假设“不勾选” -> 若矛盾，则强制“勾选”。
-            if let Some(contradiction) = self.contradiction_proof(state, cell, false, budget) {
+            if let Some(contradiction) = self.contradiction_proof(state, cell, false, meter) {
                 candidate_assumptions = candidate_assumptions.saturating_add(1);
                 if first_found.is_none() {
                     first_found = Some((cell, true, contradiction));
                 }
             }
             // 假设“勾选” -> 若矛盾，则强制“不勾选”。
-            if let Some(contradiction) = self.contradiction_proof(state, cell, true, budget) {
+            if let Some(contradiction) = self.contradiction_proof(state, cell, true, meter) {
                 candidate_assumptions = candidate_assumptions.saturating_add(1);
                 if first_found.is_none() {
                     first_found = Some((cell, false, contradiction));
@@ -1249,7 +1583,7 @@ impl Solver {
         state: &SolverState,
         cell: usize,
         assume_checked: bool,
-        budget: &mut u32,
+        meter: &mut BudgetMeter,
     ) -> Option<PropagationObserver> {
         let mut fork = state.clone();
         let assign_res = if assume_checked {
@@ -1263,17 +1597,20 @@ impl Solver {
             return Some(PropagationObserver::default());
         }
 
-        if *budget == 0 {
+        if meter.tick() {
             return None;
         }
-        *budget -= 1;
 
         let mut obs = PropagationObserver::default();
         let ok = self.propagate_to_fixpoint(&mut fork, &mut obs);
         if ok { None } else { Some(obs) }
     }
 
-    fn probe_progress_candidates(&self, state: &SolverState, budget: &mut u32) -> (u32, u32, u32) {
+    fn probe_progress_candidates(
+        &self,
+        state: &SolverState,
+        meter: &mut BudgetMeter,
+    ) -> (u32, u32, u32) {
         let mut unknown_cells = 0u32;
         let mut candidate_assumptions = 0u32;
         let mut max_burst = 0u32;
@@ -1285,10 +1622,9 @@ impl Solver {
             unknown_cells = unknown_cells.saturating_add(1);
 
             for assume_checked in [false, true] {
-                if *budget == 0 {
+                if meter.tick() {
                     break;
                 }
-                *budget -= 1;
 
                 let mut fork = state.clone();
                 let assign_res = if assume_checked {
@@ -1332,6 +1668,9 @@ impl Solver {
 
         let mut state = state;
         obs.on_node(depth);
+        if obs.should_abort() {
+            return;
+        }
         if !self.propagate_to_fixpoint(&mut state, obs) {
             obs.on_dead_end(depth);
             return;
@@ -1359,7 +1698,7 @@ impl Solver {
             }
         }
 
-        if limit != 0 && out.len() >= limit {
+        if (limit != 0 && out.len() >= limit) || obs.should_abort() {
             return;
         }
 
@@ -1376,12 +1715,11 @@ impl Solver {
     }
 
     fn find_next_unknown_cell(&self, state: &SolverState) -> Option<usize> {
-        for &id in &self.rules.decision_order {
-            if state.is_unknown_id(id) {
-                return Some(id);
-            }
-        }
-        None
+        self.rules
+            .decision_order
+            .iter()
+            .copied()
+            .find(|&id| state.is_unknown_id(id))
     }
 
     fn propagate_to_fixpoint(&self, state: &mut SolverState, obs: &mut impl SolveObserver) -> bool {
@@ -1389,117 +1727,21 @@ impl Solver {
             let old = state.hash64();
             obs.on_propagate_round();
 
-            let mut first_triggered_rule: Option<RuleType> = None;
-
-            // 传播规则并记录第一个触发的规则
-            obs.on_rule_enter(RuleType::Green);
-            if !self.propagate_green(state, obs) {
-                return false;
-            }
-            obs.on_rule_exit(RuleType::Green);
-            let green_triggered = state.hash64() != old;
-            if green_triggered {
-                obs.on_rule_trigger(RuleType::Green, first_triggered_rule.is_none());
-                if first_triggered_rule.is_none() {
-                    first_triggered_rule = Some(RuleType::Green);
-                }
-            }
-
-            let green_hash = state.hash64();
-            obs.on_rule_enter(RuleType::Yellow);
-            if !self.propagate_yellow(state, obs) {
-                return false;
-            }
-            obs.on_rule_exit(RuleType::Yellow);
-            let yellow_triggered = state.hash64() != green_hash;
-            if yellow_triggered {
-                obs.on_rule_trigger(RuleType::Yellow, first_triggered_rule.is_none());
-                if first_triggered_rule.is_none() {
-                    first_triggered_rule = Some(RuleType::Yellow);
-                }
-            }
-
-            let yellow_hash = state.hash64();
-            obs.on_rule_enter(RuleType::Blue);
-            if !self.propagate_blue(state, obs) {
-                return false;
-            }
-            obs.on_rule_exit(RuleType::Blue);
-            let blue_triggered = state.hash64() != yellow_hash;
-            if blue_triggered {
-                obs.on_rule_trigger(RuleType::Blue, first_triggered_rule.is_none());
-                if first_triggered_rule.is_none() {
-                    first_triggered_rule = Some(RuleType::Blue);
-                }
-            }
-
-            let blue_hash = state.hash64();
-            obs.on_rule_enter(RuleType::Red);
-            if !self.propagate_red(state, obs) {
-                return false;
-            }
-            obs.on_rule_exit(RuleType::Red);
-            let red_triggered = state.hash64() != blue_hash;
-            if red_triggered {
-                obs.on_rule_trigger(RuleType::Red, first_triggered_rule.is_none());
-                if first_triggered_rule.is_none() {
-                    first_triggered_rule = Some(RuleType::Red);
-                }
-            }
-
-            let red_hash = state.hash64();
-            obs.on_rule_enter(RuleType::Purple);
-            if !self.propagate_purple(state, obs) {
-                return false;
-            }
-            obs.on_rule_exit(RuleType::Purple);
-            let purple_triggered = state.hash64() != red_hash;
-            if purple_triggered {
-                obs.on_rule_trigger(RuleType::Purple, first_triggered_rule.is_none());
-                if first_triggered_rule.is_none() {
-                    first_triggered_rule = Some(RuleType::Purple);
-                }
-            }
-
-            let purple_hash = state.hash64();
-            obs.on_rule_enter(RuleType::Orange);
-            if !self.propagate_orange(state, obs) {
-                return false;
-            }
-            obs.on_rule_exit(RuleType::Orange);
-            let orange_triggered = state.hash64() != purple_hash;
-            if orange_triggered {
-                obs.on_rule_trigger(RuleType::Orange, first_triggered_rule.is_none());
-                if first_triggered_rule.is_none() {
-                    first_triggered_rule = Some(RuleType::Orange);
+            // 依次执行各阶段，并记录这一轮第一个触发的阶段
+            let mut first_triggered = true;
+            for &stage in &self.rules.stages {
+                let before = state.hash64();
+                obs.on_rule_enter(stage);
+                if !self.propagate_stage(stage, state, obs) {
+                    return false;
                 }
-            }
-
-            let orange_hash = state.hash64();
-            obs.on_rule_enter(RuleType::Cyan);
-            if !self.propagate_cyan(state, obs) {
-                return false;
-            }
-            obs.on_rule_exit(RuleType::Cyan);
-            let cyan_triggered = state.hash64() != orange_hash;
-            if cyan_triggered {
-                obs.on_rule_trigger(RuleType::Cyan, first_triggered_rule.is_none());
-                if first_triggered_rule.is_none() {
-                    first_triggered_rule = Some(RuleType::Cyan);
+                obs.on_rule_exit(stage);
+                if state.hash64() != before {
+                    obs.on_rule_trigger(stage, first_triggered);
+                    first_triggered = false;
                 }
             }
 
-            let cyan_hash = state.hash64();
-            obs.on_rule_enter(RuleType::FiveInRow);
-            if !self.propagate_five_in_a_row_possible(state) {
-                return false;
-            }
-            obs.on_rule_exit(RuleType::FiveInRow);
-            let five_in_row_triggered = state.hash64() != cyan_hash;
-            if five_in_row_triggered {
-                obs.on_rule_trigger(RuleType::FiveInRow, first_triggered_rule.is_none());
-            }
-
             if state.hash64() == old {
                 break;
             }
@@ -1507,327 +1749,99 @@ impl Solver {
         true
     }
 
-    fn propagate_green(&self, state: &mut SolverState, obs: &mut impl SolveObserver) -> bool {
-        for &id in &self.rules.green_cells {
-            let row = id / self.rules.size;
-            let col = id % self.rules.size;
-            // 教学：绿格推导涉及“该行 + 该列”
-            let mut focus = Vec::with_capacity(self.rules.size * 2);
-            for c in 0..self.rules.size {
-                focus.push(cell_id(row, c, self.rules.size));
-            }
-            for r in 0..self.rules.size {
-                let cid = cell_id(r, col, self.rules.size);
-                if !focus.contains(&cid) {
-                    focus.push(cid);
-                }
-            }
-            obs.on_rule_focus(RuleType::Green, &focus);
-            let (r_min, r_max) = state.row_min_max(row);
-            let (c_min, c_max) = state.col_min_max(col);
-
-            if r_max < c_min || c_max < r_min {
-                return false;
-            }
-
-            // 情况 A：Row 的 Max == Col 的 Min
-            if r_max == c_min {
-                if !fill_row_unknowns_as(state, row, true, obs) {
-                    return false;
-                }
-                if !fill_col_unknowns_as(state, col, false, obs) {
-                    return false;
-                }
-            }
-
-            // 情况 B：Col 的 Max == Row 的 Min
-            if c_max == r_min {
-                if !fill_col_unknowns_as(state, col, true, obs) {
-                    return false;
-                }
-                if !fill_row_unknowns_as(state, row, false, obs) {
-                    return false;
-                }
-            }
+    fn propagate_stage(
+        &self,
+        stage: RuleType,
+        state: &mut SolverState,
+        obs: &mut impl SolveObserver,
+    ) -> bool {
+        match stage {
+            RuleType::Cell(color) => match rule_for(color) {
+                Some(rule) => self.propagate_cells(rule, state, obs),
+                None => true,
+            },
+            RuleType::CountChain => self.propagate_count_chains(state, obs),
+            RuleType::Parity => self.propagate_parity(state, obs),
+            RuleType::FiveInRow => self.propagate_bingo(state, obs),
         }
-        true
     }
 
-    fn propagate_yellow(&self, state: &mut SolverState, obs: &mut impl SolveObserver) -> bool {
-        for &id in &self.rules.yellow_cells {
-            let row = id / self.rules.size;
-            let col = id % self.rules.size;
-
-            let down_id = row + (self.rules.size - 1) - col;
-            let up_id = row + col;
-
-            let (d_min, d_max) = state.diag_down_min_max(down_id);
-            let (u_min, u_max) = state.diag_up_min_max(up_id);
-
-            // 教学：黄格推导涉及“交叉两条对角线”
-            let mut focus = Vec::new();
-            focus.extend_from_slice(&self.rules.diag_down_cells[down_id]);
-            for &cid in &self.rules.diag_up_cells[up_id] {
-                if !focus.contains(&cid) {
-                    focus.push(cid);
-                }
-            }
-            obs.on_rule_focus(RuleType::Yellow, &focus);
-
-            if d_max < u_min || u_max < d_min {
+    /// 逐个传播某种颜色的线索格；每个线索格先报告它的解释范围。
+    fn propagate_cells(
+        &self,
+        rule: &dyn CellRule,
+        state: &mut SolverState,
+        obs: &mut impl SolveObserver,
+    ) -> bool {
+        let stage = RuleType::Cell(rule.color());
+        let mut out = Deducer { state, obs };
+        for &id in self.rules.cells(rule.color()) {
+            out.obs.on_rule_focus(stage, &rule.focus(&self.rules, id));
+            if !rule.propagate(&self.rules, id, &mut out) {
                 return false;
             }
-
-            // 对角线推导与绿格一致（范围卡边界）
-            if d_max == u_min {
-                if !fill_cells_unknowns_as(state, &self.rules.diag_down_cells[down_id], true, obs) {
-                    return false;
-                }
-                if !fill_cells_unknowns_as(state, &self.rules.diag_up_cells[up_id], false, obs) {
-                    return false;
-                }
-            }
-            if u_max == d_min {
-                if !fill_cells_unknowns_as(state, &self.rules.diag_up_cells[up_id], true, obs) {
-                    return false;
-                }
-                if !fill_cells_unknowns_as(state, &self.rules.diag_down_cells[down_id], false, obs)
-                {
-                    return false;
-                }
-            }
         }
         true
     }
 
-    fn propagate_red(&self, state: &mut SolverState, obs: &mut impl SolveObserver) -> bool {
-        for &id in &self.rules.red_cells {
-            // 教学：红格推导范围 = 自身 + 八邻
-            let mut focus = Vec::with_capacity(1 + self.rules.neighbors8[id].len());
-            focus.push(id);
-            focus.extend_from_slice(&self.rules.neighbors8[id]);
-            obs.on_rule_focus(RuleType::Red, &focus);
-            let mut checked = 0usize;
-            let mut last_unknown: Option<usize> = None;
-            let mut unknown_count = 0usize;
-
-            for &n in &self.rules.neighbors8[id] {
-                if state.is_checked_id(n) {
-                    checked += 1;
-                } else if state.is_unknown_id(n) {
-                    unknown_count += 1;
-                    last_unknown = Some(n);
-                }
-            }
-
-            if checked > 0 {
+    /// 胜利条件传播：没有任何连线还能完成时矛盾；否则所有仍可能完成的连线的公共格必须勾选。
+    ///
+    /// 已有连线全部勾选时 Bingo 已达成，不再推导。
+    fn propagate_bingo(&self, state: &mut SolverState, obs: &mut impl SolveObserver) -> bool {
+        let mut common: Option<Mask> = None;
+        let mut candidates = Mask::EMPTY;
+        for segment in &self.rules.win_segments {
+            if segment.iter().any(|&id| state.is_unchecked_id(id)) {
                 continue;
             }
-            if unknown_count == 0 {
-                return false;
-            }
-            if unknown_count == 1 {
-                if !try_set_checked_id(
-                    state,
-                    last_unknown.expect("unknown_count==1"),
-                    AssignReason::Propagate,
-                    obs,
-                ) {
-                    return false;
-                }
+            if segment.iter().all(|&id| state.is_checked_id(id)) {
+                return true;
             }
+            let mask = segment.iter().fold(Mask::EMPTY, |m, &id| m | Mask::bit(id));
+            common = Some(common.map_or(mask, |c| c & mask));
+            candidates |= mask;
         }
-        true
-    }
-
-    fn propagate_blue(&self, state: &mut SolverState, obs: &mut impl SolveObserver) -> bool {
-        for &id in &self.rules.blue_cells {
-            // 教学：蓝格推导范围 = 自身 + 八邻
-            let mut focus = Vec::with_capacity(1 + self.rules.neighbors8[id].len());
-            focus.push(id);
-            focus.extend_from_slice(&self.rules.neighbors8[id]);
-            obs.on_rule_focus(RuleType::Blue, &focus);
-            let mut checked = 0usize;
-            let mut unknowns = Vec::new();
-            for &n in &self.rules.neighbors8[id] {
-                if state.is_checked_id(n) {
-                    checked += 1;
-                } else if state.is_unknown_id(n) {
-                    unknowns.push(n);
-                }
-            }
-
-            if checked > 2 {
-                return false;
-            }
+        let Some(common) = common else {
+            return false;
+        };
 
-            // 已经 2 个勾选 -> 剩余未知邻居全部必须不勾选
-            if checked == 2 {
-                for n in unknowns {
-                    if !try_set_unchecked_id(state, n, AssignReason::Propagate, obs) {
-                        return false;
-                    }
-                }
-            }
+        let forced: Vec<usize> = common.iter_ones().filter(|&id| state.is_unknown_id(id)).collect();
+        if forced.is_empty() {
+            return true;
         }
-        true
-    }
-
-    fn propagate_purple(&self, state: &mut SolverState, obs: &mut impl SolveObserver) -> bool {
-        for &id in &self.rules.purple_cells {
-            // 教学：紫格推导范围 = 自身 + 八邻
-            let mut focus = Vec::with_capacity(1 + self.rules.neighbors8[id].len());
-            focus.push(id);
-            focus.extend_from_slice(&self.rules.neighbors8[id]);
-            obs.on_rule_focus(RuleType::Purple, &focus);
-            let mut checked = 0usize;
-            let mut last_unknown: Option<usize> = None;
-            let mut unknown_count = 0usize;
-
-            for &n in &self.rules.neighbors8[id] {
-                if state.is_checked_id(n) {
-                    checked += 1;
-                } else if state.is_unknown_id(n) {
-                    unknown_count += 1;
-                    last_unknown = Some(n);
-                }
-            }
-
-            if unknown_count == 0 {
-                if checked % 2 != 1 {
-                    return false;
-                }
-                continue;
-            }
-
-            // 只剩 1 个未知时，可强制推导奇偶
-            if unknown_count == 1 {
-                let target = last_unknown.expect("unknown_count==1");
-                let should_check = checked % 2 == 0;
-                let ok = if should_check {
-                    try_set_checked_id(state, target, AssignReason::Propagate, obs)
-                } else {
-                    try_set_unchecked_id(state, target, AssignReason::Propagate, obs)
-                };
-                if !ok {
-                    return false;
-                }
+        // 教学：Bingo 推导范围 = 全部仍可能完成的连线
+        let focus: Vec<usize> = candidates.iter_ones().collect();
+        obs.on_rule_focus(RuleType::FiveInRow, &focus);
+        for id in forced {
+            if !try_set_checked_id(state, id, AssignReason::Propagate, obs) {
+                return false;
             }
         }
         true
     }
+}
 
-    fn propagate_orange(&self, state: &mut SolverState, obs: &mut impl SolveObserver) -> bool {
-        for &id in &self.rules.orange_cells {
-            // 教学：橙格推导范围 = 自身 + 八邻
-            let mut focus = Vec::with_capacity(1 + self.rules.neighbors8[id].len());
-            focus.push(id);
-            focus.extend_from_slice(&self.rules.neighbors8[id]);
-            obs.on_rule_focus(RuleType::Orange, &focus);
-            let mut checked = 0usize;
-            let mut last_unknown: Option<usize> = None;
-            let mut unknown_count = 0usize;
-
-            for &n in &self.rules.neighbors8[id] {
-                if state.is_checked_id(n) {
-                    checked += 1;
-                } else if state.is_unknown_id(n) {
-                    unknown_count += 1;
-                    last_unknown = Some(n);
-                }
-            }
-
-            if unknown_count == 0 {
-                if checked % 2 != 0 {
-                    return false;
-                }
-                continue;
-            }
+/// 求解器一侧的 [`Deduce`]：赋值记为传播结论并通知观察者。
+struct Deducer<'a, O> {
+    state: &'a mut SolverState,
+    obs: &'a mut O,
+}
 
-            // 只剩 1 个未知时，可强制推导奇偶
-            if unknown_count == 1 {
-                let target = last_unknown.expect("unknown_count==1");
-                let should_check = checked % 2 == 1;
-                let ok = if should_check {
-                    try_set_checked_id(state, target, AssignReason::Propagate, obs)
-                } else {
-                    try_set_unchecked_id(state, target, AssignReason::Propagate, obs)
-                };
-                if !ok {
-                    return false;
-                }
-            }
-        }
-        true
+impl<O: SolveObserver> Deduce for Deducer<'_, O> {
+    fn state(&self) -> &SolverState {
+        self.state
     }
 
-    fn propagate_cyan(&self, state: &mut SolverState, obs: &mut impl SolveObserver) -> bool {
-        for &id in &self.rules.cyan_cells {
-            // 教学：青格推导范围 = 自身 + 四邻
-            let mut focus = Vec::with_capacity(1 + self.rules.neighbors4[id].len());
-            focus.push(id);
-            focus.extend_from_slice(&self.rules.neighbors4[id]);
-            obs.on_rule_focus(RuleType::Cyan, &focus);
-            let (row, col) = (id / self.rules.size, id % self.rules.size);
-
-            // 未勾选：规则不生效
-            if state.is_unchecked(row, col) {
-                continue;
-            }
-
-            // 已勾选：四邻至少 1 个勾选（类似红格，但邻域为 4）
-            if state.is_checked(row, col) {
-                let mut checked = 0usize;
-                let mut last_unknown: Option<usize> = None;
-                let mut unknown_count = 0usize;
-
-                for &n in &self.rules.neighbors4[id] {
-                    if state.is_checked_id(n) {
-                        checked += 1;
-                    } else if state.is_unknown_id(n) {
-                        unknown_count += 1;
-                        last_unknown = Some(n);
-                    }
-                }
-
-                if checked > 0 {
-                    continue;
-                }
-                if unknown_count == 0 {
-                    return false;
-                }
-                if unknown_count == 1 {
-                    if !try_set_checked_id(
-                        state,
-                        last_unknown.expect("unknown_count==1"),
-                        AssignReason::Propagate,
-                        obs,
-                    ) {
-                        return false;
-                    }
-                }
-                continue;
-            }
-
-            // 未知：如果四邻全部“不勾选”，则该青格不能勾选（否则必矛盾）-> 强制不勾选
-            let mut all_neighbors_unchecked = true;
-            for &n in &self.rules.neighbors4[id] {
-                if !state.is_unchecked_id(n) {
-                    all_neighbors_unchecked = false;
-                    break;
-                }
-            }
-            if all_neighbors_unchecked {
-                if !try_set_unchecked(state, row, col, AssignReason::Propagate, obs) {
-                    return false;
-                }
-            }
+    fn assign(&mut self, cell: usize, checked: bool) -> bool {
+        if checked {
+            try_set_checked_id(self.state, cell, AssignReason::Propagate, self.obs)
+        } else {
+            try_set_unchecked_id(self.state, cell, AssignReason::Propagate, self.obs)
         }
-        true
     }
 
-    fn propagate_five_in_a_row_possible(&self, state: &SolverState) -> bool {
-        five_in_a_row_possible(state)
+    fn technique(&mut self, technique: Technique) {
+        self.obs.on_technique(technique);
     }
 }
 
@@ -1899,201 +1913,84 @@ fn fill_cells_unknowns_as(
     true
 }
 
-/// 五连线可行性剪枝：只要存在“长度为 5 的线段”不包含任何“已确定不勾选”的格子，就仍有希望达成目标。
-fn five_in_a_row_possible(state: &SolverState) -> bool {
-    let size = state.size();
-    let len = 5usize;
-    if size < len {
-        return false;
-    }
-
-    // 横向
-    for row in 0..size {
-        for start_col in 0..=size - len {
-            let mut ok = true;
-            for dc in 0..len {
-                if state.is_unchecked(row, start_col + dc) {
-                    ok = false;
-                    break;
-                }
-            }
-            if ok {
-                return true;
-            }
-        }
-    }
-
-    // 纵向
-    for col in 0..size {
-        for start_row in 0..=size - len {
-            let mut ok = true;
-            for dr in 0..len {
-                if state.is_unchecked(start_row + dr, col) {
-                    ok = false;
-                    break;
-                }
-            }
-            if ok {
-                return true;
-            }
-        }
-    }
-
-    // 主对角线方向（\）
-    for start_row in 0..=size - len {
-        for start_col in 0..=size - len {
-            let mut ok = true;
-            for d in 0..len {
-                if state.is_unchecked(start_row + d, start_col + d) {
-                    ok = false;
-                    break;
-                }
-            }
-            if ok {
-                return true;
-            }
-        }
-    }
-
-    // 副对角线方向（/）
-    for start_row in 0..=size - len {
-        for start_col in (len - 1)..size {
-            let mut ok = true;
-            for d in 0..len {
-                if state.is_unchecked(start_row + d, start_col - d) {
-                    ok = false;
-                    break;
-                }
-            }
-            if ok {
-                return true;
-            }
-        }
-    }
-
-    false
-}
-
-fn bingo_segment_stats(state: &SolverState) -> (u32, u32, u32) {
-    let size = state.size();
-    let len = 5usize;
-    if size < len {
-        return (0, 0, 0);
-    }
-
+/// 统计候选连线在当前状态下的情况：`(总数, 仍可达成, 已经达成)`。
+///
+/// “仍可达成”指连线上没有“已确定不勾选”的格子；“已经达成”指连线上的格子全部已勾选。
+fn bingo_segment_stats(state: &SolverState, segments: &[Vec<usize>]) -> (u32, u32, u32) {
     let mut total = 0u32;
     let mut possible = 0u32;
     let mut guaranteed = 0u32;
 
-    // 横向
-    for row in 0..size {
-        for start_col in 0..=size - len {
-            total += 1;
-            let mut ok = true;
-            let mut all_checked = true;
-            for dc in 0..len {
-                let col = start_col + dc;
-                if state.is_unchecked(row, col) {
-                    ok = false;
-                    break;
-                }
-                if !state.is_checked(row, col) {
-                    all_checked = false;
-                }
-            }
-            if ok {
-                possible += 1;
-                if all_checked {
-                    guaranteed += 1;
-                }
-            }
+    for segment in segments {
+        total += 1;
+        if segment.iter().any(|&id| state.is_unchecked_id(id)) {
+            continue;
         }
-    }
-
-    // 纵向
-    for col in 0..size {
-        for start_row in 0..=size - len {
-            total += 1;
-            let mut ok = true;
-            let mut all_checked = true;
-            for dr in 0..len {
-                let row = start_row + dr;
-                if state.is_unchecked(row, col) {
-                    ok = false;
-                    break;
-                }
-                if !state.is_checked(row, col) {
-                    all_checked = false;
-                }
-            }
-            if ok {
-                possible += 1;
-                if all_checked {
-                    guaranteed += 1;
-                }
-            }
+        possible += 1;
+        if segment.iter().all(|&id| state.is_checked_id(id)) {
+            guaranteed += 1;
         }
     }
 
-    // 主对角线方向（\）
-    for start_row in 0..=size - len {
-        for start_col in 0..=size - len {
-            total += 1;
-            let mut ok = true;
-            let mut all_checked = true;
-            for d in 0..len {
-                let row = start_row + d;
-                let col = start_col + d;
-                if state.is_unchecked(row, col) {
-                    ok = false;
-                    break;
-                }
-                if !state.is_checked(row, col) {
-                    all_checked = false;
-                }
-            }
-            if ok {
-                possible += 1;
-                if all_checked {
-                    guaranteed += 1;
-                }
-            }
-        }
-    }
+    (total, possible, guaranteed)
+}
 
-    // 副对角线方向（/）
-    for start_row in 0..=size - len {
-        for start_col in (len - 1)..size {
-            total += 1;
-            let mut ok = true;
-            let mut all_checked = true;
-            for d in 0..len {
-                let row = start_row + d;
-                let col = start_col - d;
-                if state.is_unchecked(row, col) {
-                    ok = false;
-                    break;
-                }
-                if !state.is_checked(row, col) {
-                    all_checked = false;
-                }
-            }
-            if ok {
-                possible += 1;
-                if all_checked {
-                    guaranteed += 1;
+/// 各求解模块测试共用的可复现随机布局。
+#[cfg(test)]
+pub(crate) mod testing {
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    use crate::colors::Color;
+    use crate::masks::Mask;
+
+    /// 八种基本线索色（不含白格）。
+    pub(crate) const BASE_CLUES: [Color; 8] = [
+        Color::Red,
+        Color::Blue,
+        Color::Black,
+        Color::Green,
+        Color::Yellow,
+        Color::Purple,
+        Color::Orange,
+        Color::Cyan,
+    ];
+
+    /// 同一 `seed` 总是给出同一串布局与 mask。
+    pub(crate) fn test_rng(seed: u64) -> ChaCha8Rng {
+        ChaCha8Rng::seed_from_u64(seed)
+    }
+
+    /// 随机颜色布局：每格以 `white` 的概率为白格，否则从 `clues` 中均匀取色。
+    pub(crate) fn random_board(
+        rng: &mut ChaCha8Rng,
+        size: usize,
+        clues: &[Color],
+        white: f64,
+    ) -> Vec<Color> {
+        (0..size * size)
+            .map(|_| {
+                if rng.gen_bool(white) {
+                    Color::White
+                } else {
+                    clues[rng.gen_range(0..clues.len())]
                 }
-            }
-        }
+            })
+            .collect()
     }
 
-    (total, possible, guaranteed)
+    /// `0..cells` 中每格以概率 `p` 置位、且不与 `exclude` 重叠的随机 mask。
+    pub(crate) fn random_mask(rng: &mut ChaCha8Rng, cells: usize, p: f64, exclude: Mask) -> Mask {
+        (0..cells)
+            .filter(|&c| !exclude.contains(c) && rng.gen_bool(p))
+            .fold(Mask::EMPTY, |m, c| m | Mask::bit(c))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::masks::{cell_index, GRID_SIZE};
+    use crate::masks::{cell_index, CELL_COUNT, GRID_SIZE};
     use std::collections::BTreeSet;
 
     fn colors_from_rows(rows: [[Color; GRID_SIZE]; GRID_SIZE]) -> [Color; CELL_COUNT] {
@@ -2107,11 +2004,11 @@ mod tests {
     }
 
     fn brute_force_solution_set(colors: [Color; CELL_COUNT]) -> BTreeSet<Mask> {
-        let mut black_mask: Mask = 0;
+        let mut black_mask = Mask::EMPTY;
         let mut vars = Vec::new();
-        for i in 0..CELL_COUNT {
-            if colors[i] == Color::Black {
-                black_mask |= 1u64 << i;
+        for (i, &color) in colors.iter().enumerate() {
+            if color == Color::Black {
+                black_mask.insert(i);
             } else {
                 vars.push(i);
             }
@@ -2125,11 +2022,12 @@ mod tests {
             let mut mask = black_mask;
             for (j, &i) in vars.iter().enumerate() {
                 if (combo & (1u64 << j)) != 0 {
-                    mask |= 1u64 << i;
+                    mask.insert(i);
                 }
             }
 
-            let res = crate::validate::validate_state(mask, &color_grid).expect("validate ok");
+            let res = crate::validate::validate_state(mask, &color_grid, WinCondition::default())
+                .expect("validate ok");
             if res.is_valid && res.is_bingo {
                 set.insert(mask);
             }
@@ -2178,7 +2076,7 @@ mod tests {
         ]);
 
         let expected = brute_force_solution_set(colors);
-        let got: BTreeSet<Mask> = Solver::new(GRID_SIZE, colors.to_vec())
+        let got: BTreeSet<Mask> = Solver::new(GRID_SIZE, colors.to_vec(), WinCondition::default())
             .solve_masks_limit(0)
             .into_iter()
             .collect();
@@ -2186,9 +2084,59 @@ mod tests {
         assert_eq!(got, expected);
     }
 
+    #[test]
+    fn custom_win_conditions_match_validator() {
+        // 4x4 全白：解集完全由胜利条件决定，逐一与校验器对照。
+        let size = 4;
+        let color_grid = vec![Color::White.to_u8(); size * size];
+        for win in [
+            WinCondition::InARow {
+                length: 3,
+                diagonals: false,
+            },
+            WinCondition::InARow {
+                length: 3,
+                diagonals: true,
+            },
+            WinCondition::FullLine { diagonals: false },
+        ] {
+            let mut expected = BTreeSet::new();
+            for combo in 0u64..(1 << (size * size)) {
+                let mask = Mask::from_u64(combo);
+                let res =
+                    crate::validate::validate_state(mask, &color_grid, win).expect("validate ok");
+                if res.is_valid && res.is_bingo {
+                    expected.insert(mask);
+                }
+            }
+
+            let got: BTreeSet<Mask> = Solver::new(size, vec![Color::White; size * size], win)
+                .solve_masks_limit(0)
+                .into_iter()
+                .collect();
+            assert_eq!(got, expected, "win={win:?}");
+        }
+    }
+
+    #[test]
+    fn solves_boards_larger_than_8x8() {
+        // 9x9 全黑：唯一解为 81 格全部勾选（超过 64 位）。
+        let solver = Solver::new(9, vec![Color::Black; 81], WinCondition::default());
+        assert_eq!(solver.solve_masks_limit(2), vec![Mask::full(81)]);
+
+        // 9x9 全白，只保留最后一行可选：所有解都只能落在最后一行（bit 72..81）。
+        let solver = Solver::new(9, vec![Color::White; 81], WinCondition::default());
+        let last_row = Mask::full(81) & !Mask::full(72);
+        let solutions = solver.solve_masks_limit_with_checked_mask(Mask::EMPTY, Mask::full(72), 0);
+        assert!(!solutions.is_empty());
+        assert!(solutions.iter().all(|m| m.is_subset(&last_row)));
+        assert!(solutions.contains(&last_row));
+    }
+
     #[test]
     fn hint_does_not_claim_no_solution_for_generated_puzzle() {
-        let grid = crate::generate::generate_puzzle(123, GRID_SIZE).expect("generate ok");
+        let grid = crate::generate::generate_puzzle(123, GRID_SIZE, WinCondition::default())
+            .expect("generate ok");
         let flat: Vec<u8> = grid.into_iter().flatten().collect();
 
         let mut colors = [Color::White; CELL_COUNT];
@@ -2196,8 +2144,8 @@ mod tests {
             colors[i] = Color::from_u8(v).expect("valid color");
         }
 
-        let solver = Solver::new(GRID_SIZE, colors.to_vec());
-        let hint = solver.hint_next(0u64);
+        let solver = Solver::new(GRID_SIZE, colors.to_vec(), WinCondition::default());
+        let hint = solver.hint_next(Mask::EMPTY, Mask::EMPTY);
         assert!(!matches!(hint.status, HintStatus::NoSolution));
     }
 
@@ -2205,7 +2153,8 @@ mod tests {
     fn hint_uncheck_move_is_actionable() {
         // 该测试针对“提示要求 uncheck 但目标格并未被勾选，导致提示卡死”的问题。
         // 约定：如果返回 move.action == Uncheck，则该格必须在 checked_mask 中为 1（用户当前确实勾选了它）。
-        let grid = crate::generate::generate_puzzle(123, GRID_SIZE).expect("generate ok");
+        let grid = crate::generate::generate_puzzle(123, GRID_SIZE, WinCondition::default())
+            .expect("generate ok");
         let flat: Vec<u8> = grid.into_iter().flatten().collect();
 
         let mut colors = [Color::White; CELL_COUNT];
@@ -2213,15 +2162,15 @@ mod tests {
             colors[i] = Color::from_u8(v).expect("valid color");
         }
 
-        let mut black_mask: Mask = 0;
-        for i in 0..CELL_COUNT {
-            if colors[i] == Color::Black {
-                black_mask |= 1u64 << i;
+        let mut black_mask = Mask::EMPTY;
+        for (i, &color) in colors.iter().enumerate() {
+            if color == Color::Black {
+                black_mask.insert(i);
             }
         }
 
-        let solver = Solver::new(GRID_SIZE, colors.to_vec());
-        let hint = solver.hint_next(black_mask);
+        let solver = Solver::new(GRID_SIZE, colors.to_vec(), WinCondition::default());
+        let hint = solver.hint_next(black_mask, Mask::EMPTY);
         if let Some(mv) = hint.mv.clone() {
             // 约定：只要给出了可执行 move，就应给出结构化 reason（用于前端高质量解释）。
             assert!(hint.reason.is_some(), "hint.move exists but hint.reason is None");
@@ -2232,9 +2181,8 @@ mod tests {
                 );
             }
             if matches!(mv.action, HintAction::Uncheck) {
-                assert_ne!(
-                    black_mask & (1u64 << mv.cell),
-                    0,
+                assert!(
+                    black_mask.contains(mv.cell),
                     "hint asked to uncheck an unchecked cell: cell={}",
                     mv.cell
                 );
@@ -2242,6 +2190,129 @@ mod tests {
         }
     }
 
+    #[test]
+    fn hint_exclude_move_is_actionable() {
+        // 约定：如果返回 move.action == Exclude，则该格不能已在 unchecked_mask 中（玩家尚未排除它），
+        // 也不能是玩家已勾选的格子（那种情况只会以修复建议的 Uncheck 出现）。
+        let grid = crate::generate::generate_puzzle(123, GRID_SIZE, WinCondition::default())
+            .expect("generate ok");
+        let colors: Vec<Color> = grid
+            .into_iter()
+            .flatten()
+            .map(|v| Color::from_u8(v).expect("valid color"))
+            .collect();
+        let black_mask = (0..CELL_COUNT)
+            .filter(|&i| colors[i] == Color::Black)
+            .fold(Mask::EMPTY, |m, i| m | Mask::bit(i));
+
+        let solver = Solver::new(GRID_SIZE, colors, WinCondition::default());
+        let mut unchecked_mask = Mask::EMPTY;
+        for _ in 0..CELL_COUNT {
+            let hint = solver.hint_next(black_mask, unchecked_mask);
+            let Some(mv) = hint.mv else {
+                break;
+            };
+            if !matches!(mv.action, HintAction::Exclude) {
+                break;
+            }
+            assert!(!black_mask.contains(mv.cell), "hint asked to exclude a checked cell");
+            assert!(
+                !unchecked_mask.contains(mv.cell),
+                "hint asked to exclude an already excluded cell: cell={}",
+                mv.cell
+            );
+            unchecked_mask.insert(mv.cell);
+        }
+    }
+
+    #[test]
+    fn hint_explains_conflict_that_needs_two_undos() {
+        // 中心蓝格最多两邻勾选，玩家勾了四个邻居：只撤销一个不够，提示应给出撤销两处的最小修复。
+        let mut colors = vec![Color::White; 25];
+        colors[12] = Color::Blue;
+        let solver = Solver::new(5, colors, WinCondition::default());
+        let checked = Mask::bit(6) | Mask::bit(7) | Mask::bit(8) | Mask::bit(11) | Mask::bit(0);
+        let hint = solver.hint_next(checked, Mask::EMPTY);
+        assert!(matches!(hint.status, HintStatus::Suggested));
+        let mv = hint.mv.expect("repair move");
+        assert!(matches!(mv.action, HintAction::Uncheck));
+        let reason = hint.reason.expect("conflict explanation");
+        assert!(matches!(reason.kind, HintReasonKind::Repair));
+        assert_eq!(reason.affected_cells.len(), 2);
+        assert_eq!(reason.affected_cells[0], mv.cell);
+        assert!(reason.affected_cells.iter().all(|c| [6, 7, 8, 11].contains(c)));
+        let core = reason.conflict.expect("conflict core");
+        assert_eq!(core.checks.len(), 3);
+        assert!(core.checks.iter().all(|c| [6, 7, 8, 11].contains(c)));
+        assert_eq!(core.rules.last().map(|r| r.rule_id), Some("blue"));
+    }
+
+    #[test]
+    fn hint_reports_forced_exclude_for_unknown_cell() {
+        // 中心 Blue，八邻中已有 2 个 Black：其余邻居必须不勾选（且它们当前都是未知格）。
+        let mut colors = [Color::White; CELL_COUNT];
+        colors[12] = Color::Blue;
+        colors[6] = Color::Black;
+        colors[7] = Color::Black;
+        let black_mask = Mask::bit(6) | Mask::bit(7);
+
+        let solver = Solver::new(GRID_SIZE, colors.to_vec(), WinCondition::default());
+        let hint = solver.hint_next(black_mask, Mask::EMPTY);
+        assert!(matches!(hint.status, HintStatus::Forced));
+        let mv = hint.mv.expect("forced hint should include a move");
+        assert!(matches!(mv.action, HintAction::Exclude));
+        assert!([8usize, 11, 13, 16, 17, 18].contains(&mv.cell));
+        let technique = hint.reason.as_ref().and_then(|r| r.technique);
+        assert_eq!(technique, Some(Technique::BlueSaturation));
+
+        // 玩家排除该格后，不应再重复提示同一格。
+        let next = solver.hint_next(black_mask, Mask::bit(mv.cell));
+        assert_ne!(next.mv.map(|m| m.cell), Some(mv.cell));
+    }
+
+    #[test]
+    fn hint_names_the_cyan_isolation_technique() {
+        // 中心 Cyan 的四邻都被排除：它自身不能勾选（“青格孤立”，而不是“最后一个邻居”）。
+        let mut colors = [Color::White; CELL_COUNT];
+        colors[12] = Color::Cyan;
+        let unchecked = [7, 11, 13, 17]
+            .into_iter()
+            .fold(Mask::EMPTY, |m, c| m | Mask::bit(c));
+
+        let solver = Solver::new(GRID_SIZE, colors.to_vec(), WinCondition::default());
+        let hint = solver.hint_next(Mask::EMPTY, unchecked);
+        assert!(matches!(hint.status, HintStatus::Forced));
+        assert_eq!(hint.mv.as_ref().map(|mv| mv.cell), Some(12));
+        let reason = hint.reason.expect("forced hint should include reason");
+        assert_eq!(reason.rule_id, Some("cyan"));
+        assert_eq!(reason.technique, Some(Technique::CyanIsolated));
+        let json = serde_json::to_value(&reason).unwrap();
+        assert_eq!(json["technique"], "cyan_isolated");
+    }
+
+    #[test]
+    fn unchecked_mask_is_respected_by_hint_and_count() {
+        // 全白盘面：把除第一行以外的格子全部排除，唯一解即“第一行全勾选”。
+        let colors = [Color::White; CELL_COUNT];
+        let solver = Solver::new(GRID_SIZE, colors.to_vec(), WinCondition::default());
+        let first_row = Mask::full(GRID_SIZE);
+        let unchecked_mask = Mask::full(CELL_COUNT) & !first_row;
+
+        let solutions = solver.solve_masks_limit_with_checked_mask(Mask::EMPTY, unchecked_mask, 0);
+        assert_eq!(solutions, vec![first_row]);
+
+        let hint = solver.hint_next(Mask::EMPTY, unchecked_mask);
+        assert!(matches!(hint.status, HintStatus::Forced));
+        let mv = hint.mv.expect("forced hint should include a move");
+        assert!(matches!(mv.action, HintAction::Check));
+        assert!(mv.cell < GRID_SIZE);
+
+        // 勾选与排除重叠时以勾选为准。
+        let overlap =
+            solver.solve_masks_limit_with_checked_mask(Mask::bit(0), unchecked_mask | Mask::bit(0), 0);
+        assert_eq!(overlap, solutions);
+    }
+
     #[test]
     fn hint_suggest_includes_bingo_context_and_steps() {
         // 构造一个“只有 Bingo 目标、没有颜色约束”的局面：
@@ -2249,8 +2320,8 @@ mod tests {
         // - 不会出现反证强制
         // 因此应回落到“从一个可行解抽取一步”的建议，并提供 bingo 的教学上下文。
         let colors = [Color::White; CELL_COUNT];
-        let solver = Solver::new(GRID_SIZE, colors.to_vec());
-        let hint = solver.hint_next(0u64);
+        let solver = Solver::new(GRID_SIZE, colors.to_vec(), WinCondition::default());
+        let hint = solver.hint_next(Mask::EMPTY, Mask::EMPTY);
 
         assert!(matches!(hint.status, HintStatus::Suggested));
         let mv = hint.mv.expect("suggest should include a move");
@@ -2263,4 +2334,28 @@ mod tests {
         assert!(reason.secondary_cells.contains(&mv.cell));
         assert!(!reason.steps.is_empty());
     }
+
+    #[test]
+    fn bingo_forces_cells_shared_by_remaining_lines() {
+        let solver = Solver::new(GRID_SIZE, vec![Color::White; CELL_COUNT], WinCondition::default());
+        let mask_of = |cells: &[usize]| cells.iter().fold(Mask::EMPTY, |m, &c| m | Mask::bit(c));
+
+        // 排除首行与首列（除 (3,1)）：只剩第 3 行一条连线，整行必须勾选。
+        let unchecked = mask_of(&[0, 1, 2, 3, 4, 5, 15, 20]);
+        let mut state = solver
+            .initial_state_with_masks(Mask::EMPTY, unchecked, &mut ())
+            .unwrap();
+        assert!(solver.propagate_bingo(&mut state, &mut ()));
+        assert!((10..15).all(|id| state.is_checked_id(id)));
+
+        // 放开 (1,3)：第 3 行与第 3 列都还可能，只有交点 (3,3) 被强制。
+        let unchecked = mask_of(&[0, 1, 3, 4, 5, 15, 20]);
+        let hint = solver.hint_next(Mask::EMPTY, unchecked);
+        assert!(matches!(hint.status, HintStatus::Forced));
+        assert_eq!(hint.mv.as_ref().map(|mv| mv.cell), Some(12));
+        let reason = hint.reason.expect("forced hint should include reason");
+        assert_eq!(reason.rule_id, Some("bingo"));
+        let candidates: Vec<usize> = (10..15).chain([2, 7, 17, 22]).collect::<BTreeSet<_>>().into_iter().collect();
+        assert_eq!(reason.secondary_cells, candidates);
+    }
 }
@@ -0,0 +1,259 @@
+//! 紫/橙格的 GF(2) 奇偶推理。
+//!
+//! 每个紫/橙格对应一条方程：邻域（默认八邻）中未知格的勾选数 ≡ 目标奇偶 − 已勾选数（mod 2）。
+//! 单看一条方程，只有“只剩 1 个未知”时才能下结论（见 `propagate_purple` / `propagate_orange`）；
+//! 但相邻约束的八邻大量重叠，把两条方程相加（异或）后公共格互相抵消，常常就只剩一个格子——
+//! 这正是玩家“拿两个紫格相减”的推理。
+//!
+//! 这里对全部方程做高斯消元（化为行最简形）：消元后只含一个未知格的行即为强制结论，
+//! 出现 `0 ≡ 1` 的行即为矛盾；每行同时记录它由哪些约束组合而来，供提示解释使用。
+
+use super::{
+    try_set_checked_id, try_set_unchecked_id, AssignReason, RuleType, SolveObserver, Solver,
+    SolverState, Technique,
+};
+use crate::masks::Mask;
+
+/// 一条 GF(2) 方程：`vars` 中格子的勾选数之和 ≡ `rhs`（mod 2）；`sources` 为参与组合的约束格。
+#[derive(Clone, Copy, Debug)]
+struct Row {
+    vars: Mask,
+    rhs: bool,
+    sources: Mask,
+}
+
+impl Row {
+    fn add(&mut self, other: &Row) {
+        self.vars = self.vars ^ other.vars;
+        self.rhs ^= other.rhs;
+        self.sources = self.sources ^ other.sources;
+    }
+}
+
+/// 奇偶方程组推出的一条强制结论。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct ParityDeduction {
+    pub cell: usize,
+    pub checked: bool,
+    /// 组合出该结论的紫/橙格（row-major 递增）。
+    pub sources: Vec<usize>,
+}
+
+impl Solver {
+    /// 对当前状态下全部紫/橙格方程做消元。
+    ///
+    /// 返回全部强制结论（按格子 id 递增）；方程组无解时返回 `Err(矛盾所用的约束格)`。
+    pub(crate) fn parity_deductions(
+        &self,
+        state: &SolverState,
+    ) -> Result<Vec<ParityDeduction>, Vec<usize>> {
+        let constraints = self.rules.parity_clues();
+
+        // 逐条插入并保持行最简形：新行先被已有主元消去，再用它的主元去消已有各行。
+        let mut pivots: Vec<(usize, Row)> = Vec::new();
+        for (id, odd, hood) in constraints {
+            let mut row = Row {
+                vars: Mask::EMPTY,
+                rhs: odd,
+                sources: Mask::bit(id),
+            };
+            for &n in self.rules.neighbors(hood, id) {
+                if state.is_checked_id(n) {
+                    row.rhs = !row.rhs;
+                } else if state.is_unknown_id(n) {
+                    row.vars.insert(n);
+                }
+            }
+
+            for (col, pivot) in &pivots {
+                if row.vars.contains(*col) {
+                    row.add(pivot);
+                }
+            }
+            let Some(col) = row.vars.iter_ones().next() else {
+                if row.rhs {
+                    return Err(row.sources.iter_ones().collect());
+                }
+                continue;
+            };
+            for (_, pivot) in pivots.iter_mut() {
+                if pivot.vars.contains(col) {
+                    pivot.add(&row);
+                }
+            }
+            pivots.push((col, row));
+        }
+
+        let mut out: Vec<ParityDeduction> = pivots
+            .into_iter()
+            .filter(|(_, row)| row.vars.count_ones() == 1)
+            .map(|(cell, row)| ParityDeduction {
+                cell,
+                checked: row.rhs,
+                sources: row.sources.iter_ones().collect(),
+            })
+            .collect();
+        out.sort_by_key(|d| d.cell);
+        Ok(out)
+    }
+
+    /// 奇偶联立传播：只在至少两条紫/橙约束时才有单格传播之外的结论。
+    pub(super) fn propagate_parity(
+        &self,
+        state: &mut SolverState,
+        obs: &mut impl SolveObserver,
+    ) -> bool {
+        if self.rules.parity_clues().nth(1).is_none() {
+            return true;
+        }
+
+        let deductions = match self.parity_deductions(state) {
+            Ok(deductions) => deductions,
+            Err(sources) => {
+                obs.on_rule_focus(RuleType::Parity, &sources);
+                return false;
+            }
+        };
+        for d in deductions {
+            if !state.is_unknown_id(d.cell) {
+                continue;
+            }
+            // 教学：奇偶联立的推导范围 = 参与组合的紫/橙格
+            obs.on_rule_focus(RuleType::Parity, &d.sources);
+            obs.on_technique(Technique::of_parity(d.sources.len()));
+            let ok = if d.checked {
+                try_set_checked_id(state, d.cell, AssignReason::Propagate, obs)
+            } else {
+                try_set_unchecked_id(state, d.cell, AssignReason::Propagate, obs)
+            };
+            if !ok {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::colors::Color;
+    use crate::solver::testing::{random_board, test_rng};
+    use crate::win::WinCondition;
+
+    #[test]
+    fn combines_two_overlapping_purple_cells() {
+        // 3x3 中间一列两个紫格：(0,1) 的八邻为 {0,2,3,4,5}，(1,1) 的八邻为 {0,1,2,3,5,6,7,8}。
+        // 排除 {4,6,7,8} 后两条方程都还有多个未知，相加后只剩格子 1 ≡ 0：1 必须不勾选。
+        let mut colors = vec![Color::White; 9];
+        colors[1] = Color::Purple;
+        colors[4] = Color::Purple;
+        let solver = Solver::new(
+            3,
+            colors,
+            WinCondition::InARow {
+                length: 3,
+                diagonals: false,
+            },
+        );
+        let mut state = SolverState::new(3);
+        for cell in [4, 6, 7, 8] {
+            state.set_unchecked_id(cell).unwrap();
+        }
+
+        let deductions = solver.parity_deductions(&state).unwrap();
+        assert_eq!(
+            deductions,
+            vec![ParityDeduction {
+                cell: 1,
+                checked: false,
+                sources: vec![1, 4],
+            }]
+        );
+    }
+
+    #[test]
+    fn hint_explains_parity_combination() {
+        // 5x5：末行全黑（Bingo 已满足），(0,1)、(1,1) 为紫格，排除 {6,10,11,12}。
+        // 两条方程 0+2+5+7 ≡ 1 与 0+1+2+5+7 ≡ 1 相加得 1 ≡ 0。
+        let mut colors = vec![Color::White; 25];
+        colors[1] = Color::Purple;
+        colors[6] = Color::Purple;
+        for c in colors.iter_mut().skip(20) {
+            *c = Color::Black;
+        }
+        let solver = Solver::new(5, colors, WinCondition::default());
+        let unchecked = [6, 10, 11, 12]
+            .into_iter()
+            .fold(Mask::EMPTY, |m, c| m | Mask::bit(c));
+
+        let hint = solver.hint_next(Mask::EMPTY, unchecked);
+        let mv = hint.mv.expect("forced move");
+        assert_eq!((mv.cell, mv.forced), (1, true));
+        let reason = hint.reason.expect("reason");
+        assert_eq!(reason.rule_id, Some("purple"));
+        assert_eq!(reason.secondary_cells, vec![1, 6]);
+        assert_eq!(reason.technique, Some(Technique::ParityPair));
+        assert!(reason
+            .steps
+            .iter()
+            .any(|s| s.title_key.key == "hint.parity.combine"));
+    }
+
+    #[test]
+    fn detects_inconsistent_system() {
+        // 紫格与橙格八邻完全相同（只差自身）时，奇偶要求互相矛盾。
+        let mut colors = vec![Color::White; 4];
+        colors[0] = Color::Purple;
+        colors[3] = Color::Orange;
+        let solver = Solver::new(
+            2,
+            colors,
+            WinCondition::InARow {
+                length: 2,
+                diagonals: false,
+            },
+        );
+        let mut state = SolverState::new(2);
+        state.set_unchecked_id(0).unwrap();
+        state.set_unchecked_id(3).unwrap();
+        // (0,0) 的八邻 {1,2,3}，(1,1) 的八邻 {0,1,2}：去掉已排除的 0、3 后都只剩 {1,2}。
+        assert_eq!(solver.parity_deductions(&state), Err(vec![0, 3]));
+    }
+
+    #[test]
+    fn variant_neighbourhoods_join_the_system() {
+        // 斜向紫格 (0,1) 与斜向橙格 (2,1) 的邻域都是 {3,5}：奇偶要求互相矛盾。
+        use crate::colors::CountColor;
+        use crate::masks::Neighborhood;
+
+        let mut colors = vec![Color::White; 9];
+        colors[1] = Color::Variant(CountColor::Purple, Neighborhood::Diagonal);
+        colors[7] = Color::Variant(CountColor::Orange, Neighborhood::Diagonal);
+        let solver = Solver::new(3, colors, WinCondition::default());
+        assert_eq!(
+            solver.parity_deductions(&SolverState::new(3)),
+            Err(vec![1, 7])
+        );
+    }
+
+    #[test]
+    fn deductions_agree_with_enumeration() {
+        // 随机紫/橙/白布局：每条奇偶结论都必须在全部解中成立。
+        let mut rng = test_rng(11);
+        for _ in 0..30 {
+            let colors = random_board(&mut rng, 5, &[Color::Purple, Color::Orange], 1.0 / 3.0);
+            let solver = Solver::new(5, colors, WinCondition::default());
+            let state = SolverState::new(5);
+            let solutions = solver.solve_masks_limit(0);
+            match solver.parity_deductions(&state) {
+                Ok(deductions) => {
+                    for d in deductions {
+                        assert!(solutions.iter().all(|s| s.contains(d.cell) == d.checked));
+                    }
+                }
+                Err(_) => assert!(solutions.is_empty()),
+            }
+        }
+    }
+}
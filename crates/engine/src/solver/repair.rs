@@ -0,0 +1,386 @@
+//! 最小修复：当前标记无解时，找出改动最少的一组操作（撤销勾选，可选地把排除改为勾选）使局面恢复可解。
+//!
+//! 隐式碰撞集（implicit hitting set）：
+//! - 任何修复都必须改动每个冲突子集（见 `conflict` 模块）中的至少一处；
+//! - 取已知冲突子集的最小碰撞集作为候选修复：改动后可解即为最优；
+//!   否则在改动后的局面上再求一个冲突子集，继续迭代。
+//!
+//! 同样大小的碰撞集中，优先改动玩家最近的操作（按调用方提供的操作历史）。
+
+use serde::Serialize;
+
+use super::{
+    BudgetMeter, Budgeted, ConflictCore, HintAction, HintMove, HintReason, HintReasonKind,
+    HintReasonStep, SolveBudget, Solver,
+};
+use crate::i18n::{msg, Locale, Localize};
+use crate::masks::Mask;
+
+/// 修复最多改动的格子数：超过时放弃（碰撞集的枚举量随它组合增长）。
+pub const MAX_REPAIR_MOVES: usize = 6;
+
+/// 一组使局面恢复可解的最少改动。
+#[derive(Debug, Clone, Serialize)]
+pub struct Repair {
+    /// 按优先级排列（最近的操作在前）：撤销勾选为 `Uncheck`，解除排除为 `Check`。
+    pub moves: Vec<HintMove>,
+    pub reason: HintReason,
+}
+
+impl Localize for Repair {
+    fn localize(&mut self, locale: Locale) {
+        self.reason.localize(locale);
+    }
+}
+
+impl Solver {
+    /// 计算最小修复（见模块文档）。
+    ///
+    /// - mask 约定同 [`Solver::solve_masks_cdcl_with_budget`]；
+    /// - `history`：玩家操作过的格子，按时间先后（可重复，以最后一次为准；可为空）；
+    /// - `release_exclusions`：是否允许把排除标记改为勾选（否则排除标记视为固定前提）；
+    /// - `value` 为 None 表示当前局面可解（或在判断出是否可解之前预算就已耗尽）；
+    ///   找不到修复时 `moves` 为空，但仍带有冲突解释（见 [`Solver::minimal_repair_from`]）。
+    pub fn minimal_repair(
+        &self,
+        checked_mask: Mask,
+        unchecked_mask: Mask,
+        history: &[usize],
+        release_exclusions: bool,
+        budget: &mut SolveBudget,
+    ) -> Budgeted<Option<Repair>> {
+        let (checked_mask, unchecked_mask) = self.normalize_masks(checked_mask, unchecked_mask);
+        let mut meter = BudgetMeter::new(budget);
+        let solvable = self
+            .solve_one_with_masks(checked_mask, unchecked_mask, &mut meter)
+            .is_some();
+        if solvable || meter.stopped().is_some() {
+            return meter.finish(None);
+        }
+        let repair = Some(self.minimal_repair_from(
+            checked_mask,
+            unchecked_mask,
+            history,
+            release_exclusions,
+            &mut meter,
+        ));
+        meter.finish(repair)
+    }
+
+    /// 在已知无解的前提下计算最小修复；两个 mask 需已规范化。
+    ///
+    /// 找不到修复（超过 [`MAX_REPAIR_MOVES`] 处、只改动允许的标记无法恢复可解，或预算耗尽）时 `moves` 为空；
+    /// `reason.conflict` 总是原局面的冲突解释（预算耗尽得太早时为空）。
+    pub(super) fn minimal_repair_from(
+        &self,
+        checked_mask: Mask,
+        unchecked_mask: Mask,
+        history: &[usize],
+        release_exclusions: bool,
+        meter: &mut BudgetMeter,
+    ) -> Repair {
+        let size = self.rules.size;
+        let black = self
+            .rules
+            .given_cells
+            .iter()
+            .fold(Mask::EMPTY, |m, &id| m | Mask::bit(id));
+        let toggles = (checked_mask & !black)
+            | if release_exclusions {
+                unchecked_mask
+            } else {
+                Mask::EMPTY
+            };
+
+        // 最近的操作排在前面；不在历史中的按求解顺序排在最后。
+        let recency = |id: usize| history.iter().rposition(|&c| c == id);
+        let mut order: Vec<usize> = self
+            .rules
+            .decision_order
+            .iter()
+            .copied()
+            .filter(|&id| toggles.contains(id))
+            .collect();
+        order.sort_by_key(|&id| std::cmp::Reverse(recency(id)));
+
+        let mut cores: Vec<Mask> = Vec::new();
+        let mut first: Option<ConflictCore> = None;
+        let found = loop {
+            if meter.stopped().is_some() {
+                break None;
+            }
+            let Some(hit) = min_hitting_set(&cores, &order) else {
+                break None;
+            };
+            let (checked, unchecked) = (checked_mask & !hit, unchecked_mask & !hit);
+            if let Some((_, solution)) = self.solve_one_with_masks(checked, unchecked, meter) {
+                break Some((hit, solution));
+            }
+            if meter.stopped().is_some() {
+                break None;
+            }
+            let (core, explained) = self.repair_core(checked, unchecked, release_exclusions, meter);
+            first.get_or_insert(explained);
+            let core = core & toggles;
+            if core.is_empty() {
+                // 只靠固定前提（黑格/排除标记）就已无解。
+                break None;
+            }
+            cores.push(core);
+        };
+        let (hit, solution) = found.unwrap_or((Mask::EMPTY, Mask::EMPTY));
+
+        let cells: Vec<usize> = order
+            .iter()
+            .copied()
+            .filter(|&id| hit.contains(id))
+            .collect();
+        let moves: Vec<HintMove> = cells
+            .iter()
+            .map(|&cell| HintMove {
+                cell,
+                // 碰撞集极小：见证解里撤销的勾选都不勾选，解除的排除都勾选。
+                action: if solution.contains(cell) {
+                    HintAction::Check
+                } else {
+                    HintAction::Uncheck
+                },
+                forced: false,
+            })
+            .collect();
+
+        let conflict = first.unwrap_or_else(|| ConflictCore {
+            checks: vec![],
+            rules: vec![],
+        });
+        let mut steps = vec![HintReasonStep::new(msg!("repair.unsolvable"), None, vec![])];
+        if !conflict.checks.is_empty() {
+            steps.push(HintReasonStep::new(
+                msg!("repair.conflict", count = conflict.checks.len()),
+                None,
+                conflict.checks.clone(),
+            ));
+        }
+        for rule in &conflict.rules {
+            steps.push(HintReasonStep::new(
+                msg!("repair.via", rule = rule.title_key.clone()),
+                Some(rule.rule_id),
+                rule.cells.clone(),
+            ));
+        }
+        if moves.len() > 1 {
+            steps.push(HintReasonStep::new(
+                msg!("repair.count", count = moves.len()),
+                None,
+                cells.clone(),
+            ));
+        }
+        for mv in &moves {
+            let (row, col) = (mv.cell / size + 1, mv.cell % size + 1);
+            let title = match mv.action {
+                HintAction::Check => msg!("repair.release", row = row, col = col),
+                HintAction::Uncheck | HintAction::Exclude => {
+                    msg!("repair.uncheck", row = row, col = col)
+                }
+            };
+            steps.push(HintReasonStep::new(title, None, vec![mv.cell]));
+        }
+
+        let touched = cores.iter().fold(Mask::EMPTY, |m, &c| m | c);
+        let (affected_cells, secondary_cells) = if moves.is_empty() {
+            (conflict.checks.clone(), vec![])
+        } else {
+            (cells, (touched & !hit).iter_ones().collect())
+        };
+        Repair {
+            moves,
+            reason: HintReason {
+                kind: HintReasonKind::Repair,
+                rule_id: conflict.rules.last().map(|r| r.rule_id),
+                affected_cells,
+                secondary_cells,
+                steps,
+                technique: None,
+                conflict: Some(conflict),
+            },
+        }
+    }
+
+    /// 无解局面上的一个冲突子集：先在勾选上求极小冲突解释，允许解除排除时再逐个删去不必要的排除标记。
+    ///
+    /// 返回冲突子集（勾选与排除）以及勾选部分的冲突解释。
+    fn repair_core(
+        &self,
+        checked_mask: Mask,
+        unchecked_mask: Mask,
+        release_exclusions: bool,
+        meter: &mut BudgetMeter,
+    ) -> (Mask, ConflictCore) {
+        let explained = self.conflict_core_from(checked_mask, unchecked_mask, meter);
+        let checks = explained
+            .checks
+            .iter()
+            .fold(Mask::EMPTY, |m, &id| m | Mask::bit(id));
+        if !release_exclusions {
+            return (checks, explained);
+        }
+        let mut exclusions = unchecked_mask;
+        for cell in unchecked_mask.iter_ones() {
+            if meter.stopped().is_some() {
+                break;
+            }
+            let mut trial = exclusions;
+            trial.remove(cell);
+            let solvable = self.solve_one_with_masks(checks, trial, meter).is_some();
+            if !solvable && meter.stopped().is_none() {
+                exclusions = trial;
+            }
+        }
+        (checks | exclusions, explained)
+    }
+}
+
+/// 所有冲突子集的最小碰撞集；同样大小时取 `order` 中下标字典序最小的一组。
+///
+/// 只在出现于某个冲突子集的格子中选取；超过 [`MAX_REPAIR_MOVES`] 时返回 None。
+fn min_hitting_set(cores: &[Mask], order: &[usize]) -> Option<Mask> {
+    let touched = cores.iter().fold(Mask::EMPTY, |m, &c| m | c);
+    let candidates: Vec<usize> = order
+        .iter()
+        .copied()
+        .filter(|&id| touched.contains(id))
+        .collect();
+    let hits_all = |set: Mask| cores.iter().all(|&c| !(c & set).is_empty());
+    (0..=MAX_REPAIR_MOVES.min(candidates.len())).find_map(|k| {
+        let mut picked = Vec::with_capacity(k);
+        first_hitting(&candidates, 0, k, &mut picked, &hits_all)
+    })
+}
+
+/// 按字典序枚举 `candidates[from..]` 中再选 `k` 个的组合，返回第一个满足 `hits_all` 的集合。
+fn first_hitting(
+    candidates: &[usize],
+    from: usize,
+    k: usize,
+    picked: &mut Vec<usize>,
+    hits_all: &impl Fn(Mask) -> bool,
+) -> Option<Mask> {
+    if k == 0 {
+        let set = picked.iter().fold(Mask::EMPTY, |m, &id| m | Mask::bit(id));
+        return hits_all(set).then_some(set);
+    }
+    for i in from..=candidates.len().saturating_sub(k) {
+        picked.push(candidates[i]);
+        let found = first_hitting(candidates, i + 1, k - 1, picked, hits_all);
+        picked.pop();
+        if found.is_some() {
+            return found;
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::colors::Color;
+    use crate::win::WinCondition;
+
+    /// 中心蓝格最多两邻勾选，玩家勾了四个邻居与一个无关格。
+    fn crowded_blue() -> (Solver, Mask) {
+        let mut colors = vec![Color::White; 25];
+        colors[12] = Color::Blue;
+        let solver = Solver::new(5, colors, WinCondition::default());
+        let checked = [0, 6, 7, 8, 11]
+            .into_iter()
+            .fold(Mask::EMPTY, |m, id| m | Mask::bit(id));
+        (solver, checked)
+    }
+
+    fn repair(
+        solver: &Solver,
+        checked: Mask,
+        unchecked: Mask,
+        history: &[usize],
+        release: bool,
+    ) -> Repair {
+        let res = solver.minimal_repair(
+            checked,
+            unchecked,
+            history,
+            release,
+            &mut SolveBudget::default(),
+        );
+        assert!(res.is_finished());
+        res.value.expect("unsolvable")
+    }
+
+    fn apply(checked: Mask, unchecked: Mask, moves: &[HintMove]) -> (Mask, Mask) {
+        moves
+            .iter()
+            .fold((checked, unchecked), |(mut c, mut u), mv| {
+                c.remove(mv.cell);
+                u.remove(mv.cell);
+                (c, u)
+            })
+    }
+
+    #[test]
+    fn finds_smallest_multi_cell_repair() {
+        let (solver, checked) = crowded_blue();
+        let repair = repair(&solver, checked, Mask::EMPTY, &[], false);
+        assert_eq!(repair.moves.len(), 2);
+        assert!(repair
+            .moves
+            .iter()
+            .all(|m| [6, 7, 8, 11].contains(&m.cell) && matches!(m.action, HintAction::Uncheck)));
+        let (c, u) = apply(checked, Mask::EMPTY, &repair.moves);
+        assert!(!solver
+            .solve_masks_limit_with_checked_mask(c, u, 1)
+            .is_empty());
+        assert!(matches!(repair.reason.kind, HintReasonKind::Repair));
+        assert_eq!(repair.reason.conflict.map(|c| c.checks.len()), Some(3));
+    }
+
+    #[test]
+    fn prefers_recent_moves() {
+        let (solver, checked) = crowded_blue();
+        let history = [6, 0, 11, 7, 8, 6];
+        let repair = repair(&solver, checked, Mask::EMPTY, &history, false);
+        let cells: Vec<usize> = repair.moves.iter().map(|m| m.cell).collect();
+        assert_eq!(cells, vec![6, 8]);
+    }
+
+    #[test]
+    fn releases_exclusions_when_allowed() {
+        // 每行、每列各排除一格且覆盖中心：连不成任何五格线，只有解除排除才能恢复可解。
+        let solver = Solver::new(5, vec![Color::White; 25], WinCondition::default());
+        let unchecked = [0, 8, 12, 19, 21]
+            .into_iter()
+            .fold(Mask::EMPTY, |m, id| m | Mask::bit(id));
+        let fixed = repair(&solver, Mask::EMPTY, unchecked, &[], false);
+        assert!(fixed.moves.is_empty());
+        assert_eq!(fixed.reason.conflict.map(|c| c.checks), Some(vec![]));
+
+        let repair = repair(&solver, Mask::EMPTY, unchecked, &[19], true);
+        assert_eq!(repair.moves.len(), 1);
+        assert_eq!(repair.moves[0].cell, 19);
+        assert!(matches!(repair.moves[0].action, HintAction::Check));
+        let (c, u) = apply(Mask::EMPTY, unchecked, &repair.moves);
+        assert!(!solver
+            .solve_masks_limit_with_checked_mask(c, u, 1)
+            .is_empty());
+    }
+
+    #[test]
+    fn hitting_set_is_minimum() {
+        let cores = [
+            Mask::bit(1) | Mask::bit(2),
+            Mask::bit(2) | Mask::bit(3),
+            Mask::bit(4),
+        ];
+        let got = min_hitting_set(&cores, &[1, 2, 3, 4]).unwrap();
+        assert_eq!(got, Mask::bit(2) | Mask::bit(4));
+        assert_eq!(min_hitting_set(&[], &[1, 2]), Some(Mask::EMPTY));
+    }
+}
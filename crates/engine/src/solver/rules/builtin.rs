@@ -0,0 +1,760 @@
+//! 内置的颜色规则（与 `rules.json` 对齐）：八种颜色与数字格 0~8。
+//!
+//! 红/蓝/紫/橙按邻域参数化：八邻即原色，其余邻域为变体格（见 `Color::Variant`）。
+
+use super::{CellRule, Deduce, Filled};
+use crate::colors::{Color, CountColor};
+use crate::i18n::{msg, Message};
+use crate::masks::Neighborhood;
+use crate::solver::exact_count::Tally;
+use crate::solver::{RuleSet, Technique};
+
+/// 变体格的简短名称：原名后注明邻域，如“红格（马步格）”。
+fn short_in(hood: Neighborhood, short: Message) -> Message {
+    match hood {
+        Neighborhood::King => short,
+        hood => msg!("rule.short.variant", rule = short, hood = hood.noun()),
+    }
+}
+
+/// 线索格自身 + 邻居（红/蓝/紫/橙/数字格看各自的邻域，青看四邻）。
+fn with_neighbors(cell: usize, neighbors: &[usize]) -> Vec<usize> {
+    let mut focus = Vec::with_capacity(1 + neighbors.len());
+    focus.push(cell);
+    focus.extend_from_slice(neighbors);
+    focus
+}
+
+/// 邻居中的勾选数、未知数，以及最后一个未知邻居。
+fn neighbor_counts(out: &dyn Deduce, neighbors: &[usize]) -> (usize, usize, Option<usize>) {
+    let state = out.state();
+    let mut checked = 0usize;
+    let mut unknown_count = 0usize;
+    let mut last_unknown = None;
+    for &n in neighbors {
+        if state.is_checked_id(n) {
+            checked += 1;
+        } else if state.is_unknown_id(n) {
+            unknown_count += 1;
+            last_unknown = Some(n);
+        }
+    }
+    (checked, unknown_count, last_unknown)
+}
+
+/// 邻域逐格计数的累加器（红/蓝/紫/橙/数字格）。
+fn neighbors_tally(
+    rules: &RuleSet,
+    hood: Neighborhood,
+    cell: usize,
+    tally: Tally,
+) -> Option<(Tally, Vec<(usize, i8)>)> {
+    Some((
+        tally,
+        rules
+            .neighbors(hood, cell)
+            .iter()
+            .map(|&n| (n, 1))
+            .collect(),
+    ))
+}
+
+/// 红格：邻域（默认八邻）至少 1 个勾选。
+pub(super) struct Red(pub(super) Neighborhood);
+
+impl CellRule for Red {
+    fn color(&self) -> Color {
+        Color::counted(CountColor::Red, self.0)
+    }
+
+    fn id(&self) -> &'static str {
+        "red"
+    }
+
+    fn title(&self) -> Message {
+        match self.0 {
+            Neighborhood::King => msg!("rule.red"),
+            hood => msg!("rule.red.hood", hood = hood.noun()),
+        }
+    }
+
+    fn short_name(&self) -> Message {
+        short_in(self.0, msg!("rule.short.red"))
+    }
+
+    fn is_variant(&self) -> bool {
+        self.0 != Neighborhood::King
+    }
+
+    fn check(&self, board: &Filled, cell: usize) -> Result<(), Message> {
+        let count = board.neighbors(self.0, cell);
+        if count >= 1 {
+            Ok(())
+        } else {
+            Err(msg!("validate.red", count = count))
+        }
+    }
+
+    fn focus(&self, rules: &RuleSet, cell: usize) -> Vec<usize> {
+        with_neighbors(cell, rules.neighbors(self.0, cell))
+    }
+
+    fn propagate(&self, rules: &RuleSet, cell: usize, out: &mut dyn Deduce) -> bool {
+        let (checked, unknown_count, last_unknown) =
+            neighbor_counts(out, rules.neighbors(self.0, cell));
+        if checked > 0 {
+            return true;
+        }
+        if unknown_count == 0 {
+            return false;
+        }
+        unknown_count != 1 || out.assign(last_unknown.expect("unknown_count==1"), true)
+    }
+
+    fn technique(&self) -> Option<Technique> {
+        Some(Technique::RedLastNeighbour)
+    }
+
+    fn tally(&self, rules: &RuleSet, cell: usize) -> Option<(Tally, Vec<(usize, i8)>)> {
+        neighbors_tally(rules, self.0, cell, Tally::AtLeastOne)
+    }
+
+    fn human_weight(&self) -> u32 {
+        2
+    }
+
+    fn decision_weight(&self) -> i32 {
+        60
+    }
+
+    fn propagation_rank(&self) -> Option<u8> {
+        Some(40)
+    }
+}
+
+/// 蓝格：邻域（默认八邻）最多 2 个勾选。
+pub(super) struct Blue(pub(super) Neighborhood);
+
+impl CellRule for Blue {
+    fn color(&self) -> Color {
+        Color::counted(CountColor::Blue, self.0)
+    }
+
+    fn id(&self) -> &'static str {
+        "blue"
+    }
+
+    fn title(&self) -> Message {
+        match self.0 {
+            Neighborhood::King => msg!("rule.blue"),
+            hood => msg!("rule.blue.hood", hood = hood.noun()),
+        }
+    }
+
+    fn short_name(&self) -> Message {
+        short_in(self.0, msg!("rule.short.blue"))
+    }
+
+    fn is_variant(&self) -> bool {
+        self.0 != Neighborhood::King
+    }
+
+    fn check(&self, board: &Filled, cell: usize) -> Result<(), Message> {
+        let count = board.neighbors(self.0, cell);
+        if count <= 2 {
+            Ok(())
+        } else {
+            Err(msg!("validate.blue", count = count))
+        }
+    }
+
+    fn focus(&self, rules: &RuleSet, cell: usize) -> Vec<usize> {
+        with_neighbors(cell, rules.neighbors(self.0, cell))
+    }
+
+    fn propagate(&self, rules: &RuleSet, cell: usize, out: &mut dyn Deduce) -> bool {
+        let (checked, _, _) = neighbor_counts(out, rules.neighbors(self.0, cell));
+        if checked > 2 {
+            return false;
+        }
+        // 已经 2 个勾选 -> 剩余未知邻居全部必须不勾选
+        checked < 2 || out.fill_cells(rules.neighbors(self.0, cell), false)
+    }
+
+    fn technique(&self) -> Option<Technique> {
+        Some(Technique::BlueSaturation)
+    }
+
+    fn tally(&self, rules: &RuleSet, cell: usize) -> Option<(Tally, Vec<(usize, i8)>)> {
+        neighbors_tally(rules, self.0, cell, Tally::AtMostTwo)
+    }
+
+    fn human_weight(&self) -> u32 {
+        3
+    }
+
+    fn decision_weight(&self) -> i32 {
+        100
+    }
+
+    fn propagation_rank(&self) -> Option<u8> {
+        Some(30)
+    }
+}
+
+/// 绿格：所在行与所在列的勾选数相等。
+pub(super) struct Green;
+
+impl CellRule for Green {
+    fn color(&self) -> Color {
+        Color::Green
+    }
+
+    fn id(&self) -> &'static str {
+        "green"
+    }
+
+    fn title(&self) -> Message {
+        msg!("rule.green")
+    }
+
+    fn short_name(&self) -> Message {
+        msg!("rule.short.green")
+    }
+
+    fn check(&self, board: &Filled, cell: usize) -> Result<(), Message> {
+        let (row, col) = board.row_col(cell);
+        if row == col {
+            Ok(())
+        } else {
+            Err(msg!("validate.green", row = row, col = col))
+        }
+    }
+
+    /// 教学：绿格推导涉及“该行 + 该列”。
+    fn focus(&self, rules: &RuleSet, cell: usize) -> Vec<usize> {
+        let size = rules.size;
+        let (row, col) = (cell / size, cell % size);
+        let mut focus: Vec<usize> = (0..size).map(|c| row * size + c).collect();
+        focus.extend((0..size).filter(|&r| r != row).map(|r| r * size + col));
+        focus
+    }
+
+    fn propagate(&self, rules: &RuleSet, cell: usize, out: &mut dyn Deduce) -> bool {
+        let (row, col) = (cell / rules.size, cell % rules.size);
+        let (r_min, r_max) = out.state().row_min_max(row);
+        let (c_min, c_max) = out.state().col_min_max(col);
+        if r_max < c_min || c_max < r_min {
+            return false;
+        }
+
+        // 情况 A：Row 的 Max == Col 的 Min
+        if r_max == c_min && !(out.fill_row(row, true) && out.fill_col(col, false)) {
+            return false;
+        }
+        // 情况 B：Col 的 Max == Row 的 Min
+        if c_max == r_min && !(out.fill_col(col, true) && out.fill_row(row, false)) {
+            return false;
+        }
+        true
+    }
+
+    fn technique(&self) -> Option<Technique> {
+        Some(Technique::GreenSqueeze)
+    }
+
+    fn tally(&self, rules: &RuleSet, cell: usize) -> Option<(Tally, Vec<(usize, i8)>)> {
+        let size = rules.size;
+        let (row, col) = (cell / size, cell % size);
+        // 自身同时计入行与列，差值中抵消。
+        let mut scope: Vec<(usize, i8)> = (0..size)
+            .filter(|&c| c != col)
+            .map(|c| (row * size + c, 1))
+            .collect();
+        scope.extend(
+            (0..size)
+                .filter(|&r| r != row)
+                .map(|r| (r * size + col, -1)),
+        );
+        Some((Tally::Balanced, scope))
+    }
+
+    fn human_weight(&self) -> u32 {
+        5
+    }
+
+    fn decision_weight(&self) -> i32 {
+        90
+    }
+
+    fn propagation_rank(&self) -> Option<u8> {
+        Some(10)
+    }
+}
+
+/// 黄格：经过它的两条对角线勾选数相等。
+pub(super) struct Yellow;
+
+impl Yellow {
+    fn diagonals(rules: &RuleSet, cell: usize) -> (&[usize], &[usize]) {
+        let (row, col) = (cell / rules.size, cell % rules.size);
+        (
+            &rules.diag_down_cells[row + (rules.size - 1) - col],
+            &rules.diag_up_cells[row + col],
+        )
+    }
+}
+
+impl CellRule for Yellow {
+    fn color(&self) -> Color {
+        Color::Yellow
+    }
+
+    fn id(&self) -> &'static str {
+        "yellow"
+    }
+
+    fn title(&self) -> Message {
+        msg!("rule.yellow")
+    }
+
+    fn short_name(&self) -> Message {
+        msg!("rule.short.yellow")
+    }
+
+    fn check(&self, board: &Filled, cell: usize) -> Result<(), Message> {
+        let (down, up) = board.diagonals(cell);
+        if down == up {
+            Ok(())
+        } else {
+            Err(msg!("validate.yellow", down = down, up = up))
+        }
+    }
+
+    /// 教学：黄格推导涉及“交叉两条对角线”。
+    fn focus(&self, rules: &RuleSet, cell: usize) -> Vec<usize> {
+        let (down, up) = Self::diagonals(rules, cell);
+        let mut focus = down.to_vec();
+        focus.extend(up.iter().filter(|&&c| c != cell));
+        focus
+    }
+
+    fn propagate(&self, rules: &RuleSet, cell: usize, out: &mut dyn Deduce) -> bool {
+        let (row, col) = (cell / rules.size, cell % rules.size);
+        let (d_min, d_max) = out.state().diag_down_min_max(row + (rules.size - 1) - col);
+        let (u_min, u_max) = out.state().diag_up_min_max(row + col);
+        if d_max < u_min || u_max < d_min {
+            return false;
+        }
+
+        // 对角线推导与绿格一致（范围卡边界）
+        let (down, up) = Self::diagonals(rules, cell);
+        if d_max == u_min && !(out.fill_cells(down, true) && out.fill_cells(up, false)) {
+            return false;
+        }
+        if u_max == d_min && !(out.fill_cells(up, true) && out.fill_cells(down, false)) {
+            return false;
+        }
+        true
+    }
+
+    fn technique(&self) -> Option<Technique> {
+        Some(Technique::YellowSqueeze)
+    }
+
+    fn tally(&self, rules: &RuleSet, cell: usize) -> Option<(Tally, Vec<(usize, i8)>)> {
+        let (down, up) = Self::diagonals(rules, cell);
+        let mut scope: Vec<(usize, i8)> = down
+            .iter()
+            .filter(|&&c| c != cell)
+            .map(|&c| (c, 1))
+            .collect();
+        scope.extend(up.iter().filter(|&&c| c != cell).map(|&c| (c, -1)));
+        Some((Tally::Balanced, scope))
+    }
+
+    fn human_weight(&self) -> u32 {
+        6
+    }
+
+    fn decision_weight(&self) -> i32 {
+        80
+    }
+
+    fn propagation_rank(&self) -> Option<u8> {
+        Some(20)
+    }
+}
+
+/// 紫/橙格共用的奇偶推导：只剩 1 个未知邻居时按目标奇偶补齐。
+fn propagate_parity(
+    rules: &RuleSet,
+    hood: Neighborhood,
+    cell: usize,
+    odd: bool,
+    out: &mut dyn Deduce,
+) -> bool {
+    let (checked, unknown_count, last_unknown) = neighbor_counts(out, rules.neighbors(hood, cell));
+    let odd_now = !checked.is_multiple_of(2);
+    match unknown_count {
+        0 => odd_now == odd,
+        1 => {
+            let should_check = odd_now != odd;
+            out.assign(last_unknown.expect("unknown_count==1"), should_check)
+        }
+        _ => true,
+    }
+}
+
+/// 紫格：邻域（默认八邻）勾选数为奇数。
+pub(super) struct Purple(pub(super) Neighborhood);
+
+impl CellRule for Purple {
+    fn color(&self) -> Color {
+        Color::counted(CountColor::Purple, self.0)
+    }
+
+    fn id(&self) -> &'static str {
+        "purple"
+    }
+
+    fn title(&self) -> Message {
+        match self.0 {
+            Neighborhood::King => msg!("rule.purple"),
+            hood => msg!("rule.purple.hood", hood = hood.noun()),
+        }
+    }
+
+    fn short_name(&self) -> Message {
+        short_in(self.0, msg!("rule.short.purple"))
+    }
+
+    fn is_variant(&self) -> bool {
+        self.0 != Neighborhood::King
+    }
+
+    fn check(&self, board: &Filled, cell: usize) -> Result<(), Message> {
+        let count = board.neighbors(self.0, cell);
+        if !count.is_multiple_of(2) {
+            Ok(())
+        } else {
+            Err(msg!("validate.purple", count = count))
+        }
+    }
+
+    fn focus(&self, rules: &RuleSet, cell: usize) -> Vec<usize> {
+        with_neighbors(cell, rules.neighbors(self.0, cell))
+    }
+
+    fn propagate(&self, rules: &RuleSet, cell: usize, out: &mut dyn Deduce) -> bool {
+        propagate_parity(rules, self.0, cell, true, out)
+    }
+
+    fn technique(&self) -> Option<Technique> {
+        Some(Technique::PurpleLastNeighbour)
+    }
+
+    fn tally(&self, rules: &RuleSet, cell: usize) -> Option<(Tally, Vec<(usize, i8)>)> {
+        neighbors_tally(rules, self.0, cell, Tally::Odd)
+    }
+
+    fn human_weight(&self) -> u32 {
+        7
+    }
+
+    fn decision_weight(&self) -> i32 {
+        50
+    }
+
+    fn propagation_rank(&self) -> Option<u8> {
+        Some(50)
+    }
+}
+
+/// 橙格：邻域（默认八邻）勾选数为偶数。
+pub(super) struct Orange(pub(super) Neighborhood);
+
+impl CellRule for Orange {
+    fn color(&self) -> Color {
+        Color::counted(CountColor::Orange, self.0)
+    }
+
+    fn id(&self) -> &'static str {
+        "orange"
+    }
+
+    fn title(&self) -> Message {
+        match self.0 {
+            Neighborhood::King => msg!("rule.orange"),
+            hood => msg!("rule.orange.hood", hood = hood.noun()),
+        }
+    }
+
+    fn short_name(&self) -> Message {
+        short_in(self.0, msg!("rule.short.orange"))
+    }
+
+    fn is_variant(&self) -> bool {
+        self.0 != Neighborhood::King
+    }
+
+    fn check(&self, board: &Filled, cell: usize) -> Result<(), Message> {
+        let count = board.neighbors(self.0, cell);
+        if count.is_multiple_of(2) {
+            Ok(())
+        } else {
+            Err(msg!("validate.orange", count = count))
+        }
+    }
+
+    fn focus(&self, rules: &RuleSet, cell: usize) -> Vec<usize> {
+        with_neighbors(cell, rules.neighbors(self.0, cell))
+    }
+
+    fn propagate(&self, rules: &RuleSet, cell: usize, out: &mut dyn Deduce) -> bool {
+        propagate_parity(rules, self.0, cell, false, out)
+    }
+
+    fn technique(&self) -> Option<Technique> {
+        Some(Technique::OrangeLastNeighbour)
+    }
+
+    fn tally(&self, rules: &RuleSet, cell: usize) -> Option<(Tally, Vec<(usize, i8)>)> {
+        neighbors_tally(rules, self.0, cell, Tally::Even)
+    }
+
+    fn human_weight(&self) -> u32 {
+        7
+    }
+
+    fn decision_weight(&self) -> i32 {
+        50
+    }
+
+    fn propagation_rank(&self) -> Option<u8> {
+        Some(60)
+    }
+}
+
+/// 青格：被勾选时四邻至少 1 个勾选；未勾选时不生效。
+pub(super) struct Cyan;
+
+impl CellRule for Cyan {
+    fn color(&self) -> Color {
+        Color::Cyan
+    }
+
+    fn id(&self) -> &'static str {
+        "cyan"
+    }
+
+    fn title(&self) -> Message {
+        msg!("rule.cyan")
+    }
+
+    fn short_name(&self) -> Message {
+        msg!("rule.short.cyan")
+    }
+
+    fn check(&self, board: &Filled, cell: usize) -> Result<(), Message> {
+        if !board.is_checked(cell) {
+            return Ok(());
+        }
+        let count = board.neighbors(Neighborhood::Orthogonal, cell);
+        if count >= 1 {
+            Ok(())
+        } else {
+            Err(msg!("validate.cyan", count = count))
+        }
+    }
+
+    fn focus(&self, rules: &RuleSet, cell: usize) -> Vec<usize> {
+        with_neighbors(cell, rules.neighbors(Neighborhood::Orthogonal, cell))
+    }
+
+    fn propagate(&self, rules: &RuleSet, cell: usize, out: &mut dyn Deduce) -> bool {
+        let neighbors = rules.neighbors(Neighborhood::Orthogonal, cell);
+        // 未勾选：规则不生效
+        if out.state().is_unchecked_id(cell) {
+            return true;
+        }
+
+        // 已勾选：四邻至少 1 个勾选（类似红格，但邻域为 4）
+        if out.state().is_checked_id(cell) {
+            let (checked, unknown_count, last_unknown) = neighbor_counts(out, neighbors);
+            if checked > 0 {
+                return true;
+            }
+            if unknown_count == 0 {
+                return false;
+            }
+            return unknown_count != 1 || out.assign(last_unknown.expect("unknown_count==1"), true);
+        }
+
+        // 未知：如果四邻全部“不勾选”，则该青格不能勾选（否则必矛盾）-> 强制不勾选
+        if neighbors.iter().all(|&n| out.state().is_unchecked_id(n)) {
+            out.technique(Technique::CyanIsolated);
+            return out.assign(cell, false);
+        }
+        true
+    }
+
+    fn technique(&self) -> Option<Technique> {
+        Some(Technique::CyanLastNeighbour)
+    }
+
+    fn tally(&self, rules: &RuleSet, cell: usize) -> Option<(Tally, Vec<(usize, i8)>)> {
+        let mut scope: Vec<(usize, i8)> = rules
+            .neighbors(Neighborhood::Orthogonal, cell)
+            .iter()
+            .map(|&n| (n, 0b01))
+            .collect();
+        scope.push((cell, 0b10));
+        Some((Tally::CheckedNeedsNeighbor, scope))
+    }
+
+    fn human_weight(&self) -> u32 {
+        4
+    }
+
+    fn decision_weight(&self) -> i32 {
+        70
+    }
+
+    fn propagation_rank(&self) -> Option<u8> {
+        Some(70)
+    }
+}
+
+/// 黑格：必须勾选（作为已知条件，不参与决策与传播）。
+pub(super) struct Black;
+
+impl CellRule for Black {
+    fn color(&self) -> Color {
+        Color::Black
+    }
+
+    fn id(&self) -> &'static str {
+        "black"
+    }
+
+    fn title(&self) -> Message {
+        msg!("rule.black")
+    }
+
+    fn short_name(&self) -> Message {
+        msg!("rule.short.black")
+    }
+
+    fn fixes_checked(&self) -> bool {
+        true
+    }
+
+    fn check(&self, board: &Filled, cell: usize) -> Result<(), Message> {
+        if board.is_checked(cell) {
+            Ok(())
+        } else {
+            Err(msg!("validate.black"))
+        }
+    }
+
+    fn focus(&self, _rules: &RuleSet, cell: usize) -> Vec<usize> {
+        vec![cell]
+    }
+
+    fn propagate(&self, _rules: &RuleSet, _cell: usize, _out: &mut dyn Deduce) -> bool {
+        true
+    }
+
+    fn technique(&self) -> Option<Technique> {
+        None
+    }
+
+    fn tally(&self, _rules: &RuleSet, _cell: usize) -> Option<(Tally, Vec<(usize, i8)>)> {
+        None
+    }
+
+    fn human_weight(&self) -> u32 {
+        1
+    }
+
+    fn decision_weight(&self) -> i32 {
+        0
+    }
+
+    fn propagation_rank(&self) -> Option<u8> {
+        None
+    }
+}
+
+/// 数字格：八邻恰好 N 格勾选（一种颜色对应一个数字，见 `Color::Number`）。
+pub(super) struct Numbered(pub(super) u8);
+
+impl CellRule for Numbered {
+    fn color(&self) -> Color {
+        Color::Number(self.0)
+    }
+
+    fn id(&self) -> &'static str {
+        "number"
+    }
+
+    fn title(&self) -> Message {
+        msg!("rule.number", n = self.0)
+    }
+
+    fn short_name(&self) -> Message {
+        msg!("rule.short.number")
+    }
+
+    fn check(&self, board: &Filled, cell: usize) -> Result<(), Message> {
+        let count = board.neighbors(Neighborhood::King, cell);
+        if count == self.0 as u32 {
+            Ok(())
+        } else {
+            Err(msg!("validate.number", n = self.0, count = count))
+        }
+    }
+
+    fn focus(&self, rules: &RuleSet, cell: usize) -> Vec<usize> {
+        with_neighbors(cell, rules.neighbors(Neighborhood::King, cell))
+    }
+
+    fn propagate(&self, rules: &RuleSet, cell: usize, out: &mut dyn Deduce) -> bool {
+        let target = self.0 as usize;
+        let (checked, unknown_count, _) =
+            neighbor_counts(out, rules.neighbors(Neighborhood::King, cell));
+        if checked > target || checked + unknown_count < target {
+            return false;
+        }
+        if unknown_count == 0 {
+            return true;
+        }
+        // 已勾满 -> 其余不勾选；未知邻居恰好补足 -> 全部勾选
+        if checked == target {
+            return out.fill_cells(rules.neighbors(Neighborhood::King, cell), false);
+        }
+        checked + unknown_count != target
+            || out.fill_cells(rules.neighbors(Neighborhood::King, cell), true)
+    }
+
+    fn technique(&self) -> Option<Technique> {
+        Some(Technique::NumberCount)
+    }
+
+    fn tally(&self, rules: &RuleSet, cell: usize) -> Option<(Tally, Vec<(usize, i8)>)> {
+        neighbors_tally(rules, Neighborhood::King, cell, Tally::Exactly(self.0))
+    }
+
+    fn human_weight(&self) -> u32 {
+        3
+    }
+
+    fn decision_weight(&self) -> i32 {
+        95
+    }
+
+    fn propagation_rank(&self) -> Option<u8> {
+        Some(45)
+    }
+}
@@ -0,0 +1,400 @@
+//! 线索格规则：每种颜色一条 [`CellRule`]，按颜色编码登记在注册表里。
+//!
+//! 校验（`validate`）、生成器反推颜色（`generate`）、约束传播、精确计数（`exact_count`）、
+//! 提示解释与难度权重都经由注册表分派；新增一种颜色只需给 `Color` 加一个编码、
+//! 实现 `CellRule` 并登记到 [`REGISTRY`]（文案放进 i18n 目录）。
+//! 红/蓝/紫/橙按邻域（[`Neighborhood`]）参数化，换了邻域的变体格各占一个编码，默认不进生成器。
+//! 跨线索的组合推理（紫/橙奇偶联立、绿/黄计数链）与胜利条件仍各有模块，作为额外的传播阶段插入。
+
+mod builtin;
+
+use super::exact_count::Tally;
+use super::state::SolverState;
+use super::{cell_id, RuleSet, RuleType, Technique};
+use crate::colors::{Color, COLOR_COUNT};
+use crate::i18n::Message;
+use crate::masks::{BoardMasks, Mask, Neighborhood};
+
+/// 一种颜色的线索规则。
+///
+/// 规则只看“线索格 + 它关心的格子”：完整盘面上的检查与生成器的颜色候选走 [`Filled`]，
+/// 传播走 [`Deduce`]（由求解器实现，负责记录赋值与解释上下文）。
+pub(crate) trait CellRule: Sync {
+    /// 注册表的键。
+    fn color(&self) -> Color;
+
+    /// 规则 id（与 `rules.json` 对齐）。
+    fn id(&self) -> &'static str;
+
+    /// 提示中的规则名称（`rule.*`）。
+    fn title(&self) -> Message;
+
+    /// 简短名称（`rule.short.*`），用于分级提示。
+    fn short_name(&self) -> Message;
+
+    /// 该颜色的格子本身必须勾选（如黑格）：作为已知条件，不参与决策与传播。
+    fn fixes_checked(&self) -> bool {
+        false
+    }
+
+    /// 变体规则（换了邻域的计数格）：校验、求解与提示照常，但生成器不会自己出。
+    fn is_variant(&self) -> bool {
+        false
+    }
+
+    /// 在完整勾选方案上检查 `cell` 处的线索；不满足时返回说明。
+    fn check(&self, board: &Filled, cell: usize) -> Result<(), Message>;
+
+    /// 生成器反推颜色：目标解下 `cell` 能否填这种颜色（默认与 `check` 一致）。
+    fn fits(&self, board: &Filled, cell: usize) -> bool {
+        self.check(board, cell).is_ok()
+    }
+
+    /// 提示里解释这条线索时高亮的格子（线索格自身在前）。
+    fn focus(&self, rules: &RuleSet, cell: usize) -> Vec<usize>;
+
+    /// 传播 `cell` 处的线索；返回 false 表示矛盾。
+    fn propagate(&self, rules: &RuleSet, cell: usize, out: &mut dyn Deduce) -> bool;
+
+    /// 推导没有进一步细分时归入的技巧；不传播的规则返回 None。
+    fn technique(&self) -> Option<Technique>;
+
+    /// 精确计数用的累加器与作用范围 `[(cell, weight)]`（见 `exact_count`）；None 表示不需要。
+    fn tally(&self, rules: &RuleSet, cell: usize) -> Option<(Tally, Vec<(usize, i8)>)>;
+
+    /// 人类推理负担的经验权重（1~8，用于难度评分）。
+    fn human_weight(&self) -> u32;
+
+    /// 决策顺序中的优先级：越大越先猜（再减去邻居数的影响，见 `RuleSet::new`）。
+    fn decision_weight(&self) -> i32;
+
+    /// 传播阶段的先后：越小越先；None 表示不参与传播。
+    fn propagation_rank(&self) -> Option<u8>;
+}
+
+/// 全部颜色规则。
+///
+/// 登记顺序即生成器列出候选颜色的顺序：改动会改变同一 seed 生成的题目（含每日一题）。
+static REGISTRY: [&dyn CellRule; 33] = [
+    &builtin::Red(Neighborhood::King),
+    &builtin::Blue(Neighborhood::King),
+    &builtin::Green,
+    &builtin::Yellow,
+    &builtin::Purple(Neighborhood::King),
+    &builtin::Orange(Neighborhood::King),
+    &builtin::Cyan,
+    &builtin::Black,
+    &builtin::Numbered(0),
+    &builtin::Numbered(1),
+    &builtin::Numbered(2),
+    &builtin::Numbered(3),
+    &builtin::Numbered(4),
+    &builtin::Numbered(5),
+    &builtin::Numbered(6),
+    &builtin::Numbered(7),
+    &builtin::Numbered(8),
+    &builtin::Red(Neighborhood::Orthogonal),
+    &builtin::Red(Neighborhood::Knight),
+    &builtin::Red(Neighborhood::Line2),
+    &builtin::Red(Neighborhood::Diagonal),
+    &builtin::Blue(Neighborhood::Orthogonal),
+    &builtin::Blue(Neighborhood::Knight),
+    &builtin::Blue(Neighborhood::Line2),
+    &builtin::Blue(Neighborhood::Diagonal),
+    &builtin::Purple(Neighborhood::Orthogonal),
+    &builtin::Purple(Neighborhood::Knight),
+    &builtin::Purple(Neighborhood::Line2),
+    &builtin::Purple(Neighborhood::Diagonal),
+    &builtin::Orange(Neighborhood::Orthogonal),
+    &builtin::Orange(Neighborhood::Knight),
+    &builtin::Orange(Neighborhood::Line2),
+    &builtin::Orange(Neighborhood::Diagonal),
+];
+
+/// 计数链接在绿/黄格之后、蓝格之前；奇偶联立接在紫/橙格之后。
+const COUNT_CHAIN_RANK: u8 = 25;
+const PARITY_RANK: u8 = 65;
+
+/// 全部颜色规则（登记顺序）。
+pub(crate) fn registry() -> &'static [&'static dyn CellRule] {
+    &REGISTRY
+}
+
+/// 颜色对应的规则；白格没有规则。
+pub(crate) fn rule_for(color: Color) -> Option<&'static dyn CellRule> {
+    REGISTRY.iter().copied().find(|rule| rule.color() == color)
+}
+
+/// 生成器会出的线索颜色，每个规则族一项（按颜色编码排序，不含变体格），供随机扰动。
+///
+/// 数字格只列 `Number(0)` 作为代表，N 由生成器按目标解取，免得九个数字挤占其它颜色的概率。
+pub(crate) fn clue_colors() -> Vec<Color> {
+    (0..COLOR_COUNT as u8)
+        .filter_map(Color::from_u8)
+        .filter(|&color| rule_for(color).is_some_and(|rule| !rule.is_variant()))
+        .filter(|color| !matches!(color, Color::Number(n) if *n > 0))
+        .collect()
+}
+
+/// 传播到不动点时每一轮依次执行的阶段：颜色规则与组合推理按先后排序，胜利条件最后。
+pub(super) fn propagation_stages() -> Vec<RuleType> {
+    let mut ranked: Vec<(u8, RuleType)> = REGISTRY
+        .iter()
+        .filter_map(|rule| Some((rule.propagation_rank()?, RuleType::Cell(rule.color()))))
+        .collect();
+    ranked.push((COUNT_CHAIN_RANK, RuleType::CountChain));
+    ranked.push((PARITY_RANK, RuleType::Parity));
+    ranked.sort_by_key(|&(rank, _)| rank);
+    ranked
+        .into_iter()
+        .map(|(_, stage)| stage)
+        .chain([RuleType::FiveInRow])
+        .collect()
+}
+
+/// 规则传播时对棋盘的读写接口。
+pub(crate) trait Deduce {
+    fn state(&self) -> &SolverState;
+
+    /// 把 `cell` 定为勾选/不勾选（已是该值时什么也不做）；返回 false 表示矛盾。
+    fn assign(&mut self, cell: usize, checked: bool) -> bool;
+
+    /// 报告这一步所用的细分技巧（见 `SolveObserver::on_technique`）。
+    fn technique(&mut self, technique: Technique);
+
+    /// 把 `cells` 中的未知格全部定为同一值。
+    fn fill_cells(&mut self, cells: &[usize], checked: bool) -> bool {
+        for &id in cells {
+            if self.state().is_unknown_id(id) && !self.assign(id, checked) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// 把第 `row` 行的未知格全部定为同一值。
+    fn fill_row(&mut self, row: usize, checked: bool) -> bool {
+        let size = self.state().size();
+        let mut mask = self.state().unknown_cols_mask_in_row(row);
+        while mask != 0 {
+            let col = mask.trailing_zeros() as usize;
+            mask &= mask - 1;
+            if !self.assign(cell_id(row, col, size), checked) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// 把第 `col` 列的未知格全部定为同一值。
+    fn fill_col(&mut self, col: usize, checked: bool) -> bool {
+        let size = self.state().size();
+        let mut mask = self.state().unknown_rows_mask_in_col(col);
+        while mask != 0 {
+            let row = mask.trailing_zeros() as usize;
+            mask &= mask - 1;
+            if !self.assign(cell_id(row, col, size), checked) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// 完整勾选方案（玩家提交的盘面、生成器的目标解）上的计数视图。
+pub(crate) struct Filled<'a> {
+    size: usize,
+    masks: &'a BoardMasks,
+    checked: Mask,
+    row_counts: Vec<u8>,
+    col_counts: Vec<u8>,
+    diag_down_counts: Vec<u8>,
+    diag_up_counts: Vec<u8>,
+}
+
+impl<'a> Filled<'a> {
+    pub(crate) fn new(size: usize, masks: &'a BoardMasks, checked: Mask) -> Self {
+        let count = |lines: &[Mask]| -> Vec<u8> {
+            lines
+                .iter()
+                .map(|&m| (checked & m).count_ones() as u8)
+                .collect()
+        };
+        Self {
+            size,
+            masks,
+            checked,
+            row_counts: count(&masks.row_masks),
+            col_counts: count(&masks.col_masks),
+            diag_down_counts: count(&masks.diag_down_masks),
+            diag_up_counts: count(&masks.diag_up_masks),
+        }
+    }
+
+    fn is_checked(&self, cell: usize) -> bool {
+        self.checked.contains(cell)
+    }
+
+    /// 邻域 `hood` 中的勾选数。
+    fn neighbors(&self, hood: Neighborhood, cell: usize) -> u32 {
+        (self.checked & self.masks.neighbors(hood, cell)).count_ones()
+    }
+
+    /// 所在行、所在列的勾选数。
+    fn row_col(&self, cell: usize) -> (u8, u8) {
+        let (row, col) = (cell / self.size, cell % self.size);
+        (self.row_counts[row], self.col_counts[col])
+    }
+
+    /// 所在两条对角线（左上-右下、右上-左下）的勾选数。
+    fn diagonals(&self, cell: usize) -> (u8, u8) {
+        let (row, col) = (cell / self.size, cell % self.size);
+        (
+            self.diag_down_counts[row + (self.size - 1) - col],
+            self.diag_up_counts[row + col],
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::colors::{CountColor, MAX_NUMBER, VARIANT_HOODS};
+    use crate::validate::validate_colors;
+    use crate::win::WinCondition;
+
+    #[test]
+    fn registry_covers_every_clue_color_once() {
+        for code in 0..COLOR_COUNT as u8 {
+            let color = Color::from_u8(code).unwrap();
+            assert_eq!(color.to_u8(), code);
+            let rules = REGISTRY.iter().filter(|r| r.color() == color).count();
+            assert_eq!(rules, usize::from(color != Color::White), "{color:?}");
+        }
+        // 数字格 0~8 在扰动候选里合为一项。
+        let generated = REGISTRY.iter().filter(|r| !r.is_variant()).count();
+        assert_eq!(clue_colors().len(), generated - MAX_NUMBER as usize);
+        for rule in registry() {
+            if rule.is_variant() {
+                assert_eq!(rule.title().key, format!("rule.{}.hood", rule.id()));
+                assert_eq!(rule.short_name().key, "rule.short.variant");
+            } else {
+                assert_eq!(rule.title().key, format!("rule.{}", rule.id()));
+                assert_eq!(rule.short_name().key, format!("rule.short.{}", rule.id()));
+            }
+            // 传播的规则都归到某个技巧上，固定勾选的规则既不传播也不参与决策。
+            assert_eq!(rule.propagation_rank().is_some(), !rule.fixes_checked());
+            assert_eq!(rule.technique().is_some(), !rule.fixes_checked());
+        }
+    }
+
+    #[test]
+    fn stages_keep_the_propagation_order() {
+        use Color::*;
+        // 变体格紧跟在同色的八邻规则之后。
+        let with_variants = |base: CountColor| {
+            [Neighborhood::King]
+                .into_iter()
+                .chain(VARIANT_HOODS)
+                .map(move |hood| RuleType::Cell(Color::counted(base, hood)))
+        };
+        let mut expected = vec![
+            RuleType::Cell(Green),
+            RuleType::Cell(Yellow),
+            RuleType::CountChain,
+        ];
+        expected.extend(with_variants(CountColor::Blue));
+        expected.extend(with_variants(CountColor::Red));
+        expected.extend((0..=MAX_NUMBER).map(|n| RuleType::Cell(Number(n))));
+        expected.extend(with_variants(CountColor::Purple));
+        expected.extend(with_variants(CountColor::Orange));
+        expected.extend([RuleType::Parity, RuleType::Cell(Cyan), RuleType::FiveInRow]);
+        assert_eq!(propagation_stages(), expected);
+    }
+
+    #[test]
+    fn numbered_clue_fills_or_clears_its_neighbours() {
+        use crate::solver::Solver;
+
+        let mut colors = vec![Color::White; 9];
+        colors[4] = Color::Number(3);
+        let solver = Solver::new(3, colors, WinCondition::default());
+        let stage = RuleType::Cell(Color::Number(3));
+
+        // 已勾满 3 格：其余邻居不勾选。
+        let mut state = SolverState::new(3);
+        for cell in [0, 1, 2] {
+            state.set_checked_id(cell).unwrap();
+        }
+        assert!(solver.propagate_stage(stage, &mut state, &mut ()));
+        assert!([3, 5, 6, 7, 8].iter().all(|&c| state.is_unchecked_id(c)));
+
+        // 只剩 3 个未知邻居：全部勾选。
+        let mut state = SolverState::new(3);
+        for cell in [0, 1, 2, 3, 5] {
+            state.set_unchecked_id(cell).unwrap();
+        }
+        assert!(solver.propagate_stage(stage, &mut state, &mut ()));
+        assert!([6, 7, 8].iter().all(|&c| state.is_checked_id(c)));
+
+        // 勾选超过 3 格：矛盾。
+        let mut state = SolverState::new(3);
+        for cell in [0, 1, 2, 3] {
+            state.set_checked_id(cell).unwrap();
+        }
+        assert!(!solver.propagate_stage(stage, &mut state, &mut ()));
+    }
+
+    #[test]
+    fn variant_clues_count_over_their_neighbourhood() {
+        use crate::solver::Solver;
+
+        // 3x3 角上的马步红格：马步格只有 (1,2)、(2,1)。
+        let red = Color::Variant(CountColor::Red, Neighborhood::Knight);
+        let mut colors = vec![Color::White; 9];
+        colors[0] = red;
+        let solver = Solver::new(3, colors.clone(), WinCondition::default());
+        let mut state = SolverState::new(3);
+        state.set_unchecked_id(5).unwrap();
+        // 八邻 1、3、4 都勾不勾都行，只有马步格算数。
+        assert!(solver.propagate_stage(RuleType::Cell(red), &mut state, &mut ()));
+        assert!(state.is_checked_id(7));
+        assert!([1, 3, 4].iter().all(|&c| state.is_unknown_id(c)));
+
+        let checked = |cells: &[usize]| cells.iter().fold(Mask::EMPTY, |m, &c| m | Mask::bit(c));
+        let result = validate_colors(checked(&[1, 3, 4]), 3, &colors, WinCondition::default());
+        assert!(!result.cell_ok[0]);
+        let result = validate_colors(checked(&[7]), 3, &colors, WinCondition::default());
+        assert!(result.cell_ok[0]);
+
+        // 斜向紫格：中心格只数四个角。
+        let mut colors = vec![Color::White; 9];
+        colors[4] = Color::Variant(CountColor::Purple, Neighborhood::Diagonal);
+        let result = validate_colors(checked(&[1, 3, 5, 0]), 3, &colors, WinCondition::default());
+        assert!(result.cell_ok[4]);
+        let result = validate_colors(checked(&[0, 8]), 3, &colors, WinCondition::default());
+        assert!(!result.cell_ok[4]);
+    }
+
+    #[test]
+    fn generator_fit_agrees_with_validation() {
+        let size = 5;
+        let masks = BoardMasks::new(size);
+        let checked = [0, 1, 2, 3, 4, 6, 12, 18, 24]
+            .into_iter()
+            .fold(Mask::EMPTY, |m, c| m | Mask::bit(c));
+        let board = Filled::new(size, &masks, checked);
+        for rule in registry() {
+            let colors = vec![rule.color(); size * size];
+            let result = validate_colors(checked, size, &colors, WinCondition::default());
+            for cell in 0..size * size {
+                assert_eq!(
+                    rule.fits(&board, cell),
+                    result.cell_ok[cell],
+                    "{:?}@{cell}",
+                    rule.color()
+                );
+            }
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use crate::masks::{Mask, MAX_CELLS};
+
 /// 求解器状态（<=64x64）。
 ///
 /// 设计要点：
@@ -45,7 +47,7 @@ impl SolverState {
         // - down: row - col + (size-1)
         // - up:   row + col
         for id in 0..diag_count {
-            let dist = (id as i32 - (size as i32 - 1)).abs() as usize;
+            let dist = id.abs_diff(size - 1);
             let len = (size - dist) as u8;
             diag_down_len[id] = len;
             diag_up_len[id] = len;
@@ -262,31 +264,30 @@ impl SolverState {
         h = h.wrapping_mul(1099511628211);
 
         for &v in &self.pos_rows {
-            h ^= v as u64;
+            h ^= v;
             h = h.wrapping_mul(1099511628211);
         }
         for &v in &self.neg_rows {
-            h ^= v as u64;
+            h ^= v;
             h = h.wrapping_mul(1099511628211);
         }
         h
     }
 
-    /// 仅用于 size*size <= 64 将状态转为 row-major 的 u64 mask。
-    pub fn to_row_major_mask(&self) -> u64 {
+    /// 将“确定勾选”的格子转为 row-major 的棋盘 mask（要求 size*size <= MAX_CELLS）。
+    pub fn to_row_major_mask(&self) -> Mask {
         assert!(
-            self.size * self.size <= 64,
-            "to_row_major_mask 仅支持 size*size<=64"
+            self.size * self.size <= MAX_CELLS,
+            "to_row_major_mask 仅支持 size*size<={MAX_CELLS}"
         );
 
-        let mut out = 0u64;
+        let mut out = Mask::EMPTY;
         for row in 0..self.size {
-            let bits = self.pos_rows[row] & self.valid_mask();
-            for col in 0..self.size {
-                if (bits & (1u64 << col)) != 0 {
-                    let idx = row * self.size + col;
-                    out |= 1u64 << idx;
-                }
+            let mut bits = self.pos_rows[row] & self.valid_mask();
+            while bits != 0 {
+                let col = bits.trailing_zeros() as usize;
+                bits &= bits - 1;
+                out.insert(row * self.size + col);
             }
         }
         out
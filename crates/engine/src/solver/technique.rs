@@ -0,0 +1,294 @@
+//! 人类解题技巧目录。
+//!
+//! 传播按 `RuleType` 组织，但玩家学的是有名字的技巧：同一条规则可能对应不同的推理
+//! （如青格的“最后一个邻居”与“孤立排除”），紫/橙格联立又按参与的约束数分成“奇偶对”与“奇偶链”。
+//! 每一步推导在观察者里按“当前规则 + 规则给出的细分”归到一个技巧上（见 `SolveObserver::on_technique`），
+//! 难度分析据此统计技巧直方图与所需的最难技巧，提示则报告所用技巧的 id。
+
+use serde::Serialize;
+
+use super::{rule_for, RuleType};
+use crate::i18n::{msg, Locale, Localize, Message};
+
+/// 难度档位（由易到难）。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TechniqueTier {
+    /// 只看一个线索格及其邻居。
+    Basic,
+    /// 需要整行/整列/对角线或全部连线的计数。
+    Intermediate,
+    /// 需要把两条以上的约束联立。
+    Advanced,
+    /// 需要假设并推出矛盾，或多条约束的长链联立。
+    Expert,
+}
+
+/// 一种有名字的推理技巧；序列化为稳定的 snake_case id。
+///
+/// 声明顺序即目录顺序：先按档位、再按常见程度排列。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Technique {
+    /// 红格八邻没有勾选、只剩一个未知：它必须勾选。
+    RedLastNeighbour,
+    /// 蓝格八邻已有两个勾选：其余邻居全部不勾选。
+    BlueSaturation,
+    /// 已勾选的青格四邻没有勾选、只剩一个未知：它必须勾选。
+    CyanLastNeighbour,
+    /// 青格四邻全部不勾选：青格自身不能勾选。
+    CyanIsolated,
+    /// 数字格八邻已勾满 N 格（其余不勾选），或未知邻居恰好补足 N 格（全部勾选）。
+    NumberCount,
+    /// 紫格八邻只剩一个未知：按奇数补齐。
+    PurpleLastNeighbour,
+    /// 橙格八邻只剩一个未知：按偶数补齐。
+    OrangeLastNeighbour,
+    /// 绿格所在行列的可行勾选数范围卡在边界上。
+    GreenSqueeze,
+    /// 黄格两条对角线的可行勾选数范围卡在边界上。
+    YellowSqueeze,
+    /// 仍可能完成的连线都经过同一格：它必须勾选。
+    BingoCommonCells,
+    /// 绿/黄格把多条线串成计数相等链，取范围交集。
+    CountChain,
+    /// 两条紫/橙约束相加，公共邻格抵消后只剩一格。
+    ParityPair,
+    /// 三条以上紫/橙约束联立消元。
+    ParityChain,
+    /// 假设某格取一值，传播出矛盾，因此取另一值。
+    SingleAssumptionContradiction,
+}
+
+impl Technique {
+    /// 目录中的全部技巧（目录顺序）。
+    pub const ALL: [Technique; 14] = [
+        Technique::RedLastNeighbour,
+        Technique::BlueSaturation,
+        Technique::CyanLastNeighbour,
+        Technique::CyanIsolated,
+        Technique::NumberCount,
+        Technique::PurpleLastNeighbour,
+        Technique::OrangeLastNeighbour,
+        Technique::GreenSqueeze,
+        Technique::YellowSqueeze,
+        Technique::BingoCommonCells,
+        Technique::CountChain,
+        Technique::ParityPair,
+        Technique::ParityChain,
+        Technique::SingleAssumptionContradiction,
+    ];
+
+    /// 稳定的 id（与序列化结果一致）。
+    pub fn id(self) -> &'static str {
+        match self {
+            Technique::RedLastNeighbour => "red_last_neighbour",
+            Technique::BlueSaturation => "blue_saturation",
+            Technique::CyanLastNeighbour => "cyan_last_neighbour",
+            Technique::CyanIsolated => "cyan_isolated",
+            Technique::NumberCount => "number_count",
+            Technique::PurpleLastNeighbour => "purple_last_neighbour",
+            Technique::OrangeLastNeighbour => "orange_last_neighbour",
+            Technique::GreenSqueeze => "green_squeeze",
+            Technique::YellowSqueeze => "yellow_squeeze",
+            Technique::BingoCommonCells => "bingo_common_cells",
+            Technique::CountChain => "count_chain",
+            Technique::ParityPair => "parity_pair",
+            Technique::ParityChain => "parity_chain",
+            Technique::SingleAssumptionContradiction => "single_assumption_contradiction",
+        }
+    }
+
+    pub fn tier(self) -> TechniqueTier {
+        match self {
+            Technique::RedLastNeighbour
+            | Technique::BlueSaturation
+            | Technique::CyanLastNeighbour
+            | Technique::CyanIsolated
+            | Technique::NumberCount => TechniqueTier::Basic,
+            Technique::PurpleLastNeighbour
+            | Technique::OrangeLastNeighbour
+            | Technique::GreenSqueeze
+            | Technique::YellowSqueeze
+            | Technique::BingoCommonCells => TechniqueTier::Intermediate,
+            Technique::CountChain | Technique::ParityPair => TechniqueTier::Advanced,
+            Technique::ParityChain | Technique::SingleAssumptionContradiction => {
+                TechniqueTier::Expert
+            }
+        }
+    }
+
+    /// 技巧名称（消息键 `technique.<id>`）。
+    pub fn title(self) -> Message {
+        match self {
+            Technique::RedLastNeighbour => msg!("technique.red_last_neighbour"),
+            Technique::BlueSaturation => msg!("technique.blue_saturation"),
+            Technique::CyanLastNeighbour => msg!("technique.cyan_last_neighbour"),
+            Technique::CyanIsolated => msg!("technique.cyan_isolated"),
+            Technique::NumberCount => msg!("technique.number_count"),
+            Technique::PurpleLastNeighbour => msg!("technique.purple_last_neighbour"),
+            Technique::OrangeLastNeighbour => msg!("technique.orange_last_neighbour"),
+            Technique::GreenSqueeze => msg!("technique.green_squeeze"),
+            Technique::YellowSqueeze => msg!("technique.yellow_squeeze"),
+            Technique::BingoCommonCells => msg!("technique.bingo_common_cells"),
+            Technique::CountChain => msg!("technique.count_chain"),
+            Technique::ParityPair => msg!("technique.parity_pair"),
+            Technique::ParityChain => msg!("technique.parity_chain"),
+            Technique::SingleAssumptionContradiction => {
+                msg!("technique.single_assumption_contradiction")
+            }
+        }
+    }
+
+    /// 一句话说明（消息键 `technique.<id>.desc`）。
+    pub fn description(self) -> Message {
+        match self {
+            Technique::RedLastNeighbour => msg!("technique.red_last_neighbour.desc"),
+            Technique::BlueSaturation => msg!("technique.blue_saturation.desc"),
+            Technique::CyanLastNeighbour => msg!("technique.cyan_last_neighbour.desc"),
+            Technique::CyanIsolated => msg!("technique.cyan_isolated.desc"),
+            Technique::NumberCount => msg!("technique.number_count.desc"),
+            Technique::PurpleLastNeighbour => msg!("technique.purple_last_neighbour.desc"),
+            Technique::OrangeLastNeighbour => msg!("technique.orange_last_neighbour.desc"),
+            Technique::GreenSqueeze => msg!("technique.green_squeeze.desc"),
+            Technique::YellowSqueeze => msg!("technique.yellow_squeeze.desc"),
+            Technique::BingoCommonCells => msg!("technique.bingo_common_cells.desc"),
+            Technique::CountChain => msg!("technique.count_chain.desc"),
+            Technique::ParityPair => msg!("technique.parity_pair.desc"),
+            Technique::ParityChain => msg!("technique.parity_chain.desc"),
+            Technique::SingleAssumptionContradiction => {
+                msg!("technique.single_assumption_contradiction.desc")
+            }
+        }
+    }
+
+    /// 规则在没有进一步细分时对应的技巧（青格孤立、奇偶链由规则显式给出）。
+    pub(super) fn of_rule(rule: RuleType) -> Option<Technique> {
+        match rule {
+            RuleType::Cell(color) => rule_for(color)?.technique(),
+            RuleType::Parity => Some(Technique::ParityPair),
+            RuleType::CountChain => Some(Technique::CountChain),
+            RuleType::FiveInRow => Some(Technique::BingoCommonCells),
+        }
+    }
+
+    /// 紫/橙格联立按参与组合的约束数细分。
+    pub(super) fn of_parity(sources: usize) -> Technique {
+        if sources <= 2 {
+            Technique::ParityPair
+        } else {
+            Technique::ParityChain
+        }
+    }
+}
+
+/// 技巧目录中的一项（供前端展示技巧说明）。
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TechniqueInfo {
+    pub id: Technique,
+    pub tier: TechniqueTier,
+    pub title: String,
+    /// `title` 的消息键与参数（见 `i18n`）。
+    pub title_key: Message,
+    pub description: String,
+    /// `description` 的消息键与参数（见 `i18n`）。
+    pub description_key: Message,
+}
+
+impl Localize for TechniqueInfo {
+    fn localize(&mut self, locale: Locale) {
+        self.title = self.title_key.render(locale);
+        self.description = self.description_key.render(locale);
+    }
+}
+
+/// 完整的技巧目录（目录顺序，文字为 zh-CN）。
+pub fn technique_catalog() -> Vec<TechniqueInfo> {
+    Technique::ALL
+        .iter()
+        .map(|&t| TechniqueInfo {
+            id: t,
+            tier: t.tier(),
+            title: t.title().zh(),
+            title_key: t.title(),
+            description: t.description().zh(),
+            description_key: t.description(),
+        })
+        .collect()
+}
+
+/// 技巧的使用次数（难度报告中的直方图一项）。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TechniqueCount {
+    pub technique: Technique,
+    pub tier: TechniqueTier,
+    pub count: u32,
+}
+
+/// 把计数表整理成目录顺序的直方图（略去未用到的技巧）。
+pub(crate) fn technique_histogram(
+    counts: &std::collections::HashMap<Technique, u64>,
+) -> Vec<TechniqueCount> {
+    Technique::ALL
+        .iter()
+        .filter_map(|&t| {
+            let count = counts.get(&t).copied().filter(|&c| c > 0)?;
+            Some(TechniqueCount {
+                technique: t,
+                tier: t.tier(),
+                count: count.min(u32::MAX as u64) as u32,
+            })
+        })
+        .collect()
+}
+
+/// 所需的最难技巧：档位最高者，同档取目录中靠后的一个。
+pub(crate) fn hardest_technique(histogram: &[TechniqueCount]) -> Option<Technique> {
+    histogram
+        .iter()
+        .map(|c| c.technique)
+        .max_by_key(|&t| (t.tier(), t))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn catalog_is_ordered_by_tier_with_stable_ids() {
+        let catalog = technique_catalog();
+        assert_eq!(catalog.len(), Technique::ALL.len());
+        for pair in catalog.windows(2) {
+            assert!(pair[0].tier <= pair[1].tier, "{:?}", pair[1].id);
+        }
+        for info in &catalog {
+            assert_eq!(serde_json::to_value(info.id).unwrap(), info.id.id());
+            assert_eq!(info.title_key.key, format!("technique.{}", info.id.id()));
+        }
+    }
+
+    #[test]
+    fn histogram_follows_catalog_order_and_picks_the_hardest() {
+        let counts = [
+            (Technique::ParityPair, 2),
+            (Technique::RedLastNeighbour, 5),
+            (Technique::CountChain, 1),
+            (Technique::BlueSaturation, 0),
+        ]
+        .into_iter()
+        .collect();
+        let histogram = technique_histogram(&counts);
+        let order: Vec<Technique> = histogram.iter().map(|c| c.technique).collect();
+        assert_eq!(
+            order,
+            [
+                Technique::RedLastNeighbour,
+                Technique::CountChain,
+                Technique::ParityPair
+            ]
+        );
+        assert_eq!(hardest_technique(&histogram), Some(Technique::ParityPair));
+        assert_eq!(hardest_technique(&[]), None);
+    }
+}
@@ -0,0 +1,35 @@
+use serde::Serialize;
+
+use crate::colors::parse_grid;
+use crate::error::EngineError;
+use crate::solver::{conflicting_clues, BudgetStop, ConflictingClue, SolveBudget};
+use crate::win::WinCondition;
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct UnsolvableReport {
+    /// 题目可解（此时 `clues` 为空）。
+    pub solvable: bool,
+    /// 互相冲突的极小线索集合（row-major 递增）：把其中任意一条涂白，其余线索便不再矛盾。
+    ///
+    /// `solvable=false` 而集合为空表示胜利条件在该尺寸下无法达成。
+    pub clues: Vec<ConflictingClue>,
+    /// 预算耗尽的原因；收缩中途耗尽时 `clues` 仍然无解但不保证极小。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exhausted: Option<BudgetStop>,
+}
+
+/// 解释自定义题目为何无解：求互相冲突的极小线索集合（逐个把线索涂白做定向求解）。
+pub fn explain_unsolvable(
+    color_grid: &[u8],
+    win: WinCondition,
+    budget: &mut SolveBudget,
+) -> Result<UnsolvableReport, EngineError> {
+    let (size, colors) = parse_grid(color_grid)?;
+
+    let res = conflicting_clues(size, &colors, win, budget);
+    Ok(UnsolvableReport {
+        solvable: res.value.is_none() && res.exhausted.is_none(),
+        clues: res.value.unwrap_or_default(),
+        exhausted: res.exhausted,
+    })
+}
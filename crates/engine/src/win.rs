@@ -0,0 +1,231 @@
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::{msg, Message};
+use crate::masks::{cell_index, Mask};
+
+/// 胜利条件（Bingo）：除颜色规则外，解还必须包含至少一条“连线”。
+///
+/// 校验、求解、生成与难度分析都通过 [`WinCondition::segments`] 枚举连线，
+/// 保证各模块对“什么是解”的判断一致。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum WinCondition {
+    /// 任意一整行或一整列全部勾选；`diagonals` 时两条主对角线也算。
+    FullLine { diagonals: bool },
+    /// 任意方向上连续 `length` 个格子全部勾选；`diagonals` 时含两个斜向。
+    InARow { length: usize, diagonals: bool },
+}
+
+impl Default for WinCondition {
+    /// 默认：五连线（横/竖/斜）。5x5 上与“整行/整列/主对角线”等价。
+    fn default() -> Self {
+        Self::InARow {
+            length: 5,
+            diagonals: true,
+        }
+    }
+}
+
+impl WinCondition {
+    /// 连线长度（`FullLine` 即边长）。
+    pub fn length(&self, size: usize) -> usize {
+        match *self {
+            Self::FullLine { .. } => size,
+            Self::InARow { length, .. } => length,
+        }
+    }
+
+    /// 用于提示文案的名称，如“五连线”。
+    pub fn title(&self, size: usize) -> Message {
+        match *self {
+            Self::FullLine { .. } if size == 5 => msg!("win.five"),
+            Self::FullLine { .. } => msg!("win.full_line"),
+            Self::InARow { length: 5, .. } => msg!("win.five"),
+            Self::InARow { length, .. } => msg!("win.in_a_row", length = length),
+        }
+    }
+
+    /// 枚举 `size x size` 棋盘上的全部候选连线（格子 id 按连线方向排列）。
+    ///
+    /// 顺序固定为：横向（逐行）-> 纵向（逐列）-> `\` 方向 -> `/` 方向；
+    /// 生成器依赖该顺序保证同 seed 出同一题，不要随意调整。
+    pub fn segments(&self, size: usize) -> Vec<Vec<usize>> {
+        let mut out = Vec::new();
+        let len = self.length(size);
+        if len == 0 || len > size {
+            return out;
+        }
+        let diagonals = match *self {
+            Self::FullLine { diagonals } | Self::InARow { diagonals, .. } => diagonals,
+        };
+
+        // 横向
+        for row in 0..size {
+            for start_col in 0..=size - len {
+                out.push(
+                    (0..len)
+                        .map(|d| cell_index(row, start_col + d, size))
+                        .collect(),
+                );
+            }
+        }
+
+        // 纵向
+        for col in 0..size {
+            for start_row in 0..=size - len {
+                out.push(
+                    (0..len)
+                        .map(|d| cell_index(start_row + d, col, size))
+                        .collect(),
+                );
+            }
+        }
+
+        if diagonals {
+            // 主对角线方向（\）
+            for start_row in 0..=size - len {
+                for start_col in 0..=size - len {
+                    out.push(
+                        (0..len)
+                            .map(|d| cell_index(start_row + d, start_col + d, size))
+                            .collect(),
+                    );
+                }
+            }
+
+            // 副对角线方向（/）
+            for start_row in 0..=size - len {
+                for start_col in (len - 1)..size {
+                    out.push(
+                        (0..len)
+                            .map(|d| cell_index(start_row + d, start_col - d, size))
+                            .collect(),
+                    );
+                }
+            }
+        }
+
+        out
+    }
+
+    /// 承载候选连线的整条直线（行、列、对角线，只保留长度不小于连线长度的）。
+    ///
+    /// 每条直线的格子按 row-major 递增排列；[`WinCondition::segments`] 恰好是这些直线上
+    /// 长度为 `length` 的全部连续窗口（只是排列顺序不同）。
+    pub fn lines(&self, size: usize) -> Vec<Vec<usize>> {
+        let mut out = Vec::new();
+        let len = self.length(size);
+        if len == 0 || len > size {
+            return out;
+        }
+        let diagonals = match *self {
+            Self::FullLine { diagonals } | Self::InARow { diagonals, .. } => diagonals,
+        };
+
+        for row in 0..size {
+            out.push((0..size).map(|col| cell_index(row, col, size)).collect());
+        }
+        for col in 0..size {
+            out.push((0..size).map(|row| cell_index(row, col, size)).collect());
+        }
+
+        if diagonals {
+            // `\` 方向：起点在第 0 行或第 0 列
+            for start in (0..size).rev().map(|r| (r, 0)).chain((1..size).map(|c| (0, c))) {
+                let n = size - start.0.max(start.1);
+                if n >= len {
+                    out.push((0..n).map(|d| cell_index(start.0 + d, start.1 + d, size)).collect());
+                }
+            }
+            // `/` 方向：起点在第 0 行或最后一列
+            for start in (0..size).map(|c| (0, c)).chain((1..size).map(|r| (r, size - 1))) {
+                let n = (size - start.0).min(start.1 + 1);
+                if n >= len {
+                    out.push((0..n).map(|d| cell_index(start.0 + d, start.1 - d, size)).collect());
+                }
+            }
+        }
+
+        out
+    }
+
+    /// 与 [`WinCondition::segments`] 同序的连线 mask。
+    pub fn segment_masks(&self, size: usize) -> Vec<Mask> {
+        self.segments(size)
+            .into_iter()
+            .map(|cells| {
+                let mut m = Mask::EMPTY;
+                for id in cells {
+                    m.insert(id);
+                }
+                m
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::masks::BoardMasks;
+
+    #[test]
+    fn default_matches_full_lines_on_5x5() {
+        // 生成器依赖连线顺序：5x5 上默认五连线必须是“5 行 -> 5 列 -> 主对角线 -> 副对角线”。
+        let bm = BoardMasks::new(5);
+        let mut expected = bm.row_masks.clone();
+        expected.extend_from_slice(&bm.col_masks);
+        expected.push(bm.diag_down_masks[4]);
+        expected.push(bm.diag_up_masks[4]);
+
+        assert_eq!(WinCondition::default().segment_masks(5), expected);
+        assert_eq!(
+            WinCondition::FullLine { diagonals: true }.segment_masks(5),
+            expected
+        );
+    }
+
+    #[test]
+    fn segment_counts() {
+        let five = WinCondition::default();
+        // 9x9：横 9*5 + 竖 9*5 + 两个斜向各 5*5
+        assert_eq!(five.segments(9).len(), 45 + 45 + 25 + 25);
+
+        let no_diag = WinCondition::InARow {
+            length: 5,
+            diagonals: false,
+        };
+        assert_eq!(no_diag.segments(9).len(), 90);
+
+        let full = WinCondition::FullLine { diagonals: false };
+        assert_eq!(full.segments(9).len(), 18);
+        assert!(full.segments(9).iter().all(|s| s.len() == 9));
+
+        assert!(five.segments(4).is_empty());
+    }
+
+    #[test]
+    fn segments_are_windows_of_lines() {
+        use std::collections::BTreeSet;
+
+        for win in [
+            WinCondition::default(),
+            WinCondition::InARow {
+                length: 3,
+                diagonals: false,
+            },
+            WinCondition::FullLine { diagonals: true },
+        ] {
+            for size in [3usize, 5, 7] {
+                let len = win.length(size);
+                let from_lines: BTreeSet<Vec<usize>> = win
+                    .lines(size)
+                    .iter()
+                    .flat_map(|line| line.windows(len).map(|w| w.to_vec()).collect::<Vec<_>>())
+                    .collect();
+                let segments: BTreeSet<Vec<usize>> = win.segments(size).into_iter().collect();
+                assert_eq!(from_lines, segments, "win={win:?}, size={size}");
+            }
+        }
+    }
+}